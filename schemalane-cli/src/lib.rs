@@ -2,13 +2,19 @@
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use schemalane_core::{
-    SchemalaneConfig, SchemalaneError, SchemalaneMigrator, format_status_table,
-    init_migration_project, should_fail_on_pending,
+    AdvisoryLock, AppliedMigration, ConfigLayer, ConfigLoader, DEFAULT_BASELINE_DESCRIPTION,
+    FilesystemMigrationSource, MigrationPlan, MigrationRetry, MigrationTarget, OnFailure,
+    RepairReport, RunReport, SchemalaneConfig, SchemalaneError, SchemalaneMigrator, TargetConfig,
+    TargetsConfig, TenantRunner, TenantSelector, TenantStatusRollup, ensure_valid,
+    format_migration_plan, format_status_table, format_tenant_status_table,
+    format_validation_report, init_migration_project, parse_timeout, should_fail_on_pending,
 };
 use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+use std::collections::BTreeMap;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Duration;
 
 const DEFAULT_MIGRATION_DIR: &str = "./migration";
 const DEFAULT_SQL_DIR: &str = "./migrations";
@@ -43,22 +49,22 @@ impl EmbeddedRunner {
     {
         let cli = EmbeddedCli::parse_from(args);
 
-        let db = connect(&cli.database_url).await?;
+        let db = connect(&cli.database_url, cli.tenants.max_connections()).await?;
         let migrations_dir = cli
             .dir
             .clone()
             .unwrap_or_else(|| PathBuf::from(self.migrations_dir));
 
-        let config = SchemalaneConfig {
-            schema: cli.schema,
-            history_table: cli.history_table,
-            migrations_dir,
-            installed_by: cli.installed_by,
-            ..Default::default()
-        };
+        let command = DbCommand::from(cli.command);
+        let mut config = cli.config.into_config(migrations_dir)?;
+        config.group_transactions = command.groups_transactions();
 
-        let migrator = (self.build_migrator)(config);
-        run_db_command(&migrator, &db, cli.command.into()).await
+        // Embedded migrations are used unless `--dir` explicitly asks for disk.
+        let mut migrator = (self.build_migrator)(config);
+        if let Some(dir) = cli.dir {
+            migrator.register_source(FilesystemMigrationSource::new(dir));
+        }
+        cli.tenants.run(&migrator, &db, command).await
     }
 }
 
@@ -110,19 +116,412 @@ struct MigrateArgs {
     #[arg(long, env = "DATABASE_URL")]
     database_url: Option<String>,
 
-    #[arg(long, default_value = "public")]
-    schema: String,
+    #[command(flatten)]
+    config: ConfigArgs,
 
-    #[arg(long, default_value = "flyway_schema_history")]
-    history_table: String,
+    #[command(flatten)]
+    tenants: TenantArgs,
 
-    #[arg(long)]
-    installed_by: Option<String>,
+    /// TOML file listing several databases to run `up`, `status` or
+    /// `validate` against, in order.
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["schema_pattern", "tenants_query"]
+    )]
+    targets: Option<PathBuf>,
+
+    /// Whether to `stop` or `continue` after a target fails; overrides
+    /// `on_failure` in the targets file.
+    #[arg(long, value_name = "stop|continue", requires = "targets")]
+    on_failure: Option<OnFailure>,
 
     #[command(subcommand)]
     command: Option<MigrateCommand>,
 }
 
+/// Flags mapped onto `SchemalaneConfig`, shared by the root and embedded CLIs.
+/// Flags left unset fall back to `SCHEMALANE_*` environment variables, then
+/// the config file, then the defaults.
+#[derive(Debug, Args)]
+struct ConfigArgs {
+    /// Config file; defaults to `$SCHEMALANE_CONFIG`, or `schemalane.toml`
+    /// if it exists.
+    #[arg(long = "config", value_name = "FILE")]
+    config_file: Option<PathBuf>,
+
+    /// `[env.<name>]` profile of the config file to use; defaults to
+    /// `$SCHEMALANE_ENV`.
+    #[arg(long, value_name = "NAME")]
+    env: Option<String>,
+
+    /// Schema holding the history table [default: public].
+    #[arg(long, conflicts_with = "schemas")]
+    schema: Option<String>,
+
+    /// Schemas to manage, comma-separated; the first holds the history table.
+    /// `up` creates missing ones, `fresh` cleans all.
+    #[arg(long, value_name = "SCHEMA,...", value_delimiter = ',')]
+    schemas: Vec<String>,
+
+    /// [default: `flyway_schema_history`]
+    #[arg(long)]
+    history_table: Option<String>,
+
+    #[arg(long)]
+    installed_by: Option<String>,
+
+    /// Apply pending migrations older than the latest applied version;
+    /// `--out-of-order=false` turns off a config file or environment setting.
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    out_of_order: Option<bool>,
+
+    /// Give up after waiting this many seconds for the advisory lock
+    /// (0 fails at once if it is held). Waits forever by default.
+    #[arg(long, value_name = "SECONDS")]
+    lock_timeout: Option<u64>,
+
+    /// Advisory lock key: an integer, or `derived` for the key Flyway takes
+    /// on the schema and history table.
+    #[arg(
+        long,
+        value_name = "ID|derived",
+        allow_negative_numbers = true,
+        conflicts_with = "no_lock"
+    )]
+    lock_id: Option<AdvisoryLock>,
+
+    /// Run without the advisory lock (only safe with a single runner).
+    #[arg(long)]
+    no_lock: bool,
+
+    /// Value for a `${key}` placeholder in SQL migrations; repeatable.
+    #[arg(
+        long = "placeholder",
+        value_name = "KEY=VALUE",
+        value_parser = parse_placeholder
+    )]
+    placeholders: Vec<(String, String)>,
+
+    /// Marks the start of a placeholder; an empty value turns substitution off
+    /// [default: ${].
+    #[arg(long)]
+    placeholder_prefix: Option<String>,
+
+    /// [default: }]
+    #[arg(long)]
+    placeholder_suffix: Option<String>,
+
+    /// Default `lock_timeout` for each migration transaction, e.g. `5s`.
+    #[arg(long, value_name = "DURATION", value_parser = parse_timeout)]
+    migration_lock_timeout: Option<Duration>,
+
+    /// Default `statement_timeout` for each migration transaction, e.g. `2min`.
+    #[arg(long, value_name = "DURATION", value_parser = parse_timeout)]
+    statement_timeout: Option<Duration>,
+
+    /// Retry a transactional migration this many times if it fails on a lock
+    /// (SQLSTATE 55P03) or a deadlock (40P01) [default: 0].
+    #[arg(long, value_name = "N")]
+    retries: Option<u32>,
+
+    /// Wait before the first retry; doubles for each further one
+    /// [default: 1s].
+    #[arg(long, value_name = "DURATION", value_parser = parse_timeout)]
+    retry_backoff: Option<Duration>,
+
+    /// [default: 30s]
+    #[arg(long, value_name = "DURATION", value_parser = parse_timeout)]
+    retry_max_backoff: Option<Duration>,
+}
+
+fn parse_placeholder(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_owned(), value.to_owned())),
+        _ => Err(format!("expected KEY=VALUE, got '{value}'")),
+    }
+}
+
+impl ConfigArgs {
+    /// Defaults, then the config file, then `SCHEMALANE_*` environment
+    /// variables, then these flags.
+    fn into_config(self, migrations_dir: PathBuf) -> Result<SchemalaneConfig, SchemalaneError> {
+        let mut loader = ConfigLoader::from_process_env();
+        if let Some(path) = &self.config_file {
+            loader = loader.with_file(path);
+        }
+        if let Some(profile) = &self.env {
+            loader = loader.with_profile(profile);
+        }
+        self.into_config_with(&loader, migrations_dir)
+    }
+
+    fn into_config_with(
+        self,
+        loader: &ConfigLoader,
+        migrations_dir: PathBuf,
+    ) -> Result<SchemalaneConfig, SchemalaneError> {
+        let layer = loader.layer()?;
+        for key in &layer.ignored_keys {
+            eprintln!("Ignoring flyway.conf setting {key}: Schemalane has no equivalent.");
+        }
+        let mut config = SchemalaneConfig {
+            migrations_dir,
+            ..Default::default()
+        };
+        layer.apply_to(&mut config);
+        self.into_layer().apply_to(&mut config);
+        Ok(config)
+    }
+
+    fn into_layer(self) -> ConfigLayer {
+        ConfigLayer {
+            schema: self.schema,
+            schemas: (!self.schemas.is_empty()).then_some(self.schemas),
+            history_table: self.history_table,
+            installed_by: self.installed_by,
+            advisory_lock: if self.no_lock {
+                Some(AdvisoryLock::Disabled)
+            } else {
+                self.lock_id
+            },
+            lock_timeout: self.lock_timeout.map(Duration::from_secs),
+            migration_lock_timeout: self.migration_lock_timeout,
+            statement_timeout: self.statement_timeout,
+            out_of_order: self.out_of_order,
+            placeholders: self.placeholders.into_iter().collect(),
+            placeholder_prefix: self.placeholder_prefix,
+            placeholder_suffix: self.placeholder_suffix,
+            retries: self.retries,
+            retry_backoff: self.retry_backoff,
+            retry_max_backoff: self.retry_max_backoff,
+            ..Default::default()
+        }
+    }
+
+    /// Forward these flags to a migration crate's embedded CLI.
+    fn push_args(&self, cargo: &mut Command) {
+        if let Some(path) = &self.config_file {
+            cargo.arg("--config").arg(path);
+        }
+
+        if let Some(profile) = &self.env {
+            cargo.arg("--env").arg(profile);
+        }
+
+        if let Some(schema) = &self.schema {
+            cargo.arg("--schema").arg(schema);
+        }
+
+        if let Some(history_table) = &self.history_table {
+            cargo.arg("--history-table").arg(history_table);
+        }
+
+        if !self.schemas.is_empty() {
+            cargo.arg(format!("--schemas={}", self.schemas.join(",")));
+        }
+
+        if let Some(installed_by) = &self.installed_by {
+            cargo.arg("--installed-by").arg(installed_by);
+        }
+
+        if let Some(out_of_order) = self.out_of_order {
+            cargo.arg(format!("--out-of-order={out_of_order}"));
+        }
+
+        if let Some(lock_timeout) = self.lock_timeout {
+            cargo.arg("--lock-timeout").arg(lock_timeout.to_string());
+        }
+
+        if let Some(lock_id) = self.lock_id {
+            cargo.arg(format!("--lock-id={lock_id}"));
+        }
+
+        if self.no_lock {
+            cargo.arg("--no-lock");
+        }
+
+        for (key, value) in &self.placeholders {
+            cargo.arg(format!("--placeholder={key}={value}"));
+        }
+        if let Some(prefix) = &self.placeholder_prefix {
+            cargo.arg(format!("--placeholder-prefix={prefix}"));
+        }
+
+        if let Some(suffix) = &self.placeholder_suffix {
+            cargo.arg(format!("--placeholder-suffix={suffix}"));
+        }
+
+        if let Some(timeout) = self.migration_lock_timeout {
+            cargo
+                .arg("--migration-lock-timeout")
+                .arg(format!("{}ms", timeout.as_millis()));
+        }
+
+        if let Some(timeout) = self.statement_timeout {
+            cargo
+                .arg("--statement-timeout")
+                .arg(format!("{}ms", timeout.as_millis()));
+        }
+
+        if let Some(retries) = self.retries {
+            cargo.arg("--retries").arg(retries.to_string());
+        }
+
+        if let Some(backoff) = self.retry_backoff {
+            cargo
+                .arg("--retry-backoff")
+                .arg(format!("{}ms", backoff.as_millis()));
+        }
+
+        if let Some(backoff) = self.retry_max_backoff {
+            cargo
+                .arg("--retry-max-backoff")
+                .arg(format!("{}ms", backoff.as_millis()));
+        }
+    }
+}
+
+/// Multi-tenant mode: run `up` or `status` once per matching schema.
+#[derive(Debug, Args)]
+struct TenantArgs {
+    /// Migrate every schema whose name matches this SQL `LIKE` pattern,
+    /// e.g. `tenant_%`.
+    #[arg(long, value_name = "PATTERN", conflicts_with = "tenants_query")]
+    schema_pattern: Option<String>,
+
+    /// Migrate every schema named in the first column of this query.
+    #[arg(long, value_name = "SQL")]
+    tenants_query: Option<String>,
+
+    /// How many tenants to migrate at once.
+    #[arg(long, value_name = "N", default_value_t = 4)]
+    tenant_concurrency: usize,
+}
+
+impl TenantArgs {
+    fn selector(&self) -> Option<TenantSelector> {
+        self.schema_pattern
+            .clone()
+            .map(TenantSelector::SchemaPattern)
+            .or_else(|| self.tenants_query.clone().map(TenantSelector::Query))
+    }
+
+    /// Each tenant in flight may hold a connection for its advisory lock
+    /// and one to run migrations on.
+    fn max_connections(&self) -> u32 {
+        if self.selector().is_none() {
+            return 5;
+        }
+        let per_tenant = u32::try_from(self.tenant_concurrency.max(1)).unwrap_or(u32::MAX);
+        per_tenant.saturating_mul(2).saturating_add(1).max(5)
+    }
+
+    /// Run `command` once, or once per tenant in multi-tenant mode.
+    async fn run(
+        &self,
+        migrator: &SchemalaneMigrator,
+        db: &DatabaseConnection,
+        command: DbCommand,
+    ) -> Result<(), SchemalaneError> {
+        let Some(selector) = self.selector() else {
+            return run_db_command(migrator, db, command).await;
+        };
+        let runner = TenantRunner::new(migrator).with_concurrency(self.tenant_concurrency);
+        let tenants = runner.tenants(db, &selector).await?;
+        match command {
+            DbCommand::Up {
+                dry_run: false,
+                target,
+                ..
+            } => tenant_results(
+                runner.up_to(db, &tenants, &target).await,
+                |schema, report| print_tenant_run(schema, report, &target),
+            ),
+            DbCommand::Status {
+                format,
+                fail_on_pending,
+            } => {
+                let reports = runner.status(db, &tenants).await;
+                let rollup = TenantStatusRollup::from_reports(&reports);
+                print_output(
+                    format,
+                    || format_tenant_status_table(&rollup),
+                    || serde_json::to_string_pretty(&rollup),
+                )?;
+                tenant_results(reports, |_, _| {})?;
+                let pending = rollup.tenants.iter().map(|tenant| tenant.pending).sum();
+                if fail_on_pending && pending > 0 {
+                    return Err(SchemalaneError::PendingMigrations(pending));
+                }
+                Ok(())
+            }
+            _ => Err(SchemalaneError::Validation(
+                "--schema-pattern and --tenants-query only support `up` (without --dry-run) \
+                 and `status`"
+                    .to_owned(),
+            )),
+        }
+    }
+
+    fn push_args(&self, cargo: &mut Command) {
+        if let Some(pattern) = &self.schema_pattern {
+            cargo.arg(format!("--schema-pattern={pattern}"));
+        }
+        if let Some(query) = &self.tenants_query {
+            cargo.arg(format!("--tenants-query={query}"));
+        }
+        cargo
+            .arg("--tenant-concurrency")
+            .arg(self.tenant_concurrency.to_string());
+    }
+}
+
+/// Print each tenant's outcome with `print`, then fail with the most severe
+/// error of the failing tenants, if any.
+fn tenant_results<T>(
+    results: BTreeMap<String, Result<T, SchemalaneError>>,
+    print: impl Fn(&str, &T),
+) -> Result<(), SchemalaneError> {
+    let mut errors = Vec::new();
+    for (schema, result) in results {
+        match result {
+            Ok(value) => print(&schema, &value),
+            Err(err) => {
+                eprintln!("[{schema}] {err}");
+                errors.push(err);
+            }
+        }
+    }
+    if !errors.is_empty() {
+        eprintln!("{} tenant(s) failed.", errors.len());
+    }
+    SchemalaneError::most_severe(errors).map_or(Ok(()), Err)
+}
+
+fn print_tenant_run(schema: &str, report: &RunReport, target: &MigrationTarget) {
+    println!(
+        "[{schema}] Applied {} migration(s), skipped {}.",
+        report.applied.len(),
+        report.skipped
+    );
+    print_created_schemas(&report.created_schemas);
+    print_applied(&report.applied);
+    print_retries(&report.retries);
+    if !report.unapplied.is_empty() {
+        println!(
+            "[{schema}] Left {} pending migration(s) beyond target {target}.",
+            report.unapplied.len()
+        );
+    }
+}
+
 #[derive(Debug, Subcommand)]
 enum MigrateCommand {
     Init {
@@ -132,17 +531,60 @@ enum MigrateCommand {
         #[arg(long)]
         force: bool,
     },
-    Up,
+    Up {
+        /// Stop at this version, or `next`, `<n>-steps`, `latest`.
+        #[arg(long, default_value = "latest")]
+        target: MigrationTarget,
+
+        /// Run all pending migrations in one transaction: all or nothing.
+        #[arg(long)]
+        group: bool,
+
+        /// Print the migration plan instead of executing it.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output format for `--dry-run`.
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
     Status {
-        #[arg(long, value_enum, default_value_t = StatusFormat::Table)]
-        format: StatusFormat,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
 
         #[arg(long)]
         fail_on_pending: bool,
     },
+    /// Check migrations against the history table without taking a lock or
+    /// changing anything.
+    Validate {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
     Fresh {
         #[arg(long)]
         yes: bool,
+
+        /// Print the migration plan instead of executing it; needs no `--yes`.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output format for `--dry-run`.
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    Repair {
+        /// Also mark applied migrations that no longer exist locally as deleted.
+        #[arg(long)]
+        mark_missing_deleted: bool,
+    },
+    Baseline {
+        /// Local migrations at or below this version are treated as applied.
+        #[arg(long = "version", default_value = "1")]
+        baseline_version: String,
+
+        #[arg(long, default_value = DEFAULT_BASELINE_DESCRIPTION)]
+        description: String,
     },
 }
 
@@ -151,15 +593,13 @@ struct EmbeddedCli {
     #[arg(long, env = "DATABASE_URL")]
     database_url: String,
 
-    #[arg(long, default_value = "public")]
-    schema: String,
+    #[command(flatten)]
+    config: ConfigArgs,
 
-    #[arg(long, default_value = "flyway_schema_history")]
-    history_table: String,
-
-    #[arg(long)]
-    installed_by: Option<String>,
+    #[command(flatten)]
+    tenants: TenantArgs,
 
+    /// Read migrations from this directory instead of the embedded set.
     #[arg(long)]
     dir: Option<PathBuf>,
 
@@ -169,41 +609,135 @@ struct EmbeddedCli {
 
 #[derive(Debug, Subcommand)]
 enum EmbeddedCommand {
-    Up,
+    Up {
+        /// Stop at this version, or `next`, `<n>-steps`, `latest`.
+        #[arg(long, default_value = "latest")]
+        target: MigrationTarget,
+
+        /// Run all pending migrations in one transaction: all or nothing.
+        #[arg(long)]
+        group: bool,
+
+        /// Print the migration plan instead of executing it.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output format for `--dry-run`.
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
     Status {
-        #[arg(long, value_enum, default_value_t = StatusFormat::Table)]
-        format: StatusFormat,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
 
         #[arg(long)]
         fail_on_pending: bool,
     },
+    /// Check migrations against the history table without taking a lock or
+    /// changing anything.
+    Validate {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
     Fresh {
         #[arg(long)]
         yes: bool,
+
+        /// Print the migration plan instead of executing it; needs no `--yes`.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output format for `--dry-run`.
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    Repair {
+        /// Also mark applied migrations that no longer exist locally as deleted.
+        #[arg(long)]
+        mark_missing_deleted: bool,
+    },
+    Baseline {
+        /// Local migrations at or below this version are treated as applied.
+        #[arg(long = "version", default_value = "1")]
+        baseline_version: String,
+
+        #[arg(long, default_value = DEFAULT_BASELINE_DESCRIPTION)]
+        description: String,
     },
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
-enum StatusFormat {
+enum OutputFormat {
     Table,
     Json,
 }
 
+impl OutputFormat {
+    const fn as_arg(self) -> &'static str {
+        match self {
+            Self::Table => "table",
+            Self::Json => "json",
+        }
+    }
+}
+
+#[derive(Clone)]
 enum DbCommand {
-    Up,
+    Up {
+        target: MigrationTarget,
+        group: bool,
+        dry_run: bool,
+        format: OutputFormat,
+    },
     Status {
-        format: StatusFormat,
+        format: OutputFormat,
         fail_on_pending: bool,
     },
+    Validate {
+        format: OutputFormat,
+    },
     Fresh {
         yes: bool,
+        dry_run: bool,
+        format: OutputFormat,
+    },
+    Repair {
+        mark_missing_deleted: bool,
     },
+    Baseline {
+        baseline_version: String,
+        description: String,
+    },
+}
+
+impl DbCommand {
+    const fn groups_transactions(&self) -> bool {
+        matches!(self, Self::Up { group: true, .. })
+    }
+
+    /// Commands that `--targets` runs against each database.
+    const fn runs_per_target(&self) -> bool {
+        matches!(
+            self,
+            Self::Up { .. } | Self::Status { .. } | Self::Validate { .. }
+        )
+    }
 }
 
 impl From<EmbeddedCommand> for DbCommand {
     fn from(command: EmbeddedCommand) -> Self {
         match command {
-            EmbeddedCommand::Up => Self::Up,
+            EmbeddedCommand::Up {
+                target,
+                group,
+                dry_run,
+                format,
+            } => Self::Up {
+                target,
+                group,
+                dry_run,
+                format,
+            },
             EmbeddedCommand::Status {
                 format,
                 fail_on_pending,
@@ -211,7 +745,76 @@ impl From<EmbeddedCommand> for DbCommand {
                 format,
                 fail_on_pending,
             },
-            EmbeddedCommand::Fresh { yes } => Self::Fresh { yes },
+            EmbeddedCommand::Validate { format } => Self::Validate { format },
+            EmbeddedCommand::Fresh {
+                yes,
+                dry_run,
+                format,
+            } => Self::Fresh {
+                yes,
+                dry_run,
+                format,
+            },
+            EmbeddedCommand::Repair {
+                mark_missing_deleted,
+            } => Self::Repair {
+                mark_missing_deleted,
+            },
+            EmbeddedCommand::Baseline {
+                baseline_version,
+                description,
+            } => Self::Baseline {
+                baseline_version,
+                description,
+            },
+        }
+    }
+}
+
+impl From<MigrateCommand> for DbCommand {
+    fn from(command: MigrateCommand) -> Self {
+        match command {
+            MigrateCommand::Init { .. } => unreachable!("init is handled in outer match"),
+            MigrateCommand::Up {
+                target,
+                group,
+                dry_run,
+                format,
+            } => Self::Up {
+                target,
+                group,
+                dry_run,
+                format,
+            },
+            MigrateCommand::Status {
+                format,
+                fail_on_pending,
+            } => Self::Status {
+                format,
+                fail_on_pending,
+            },
+            MigrateCommand::Validate { format } => Self::Validate { format },
+            MigrateCommand::Fresh {
+                yes,
+                dry_run,
+                format,
+            } => Self::Fresh {
+                yes,
+                dry_run,
+                format,
+            },
+            MigrateCommand::Repair {
+                mark_missing_deleted,
+            } => Self::Repair {
+                mark_missing_deleted,
+            },
+            MigrateCommand::Baseline {
+                baseline_version,
+                description,
+            } => Self::Baseline {
+                baseline_version,
+                description,
+            },
         }
     }
 }
@@ -221,12 +824,18 @@ async fn run_root_cli(cli: Cli) -> Result<(), SchemalaneError> {
     let MigrateArgs {
         migration_dir,
         database_url,
-        schema,
-        history_table,
-        installed_by,
+        config,
+        tenants,
+        targets,
+        on_failure,
         command,
     } = args;
-    let command = command.unwrap_or(MigrateCommand::Up);
+    let command = command.unwrap_or(MigrateCommand::Up {
+        target: MigrationTarget::Latest,
+        group: false,
+        dry_run: false,
+        format: OutputFormat::Table,
+    });
 
     match command {
         MigrateCommand::Init { path, force } => {
@@ -245,14 +854,16 @@ async fn run_root_cli(cli: Cli) -> Result<(), SchemalaneError> {
             Ok(())
         }
         command => {
+            if let Some(targets) = targets {
+                return run_targets(&targets, on_failure, config, command).await;
+            }
             let manifest_path = migration_dir.join("Cargo.toml");
             if manifest_path.is_file() {
                 return run_via_migration_crate(
                     &manifest_path,
                     database_url.as_deref(),
-                    &schema,
-                    &history_table,
-                    installed_by.as_deref(),
+                    &config,
+                    &tenants,
                     &command,
                 );
             }
@@ -270,42 +881,78 @@ async fn run_root_cli(cli: Cli) -> Result<(), SchemalaneError> {
                 )
             })?;
 
-            let db = connect(&database_url).await?;
-
-            let config = SchemalaneConfig {
-                schema,
-                history_table,
-                migrations_dir: PathBuf::from(DEFAULT_SQL_DIR),
-                installed_by,
-                ..Default::default()
-            };
+            let db = connect(&database_url, tenants.max_connections()).await?;
 
+            let command = DbCommand::from(command);
+            let mut config = config.into_config(PathBuf::from(DEFAULT_SQL_DIR))?;
+            config.group_transactions = command.groups_transactions();
             let migrator = SchemalaneMigrator::new(config);
 
-            let db_command = match command {
-                MigrateCommand::Init { .. } => unreachable!("init is handled in outer match"),
-                MigrateCommand::Up => DbCommand::Up,
-                MigrateCommand::Status {
-                    format,
-                    fail_on_pending,
-                } => DbCommand::Status {
-                    format,
-                    fail_on_pending,
-                },
-                MigrateCommand::Fresh { yes } => DbCommand::Fresh { yes },
-            };
+            tenants.run(&migrator, &db, command).await
+        }
+    }
+}
+
+/// Run `command` against every database of a targets file, in order, and
+/// fail with the most severe error if any of them failed.
+async fn run_targets(
+    path: &Path,
+    on_failure: Option<OnFailure>,
+    config: ConfigArgs,
+    command: MigrateCommand,
+) -> Result<(), SchemalaneError> {
+    let targets = TargetsConfig::load(path)?;
+    let on_failure = on_failure.unwrap_or(targets.on_failure);
+    let command = DbCommand::from(command);
+    if !command.runs_per_target() {
+        return Err(SchemalaneError::Validation(
+            "--targets only supports `up`, `status` and `validate`".to_owned(),
+        ));
+    }
+    let mut base = config.into_config(PathBuf::from(DEFAULT_SQL_DIR))?;
+    base.group_transactions = command.groups_transactions();
 
-            run_db_command(&migrator, &db, db_command).await
+    let mut errors = Vec::new();
+    let mut succeeded = 0;
+    for (index, target) in targets.targets.iter().enumerate() {
+        // Progress goes to stderr so stdout holds only each target's
+        // report, e.g. one JSON document per target with `--format json`.
+        eprintln!("== {} ==", target.name);
+        match run_target(target, &base, command.clone()).await {
+            Ok(()) => succeeded += 1,
+            Err(err) => {
+                eprintln!("[{}] {err}", target.name);
+                errors.push(err);
+                if on_failure == OnFailure::Stop {
+                    let skipped = targets.targets.len() - index - 1;
+                    if skipped > 0 {
+                        eprintln!("Stopping; skipped {skipped} remaining target(s).");
+                    }
+                    break;
+                }
+            }
         }
     }
+
+    eprintln!("{succeeded} target(s) succeeded, {} failed.", errors.len());
+    SchemalaneError::most_severe(errors).map_or(Ok(()), Err)
+}
+
+async fn run_target(
+    target: &TargetConfig,
+    base: &SchemalaneConfig,
+    command: DbCommand,
+) -> Result<(), SchemalaneError> {
+    let db = connect(&target.database_url()?, 5).await?;
+    let migrator = SchemalaneMigrator::new(target.apply(base));
+    run_db_command(&migrator, &db, command).await
 }
 
 fn run_via_migration_crate(
     manifest_path: &Path,
     database_url: Option<&str>,
-    schema: &str,
-    history_table: &str,
-    installed_by: Option<&str>,
+    config: &ConfigArgs,
+    tenants: &TenantArgs,
     command: &MigrateCommand,
 ) -> Result<(), SchemalaneError> {
     let mut cargo = Command::new("cargo");
@@ -319,39 +966,69 @@ fn run_via_migration_crate(
         cargo.arg("--database-url").arg(database_url);
     }
 
-    cargo
-        .arg("--schema")
-        .arg(schema)
-        .arg("--history-table")
-        .arg(history_table);
-
-    if let Some(installed_by) = installed_by {
-        cargo.arg("--installed-by").arg(installed_by);
-    }
+    config.push_args(&mut cargo);
+    tenants.push_args(&mut cargo);
 
     match command {
         MigrateCommand::Init { .. } => unreachable!("init is handled in outer match"),
-        MigrateCommand::Up => {
-            cargo.arg("up");
+        MigrateCommand::Up {
+            target,
+            group,
+            dry_run,
+            format,
+        } => {
+            cargo.arg("up").arg("--target").arg(target.to_string());
+            if *group {
+                cargo.arg("--group");
+            }
+            if *dry_run {
+                cargo.arg("--dry-run").arg("--format").arg(format.as_arg());
+            }
         }
         MigrateCommand::Status {
             format,
             fail_on_pending,
         } => {
             cargo.arg("status");
-            cargo.arg("--format").arg(match format {
-                StatusFormat::Table => "table",
-                StatusFormat::Json => "json",
-            });
+            cargo.arg("--format").arg(format.as_arg());
             if *fail_on_pending {
                 cargo.arg("--fail-on-pending");
             }
         }
-        MigrateCommand::Fresh { yes } => {
+        MigrateCommand::Validate { format } => {
+            cargo.arg("validate").arg("--format").arg(format.as_arg());
+        }
+        MigrateCommand::Fresh {
+            yes,
+            dry_run,
+            format,
+        } => {
             cargo.arg("fresh");
             if *yes {
                 cargo.arg("--yes");
             }
+            if *dry_run {
+                cargo.arg("--dry-run").arg("--format").arg(format.as_arg());
+            }
+        }
+        MigrateCommand::Repair {
+            mark_missing_deleted,
+        } => {
+            cargo.arg("repair");
+            if *mark_missing_deleted {
+                cargo.arg("--mark-missing-deleted");
+            }
+        }
+        MigrateCommand::Baseline {
+            baseline_version,
+            description,
+        } => {
+            cargo
+                .arg("baseline")
+                .arg("--version")
+                .arg(baseline_version)
+                .arg("--description")
+                .arg(description);
         }
     }
 
@@ -372,33 +1049,125 @@ fn run_via_migration_crate(
     }
 }
 
-async fn connect(database_url: &str) -> Result<DatabaseConnection, SchemalaneError> {
+async fn connect(
+    database_url: &str,
+    max_connections: u32,
+) -> Result<DatabaseConnection, SchemalaneError> {
     let mut connect_opts = ConnectOptions::new(database_url.to_owned());
-    connect_opts.max_connections(5);
+    connect_opts.max_connections(max_connections);
     connect_opts.min_connections(1);
     Database::connect(connect_opts)
         .await
         .map_err(SchemalaneError::from)
 }
 
+fn print_plan(plan: &MigrationPlan, format: OutputFormat) -> Result<(), SchemalaneError> {
+    print_output(
+        format,
+        || format_migration_plan(plan),
+        || serde_json::to_string_pretty(plan),
+    )
+}
+
+fn print_output(
+    format: OutputFormat,
+    table: impl FnOnce() -> String,
+    json: impl FnOnce() -> serde_json::Result<String>,
+) -> Result<(), SchemalaneError> {
+    match format {
+        OutputFormat::Table => println!("{}", table()),
+        OutputFormat::Json => println!(
+            "{}",
+            json().map_err(|err| {
+                SchemalaneError::Validation(format!("failed to encode JSON: {err}"))
+            })?
+        ),
+    }
+    Ok(())
+}
+
+fn print_applied(applied: &[AppliedMigration]) {
+    for migration in applied {
+        let version = migration
+            .version
+            .as_ref()
+            .map_or_else(|| "R".to_owned(), |version| format!("V{version}"));
+        println!(
+            "- {version} {} ({}) [{} ms]",
+            migration.description, migration.script, migration.execution_time_ms
+        );
+    }
+}
+
+fn print_created_schemas(schemas: &[String]) {
+    if !schemas.is_empty() {
+        println!("Created schema(s): {}.", schemas.join(", "));
+    }
+}
+
+fn print_retries(retries: &[MigrationRetry]) {
+    for retry in retries {
+        println!(
+            "- retried {} after attempt {} failed with SQLSTATE {} [waited {} ms]",
+            retry.script, retry.attempt, retry.sqlstate, retry.backoff_ms
+        );
+    }
+}
+
+fn print_repair_report(report: &RepairReport) {
+    println!(
+        "Repair completed. Removed {} failed row(s), realigned {} row(s), marked {} row(s) as deleted.",
+        report.removed_failed.len(),
+        report.realigned.len(),
+        report.marked_deleted.len()
+    );
+    for (action, entries) in [
+        ("removed failed", &report.removed_failed),
+        ("realigned", &report.realigned),
+        ("marked deleted", &report.marked_deleted),
+    ] {
+        for entry in entries {
+            println!(
+                "- {action}: {} {} ({}) [rank {}]",
+                entry.version.as_deref().unwrap_or("-"),
+                entry.description,
+                entry.script,
+                entry.installed_rank
+            );
+        }
+    }
+}
+
 async fn run_db_command(
     migrator: &SchemalaneMigrator,
     db: &DatabaseConnection,
     command: DbCommand,
 ) -> Result<(), SchemalaneError> {
     match command {
-        DbCommand::Up => {
-            let report = migrator.up(db).await?;
+        DbCommand::Up {
+            target,
+            dry_run: true,
+            format,
+            ..
+        } => print_plan(&migrator.dry_run_up(db, &target).await?, format)?,
+        DbCommand::Up { target, .. } => {
+            let report = migrator.up_to(db, &target).await?;
+            print_created_schemas(&report.created_schemas);
             println!(
                 "Applied {} migration(s), skipped {}.",
                 report.applied.len(),
                 report.skipped
             );
-            for applied in report.applied {
+            print_applied(&report.applied);
+            print_retries(&report.retries);
+            if !report.unapplied.is_empty() {
                 println!(
-                    "- V{} {} ({}) [{} ms]",
-                    applied.version, applied.description, applied.script, applied.execution_time_ms
+                    "Left {} pending migration(s) beyond target {target}:",
+                    report.unapplied.len()
                 );
+                for migration in &report.unapplied {
+                    println!("- {}", migration.script);
+                }
             }
         }
         DbCommand::Status {
@@ -406,31 +1175,60 @@ async fn run_db_command(
             fail_on_pending,
         } => {
             let report = migrator.status(db).await?;
-            match format {
-                StatusFormat::Table => println!("{}", format_status_table(&report)),
-                StatusFormat::Json => println!(
-                    "{}",
-                    serde_json::to_string_pretty(&report).map_err(|err| {
-                        SchemalaneError::Validation(format!("failed to encode JSON: {err}"))
-                    })?
-                ),
-            }
+            print_output(
+                format,
+                || format_status_table(&report),
+                || serde_json::to_string_pretty(&report),
+            )?;
             if fail_on_pending {
                 should_fail_on_pending(&report)?;
             }
         }
-        DbCommand::Fresh { yes } => {
+        DbCommand::Validate { format } => {
+            let report = migrator.validate(db).await?;
+            print_output(
+                format,
+                || format_validation_report(&report),
+                || serde_json::to_string_pretty(&report),
+            )?;
+            ensure_valid(&report)?;
+        }
+        DbCommand::Fresh {
+            dry_run: true,
+            format,
+            ..
+        } => print_plan(&migrator.dry_run_fresh(db).await?, format)?,
+        DbCommand::Fresh { yes, .. } => {
             let report = migrator.fresh(db, yes).await?;
             println!(
-                "Fresh completed. Applied {} migration(s).",
+                "Fresh completed. Dropped {} object(s), applied {} migration(s).",
+                report.dropped_objects.len(),
                 report.applied.len()
             );
-            for applied in report.applied {
-                println!(
-                    "- V{} {} ({}) [{} ms]",
-                    applied.version, applied.description, applied.script, applied.execution_time_ms
-                );
+            for object in &report.dropped_objects {
+                println!("- dropped {object}");
             }
+            print_created_schemas(&report.created_schemas);
+            print_applied(&report.applied);
+            print_retries(&report.retries);
+        }
+        DbCommand::Repair {
+            mark_missing_deleted,
+        } => {
+            let report = migrator.repair(db, mark_missing_deleted).await?;
+            print_repair_report(&report);
+        }
+        DbCommand::Baseline {
+            baseline_version,
+            description,
+        } => {
+            let report = migrator
+                .baseline(db, &baseline_version, &description)
+                .await?;
+            println!(
+                "Baselined at version {} ({}) [rank {}].",
+                report.version, report.description, report.installed_rank
+            );
         }
     }
 
@@ -439,9 +1237,21 @@ async fn run_db_command(
 
 #[cfg(test)]
 mod tests {
-    use super::{Cli, DEFAULT_MIGRATION_DIR, MigrateCommand, RootCommand};
+    use super::{
+        AdvisoryLock, Cli, ConfigArgs, ConfigLoader, DEFAULT_MIGRATION_DIR, DbCommand,
+        MigrateCommand, MigrationTarget, OnFailure, OutputFormat, RootCommand, SchemalaneConfig,
+        TenantSelector,
+    };
     use clap::Parser;
+    use schemalane_core::{MigrationTimeouts, RetryPolicy};
     use std::path::PathBuf;
+    use std::time::Duration;
+
+    /// The flags over the defaults, without a config file or environment.
+    fn cli_config(args: ConfigArgs) -> SchemalaneConfig {
+        args.into_config_with(&ConfigLoader::new(), PathBuf::from("migrations"))
+            .expect("config without file or environment")
+    }
 
     #[test]
     fn parse_short_migration_dir_flag() {
@@ -449,7 +1259,14 @@ mod tests {
             .expect("CLI args should parse");
         let RootCommand::Migrate(args) = cli.command;
         assert_eq!(args.migration_dir, PathBuf::from("test2/migration"));
-        assert!(matches!(args.command, Some(MigrateCommand::Up)));
+        assert!(matches!(
+            args.command,
+            Some(MigrateCommand::Up {
+                target: MigrationTarget::Latest,
+                dry_run: false,
+                ..
+            })
+        ));
     }
 
     #[test]
@@ -468,4 +1285,385 @@ mod tests {
         assert_eq!(args.migration_dir, PathBuf::from(DEFAULT_MIGRATION_DIR));
         assert!(args.command.is_none(), "no subcommand means implicit up");
     }
+
+    #[test]
+    fn parse_repair_flags() {
+        let cli =
+            Cli::try_parse_from(["schemalane", "migrate", "repair", "--mark-missing-deleted"])
+                .expect("CLI args should parse");
+        let RootCommand::Migrate(args) = cli.command;
+        assert!(matches!(
+            args.command,
+            Some(MigrateCommand::Repair {
+                mark_missing_deleted: true
+            })
+        ));
+    }
+
+    #[test]
+    fn parse_baseline_defaults() {
+        let cli = Cli::try_parse_from(["schemalane", "migrate", "baseline", "--version", "40"])
+            .expect("CLI args should parse");
+        let RootCommand::Migrate(args) = cli.command;
+        let Some(MigrateCommand::Baseline {
+            baseline_version,
+            description,
+        }) = args.command
+        else {
+            unreachable!("expected baseline command");
+        };
+        assert_eq!(baseline_version, "40");
+        assert_eq!(description, "<< Flyway Baseline >>");
+    }
+
+    #[test]
+    fn parse_up_targets() {
+        for (arg, expected) in [
+            ("next", MigrationTarget::Steps(1)),
+            ("3-steps", MigrationTarget::Steps(3)),
+            (
+                "2026.02.24",
+                MigrationTarget::parse("2026.2.24").expect("valid version"),
+            ),
+        ] {
+            let cli = Cli::try_parse_from(["schemalane", "migrate", "up", "--target", arg])
+                .expect("CLI args should parse");
+            let RootCommand::Migrate(args) = cli.command;
+            let Some(MigrateCommand::Up { target, .. }) = args.command else {
+                unreachable!("expected up command");
+            };
+            assert_eq!(target, expected);
+        }
+
+        Cli::try_parse_from(["schemalane", "migrate", "up", "--target", "0-steps"])
+            .expect_err("zero steps should be rejected");
+    }
+
+    #[test]
+    fn parse_up_group() {
+        let cli = Cli::try_parse_from(["schemalane", "migrate", "up", "--group"])
+            .expect("CLI args should parse");
+        let RootCommand::Migrate(args) = cli.command;
+        let command = DbCommand::from(args.command.expect("up command"));
+        assert!(command.groups_transactions());
+
+        let cli =
+            Cli::try_parse_from(["schemalane", "migrate", "up"]).expect("CLI args should parse");
+        let RootCommand::Migrate(args) = cli.command;
+        let command = DbCommand::from(args.command.expect("up command"));
+        assert!(!command.groups_transactions());
+    }
+
+    #[test]
+    fn parse_fresh_dry_run_without_yes() {
+        let cli = Cli::try_parse_from([
+            "schemalane",
+            "migrate",
+            "fresh",
+            "--dry-run",
+            "--format",
+            "json",
+        ])
+        .expect("CLI args should parse");
+        let RootCommand::Migrate(args) = cli.command;
+        assert!(matches!(
+            args.command,
+            Some(MigrateCommand::Fresh {
+                yes: false,
+                dry_run: true,
+                format: OutputFormat::Json
+            })
+        ));
+    }
+
+    #[test]
+    fn parse_validate_format() {
+        let cli = Cli::try_parse_from(["schemalane", "migrate", "validate", "--format", "json"])
+            .expect("CLI args should parse");
+        let RootCommand::Migrate(args) = cli.command;
+        assert!(matches!(
+            args.command,
+            Some(MigrateCommand::Validate {
+                format: OutputFormat::Json
+            })
+        ));
+    }
+
+    #[test]
+    fn parse_lock_timeout_seconds() {
+        let cli = Cli::try_parse_from(["schemalane", "migrate", "--lock-timeout", "30", "up"])
+            .expect("CLI args should parse");
+        let RootCommand::Migrate(args) = cli.command;
+        let config = cli_config(args.config);
+        assert_eq!(config.lock_timeout, Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn parse_lock_flags() {
+        let config_for = |args: &[&str]| {
+            let cli = Cli::try_parse_from(args).expect("CLI args should parse");
+            let RootCommand::Migrate(args) = cli.command;
+            cli_config(args.config)
+        };
+
+        let config = config_for(&["schemalane", "migrate", "--lock-id", "derived", "up"]);
+        assert_eq!(config.advisory_lock, AdvisoryLock::Derived);
+        let config = config_for(&["schemalane", "migrate", "--lock-id", "-17", "up"]);
+        assert_eq!(config.advisory_lock, AdvisoryLock::Fixed(-17));
+        let config = config_for(&["schemalane", "migrate", "--no-lock", "up"]);
+        assert_eq!(config.advisory_lock, AdvisoryLock::Disabled);
+
+        Cli::try_parse_from(["schemalane", "migrate", "--lock-id", "1", "--no-lock", "up"])
+            .expect_err("--lock-id and --no-lock conflict");
+    }
+
+    #[test]
+    fn parse_placeholders() {
+        let cli = Cli::try_parse_from([
+            "schemalane",
+            "migrate",
+            "--placeholder",
+            "app_role=baker",
+            "--placeholder=filter=a=b",
+            "--placeholder-prefix",
+            "{{",
+            "--placeholder-suffix",
+            "}}",
+            "up",
+        ])
+        .expect("CLI args should parse");
+        let RootCommand::Migrate(args) = cli.command;
+        let config = cli_config(args.config);
+        assert_eq!(config.placeholders.len(), 2);
+        assert_eq!(config.placeholders["app_role"], "baker");
+        assert_eq!(config.placeholders["filter"], "a=b");
+        assert_eq!(config.placeholder_prefix, "{{");
+        assert_eq!(config.placeholder_suffix, "}}");
+
+        Cli::try_parse_from(["schemalane", "migrate", "--placeholder", "app_role", "up"])
+            .expect_err("placeholders need a value");
+    }
+
+    #[test]
+    fn parse_migration_timeouts() {
+        let cli = Cli::try_parse_from([
+            "schemalane",
+            "migrate",
+            "--migration-lock-timeout",
+            "5s",
+            "--statement-timeout",
+            "2min",
+            "up",
+        ])
+        .expect("CLI args should parse");
+        let RootCommand::Migrate(args) = cli.command;
+        let config = cli_config(args.config);
+        assert_eq!(
+            config.migration_timeouts,
+            MigrationTimeouts {
+                lock_timeout: Some(Duration::from_secs(5)),
+                statement_timeout: Some(Duration::from_mins(2)),
+            }
+        );
+        assert_eq!(config.lock_timeout, None);
+
+        Cli::try_parse_from(["schemalane", "migrate", "--statement-timeout", "soon", "up"])
+            .expect_err("durations need a unit we know");
+    }
+
+    #[test]
+    fn parse_retry_policy() {
+        let cli = Cli::try_parse_from([
+            "schemalane",
+            "migrate",
+            "--retries",
+            "3",
+            "--retry-backoff",
+            "500ms",
+            "up",
+        ])
+        .expect("CLI args should parse");
+        let RootCommand::Migrate(args) = cli.command;
+        let config = cli_config(args.config);
+        assert_eq!(
+            config.retry,
+            RetryPolicy {
+                max_retries: 3,
+                initial_backoff: Duration::from_millis(500),
+                max_backoff: Duration::from_secs(30),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_schema_list() {
+        let config_for = |args: &[&str]| {
+            let cli = Cli::try_parse_from(args).expect("CLI args should parse");
+            let RootCommand::Migrate(args) = cli.command;
+            cli_config(args.config)
+        };
+
+        let config = config_for(&[
+            "schemalane",
+            "migrate",
+            "--schemas",
+            "public,audit,billing",
+            "up",
+        ]);
+        assert_eq!(config.schemas, ["public", "audit", "billing"]);
+        assert_eq!(config.managed_schemas(), ["public", "audit", "billing"]);
+
+        let config = config_for(&["schemalane", "migrate", "--schema", "app", "up"]);
+        assert_eq!(config.managed_schemas(), ["app"]);
+
+        assert!(
+            Cli::try_parse_from([
+                "schemalane",
+                "migrate",
+                "--schema",
+                "app",
+                "--schemas",
+                "public,audit",
+                "up",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_tenant_selection() {
+        let tenants_for = |args: &[&str]| {
+            let cli = Cli::try_parse_from(args).expect("CLI args should parse");
+            let RootCommand::Migrate(args) = cli.command;
+            args.tenants
+        };
+
+        let tenants = tenants_for(&[
+            "schemalane",
+            "migrate",
+            "--schema-pattern",
+            "tenant_%",
+            "--tenant-concurrency",
+            "8",
+            "up",
+        ]);
+        assert_eq!(
+            tenants.selector(),
+            Some(TenantSelector::SchemaPattern("tenant_%".to_owned()))
+        );
+        assert_eq!(tenants.max_connections(), 17);
+
+        let tenants = tenants_for(&[
+            "schemalane",
+            "migrate",
+            "--tenants-query",
+            "SELECT schema_name FROM tenants",
+            "status",
+        ]);
+        assert_eq!(
+            tenants.selector(),
+            Some(TenantSelector::Query(
+                "SELECT schema_name FROM tenants".to_owned()
+            ))
+        );
+        assert_eq!(tenants.max_connections(), 9);
+
+        let tenants = tenants_for(&["schemalane", "migrate", "up"]);
+        assert_eq!(tenants.selector(), None);
+        assert_eq!(tenants.max_connections(), 5);
+
+        assert!(
+            Cli::try_parse_from([
+                "schemalane",
+                "migrate",
+                "--schema-pattern",
+                "tenant_%",
+                "--tenants-query",
+                "SELECT 'a'",
+                "up",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_targets_file_and_failure_policy() {
+        let cli = Cli::try_parse_from([
+            "schemalane",
+            "migrate",
+            "--targets",
+            "schemalane-targets.toml",
+            "--on-failure",
+            "continue",
+            "status",
+        ])
+        .expect("CLI args should parse");
+        let RootCommand::Migrate(args) = cli.command;
+        assert_eq!(args.targets, Some(PathBuf::from("schemalane-targets.toml")));
+        assert_eq!(args.on_failure, Some(OnFailure::Continue));
+        assert!(DbCommand::from(args.command.expect("command")).runs_per_target());
+
+        for args in [
+            &["schemalane", "migrate", "--on-failure", "stop", "up"][..],
+            &[
+                "schemalane",
+                "migrate",
+                "--targets",
+                "t.toml",
+                "--schema-pattern",
+                "tenant_%",
+                "up",
+            ],
+            &[
+                "schemalane",
+                "migrate",
+                "--targets",
+                "t.toml",
+                "--on-failure",
+                "retry",
+                "up",
+            ],
+        ] {
+            assert!(Cli::try_parse_from(args).is_err(), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn parse_flags_override_environment_variables() {
+        let cli = Cli::try_parse_from([
+            "schemalane",
+            "migrate",
+            "--history-table",
+            "cli_history",
+            "--retries",
+            "2",
+            "--out-of-order=false",
+            "up",
+        ])
+        .expect("CLI args should parse");
+        let RootCommand::Migrate(args) = cli.command;
+        let loader = ConfigLoader::new().with_env_vars([
+            ("SCHEMALANE_SCHEMA".to_owned(), "env_schema".to_owned()),
+            (
+                "SCHEMALANE_HISTORY_TABLE".to_owned(),
+                "env_history".to_owned(),
+            ),
+            ("SCHEMALANE_RETRIES".to_owned(), "5".to_owned()),
+            ("SCHEMALANE_OUT_OF_ORDER".to_owned(), "true".to_owned()),
+        ]);
+        let config = args
+            .config
+            .into_config_with(&loader, PathBuf::from("migrations"))
+            .expect("config loads");
+        assert_eq!(config.schema, "env_schema");
+        assert_eq!(config.history_table, "cli_history");
+        assert_eq!(config.retry.max_retries, 2);
+        assert!(!config.out_of_order);
+        assert_eq!(config.placeholder_prefix, "${");
+
+        let cli = Cli::try_parse_from(["schemalane", "migrate", "--out-of-order", "up"])
+            .expect("bare flag should parse");
+        let RootCommand::Migrate(args) = cli.command;
+        assert_eq!(args.config.out_of_order, Some(true));
+    }
 }
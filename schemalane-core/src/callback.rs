@@ -0,0 +1,137 @@
+use crate::{RustMigrationFuture, SchemalaneError};
+use sea_orm_migration::SchemaManager;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Points in `up` and `fresh` where callbacks run, named as in Flyway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CallbackEvent {
+    /// Once, before the first pending migration.
+    BeforeMigrate,
+    /// Before each migration, inside its transaction if it has one.
+    BeforeEachMigrate,
+    /// After each migration, inside its transaction if it has one.
+    AfterEachMigrate,
+    /// Once, after every migration succeeded.
+    AfterMigrate,
+    /// Once, after a migration or another callback failed.
+    AfterMigrateError,
+}
+
+impl CallbackEvent {
+    pub const ALL: [Self; 5] = [
+        Self::BeforeMigrate,
+        Self::BeforeEachMigrate,
+        Self::AfterEachMigrate,
+        Self::AfterMigrate,
+        Self::AfterMigrateError,
+    ];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::BeforeMigrate => "beforeMigrate",
+            Self::BeforeEachMigrate => "beforeEachMigrate",
+            Self::AfterEachMigrate => "afterEachMigrate",
+            Self::AfterMigrate => "afterMigrate",
+            Self::AfterMigrateError => "afterMigrateError",
+        }
+    }
+
+    /// Parses a Flyway event name such as `afterMigrate`.
+    pub fn parse(value: &str) -> Result<Self, SchemalaneError> {
+        Self::ALL
+            .into_iter()
+            .find(|event| event.as_str() == value)
+            .ok_or_else(|| {
+                SchemalaneError::Validation(format!(
+                    "invalid callback event '{value}': expected one of {}",
+                    Self::ALL.map(Self::as_str).join(", ")
+                ))
+            })
+    }
+
+    /// The event of a SQL callback script named `<event>.sql` or
+    /// `<event>__<description>.sql`; `None` for any other file.
+    pub fn from_script(file_name: &str) -> Option<Self> {
+        let stem = file_name.strip_suffix(".sql")?;
+        let name = stem.split_once("__").map_or(stem, |(name, _)| name);
+        Self::parse(name).ok()
+    }
+}
+
+impl std::str::FromStr for CallbackEvent {
+    type Err = SchemalaneError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse(value)
+    }
+}
+
+impl std::fmt::Display for CallbackEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A SQL callback script found next to the migrations, e.g.
+/// `afterMigrate__refresh_views.sql`.
+#[derive(Debug, Clone)]
+pub struct SqlCallback {
+    pub event: CallbackEvent,
+    pub script: String,
+    pub sql: Cow<'static, str>,
+}
+
+impl SqlCallback {
+    /// `None` if `script` is not named after a callback event.
+    pub fn new(script: impl Into<String>, sql: impl Into<Cow<'static, str>>) -> Option<Self> {
+        let script = script.into();
+        Some(Self {
+            event: CallbackEvent::from_script(&script)?,
+            script,
+            sql: sql.into(),
+        })
+    }
+}
+
+/// What a Rust callback is being run for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallbackContext {
+    pub event: CallbackEvent,
+    /// The migration for `BeforeEachMigrate`/`AfterEachMigrate`, and the one
+    /// that failed, if any, for `AfterMigrateError`.
+    pub script: Option<String>,
+}
+
+type DynRustCallbackFn = dyn for<'a> Fn(&'a SchemaManager<'a>, &'a CallbackContext) -> RustMigrationFuture<'a>
+    + Send
+    + Sync;
+
+/// A callback written in Rust, registered with
+/// `SchemalaneMigrator::with_callback`.
+#[derive(Clone)]
+pub struct RustCallback {
+    run: Arc<DynRustCallbackFn>,
+}
+
+impl RustCallback {
+    pub fn new<F>(run: F) -> Self
+    where
+        F: for<'a> Fn(&'a SchemaManager<'a>, &'a CallbackContext) -> RustMigrationFuture<'a>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self { run: Arc::new(run) }
+    }
+
+    pub(crate) async fn run(
+        &self,
+        manager: &SchemaManager<'_>,
+        context: &CallbackContext,
+    ) -> Result<(), sea_orm::DbErr> {
+        (self.run)(manager, context).await
+    }
+}
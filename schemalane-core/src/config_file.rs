@@ -0,0 +1,755 @@
+use crate::{AdvisoryLock, SchemalaneConfig, SchemalaneError, parse_timeout};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Read from the working directory when no other file is named.
+pub const DEFAULT_CONFIG_FILE: &str = "schemalane.toml";
+/// Names the config file, like `--config`.
+pub const CONFIG_FILE_ENV_VAR: &str = "SCHEMALANE_CONFIG";
+/// Names the `[env.<name>]` profile to use, like `--env`.
+pub const PROFILE_ENV_VAR: &str = "SCHEMALANE_ENV";
+const ENV_VAR_PREFIX: &str = "SCHEMALANE_";
+const PLACEHOLDER_KEY_PREFIX: &str = "placeholders.";
+
+/// Settings from one source: a config file, its profile, environment
+/// variables or command-line flags. Unset settings leave the layer below
+/// alone.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigLayer {
+    pub schema: Option<String>,
+    pub schemas: Option<Vec<String>>,
+    pub history_table: Option<String>,
+    pub migrations_dirs: Option<Vec<PathBuf>>,
+    pub installed_by: Option<String>,
+    pub advisory_lock: Option<AdvisoryLock>,
+    pub lock_timeout: Option<Duration>,
+    pub migration_lock_timeout: Option<Duration>,
+    pub statement_timeout: Option<Duration>,
+    pub out_of_order: Option<bool>,
+    /// Merged with the placeholders of lower layers, overriding equal names.
+    pub placeholders: BTreeMap<String, String>,
+    pub placeholder_prefix: Option<String>,
+    pub placeholder_suffix: Option<String>,
+    pub retries: Option<u32>,
+    pub retry_backoff: Option<Duration>,
+    pub retry_max_backoff: Option<Duration>,
+    /// Keys of an imported `flyway.conf` that Schemalane has no equivalent
+    /// for and skipped.
+    pub ignored_keys: Vec<String>,
+}
+
+impl ConfigLayer {
+    /// Set `key`, named as in `schemalane.toml`, from its text form. Lists
+    /// are comma-separated and placeholders are set as `placeholders.<name>`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), SchemalaneError> {
+        if let Some(name) = key.strip_prefix(PLACEHOLDER_KEY_PREFIX) {
+            self.placeholders.insert(name.to_owned(), value.to_owned());
+            return Ok(());
+        }
+        match key {
+            "schemas" | "migrations_dirs" => {
+                return self.set_list(key, split_list(value));
+            }
+            "schema" => self.schema = Some(value.to_owned()),
+            "history_table" => self.history_table = Some(value.to_owned()),
+            "installed_by" => self.installed_by = Some(value.to_owned()),
+            "lock_id" => {
+                self.advisory_lock = Some(if value == "disabled" {
+                    AdvisoryLock::Disabled
+                } else {
+                    AdvisoryLock::parse(value)?
+                });
+            }
+            "lock_timeout" => self.lock_timeout = Some(parse_timeout(value)?),
+            "migration_lock_timeout" => self.migration_lock_timeout = Some(parse_timeout(value)?),
+            "statement_timeout" => self.statement_timeout = Some(parse_timeout(value)?),
+            "out_of_order" => self.out_of_order = Some(parse_bool(key, value)?),
+            "placeholder_prefix" => self.placeholder_prefix = Some(value.to_owned()),
+            "placeholder_suffix" => self.placeholder_suffix = Some(value.to_owned()),
+            "retries" => {
+                self.retries = Some(value.parse().map_err(|_| {
+                    SchemalaneError::Validation(format!(
+                        "invalid retries '{value}': expected a non-negative integer"
+                    ))
+                })?);
+            }
+            "retry_backoff" => self.retry_backoff = Some(parse_timeout(value)?),
+            "retry_max_backoff" => self.retry_max_backoff = Some(parse_timeout(value)?),
+            _ => {
+                return Err(SchemalaneError::Validation(format!(
+                    "unknown setting '{key}'"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn set_list(&mut self, key: &str, values: Vec<String>) -> Result<(), SchemalaneError> {
+        match key {
+            "schemas" => self.schemas = Some(values),
+            "migrations_dirs" => {
+                self.migrations_dirs = Some(values.into_iter().map(PathBuf::from).collect());
+            }
+            _ => {
+                return Err(SchemalaneError::Validation(format!(
+                    "setting '{key}' is not a list"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// `SCHEMALANE_<KEY>` variables, e.g. `SCHEMALANE_HISTORY_TABLE`, and
+    /// `SCHEMALANE_PLACEHOLDERS_<NAME>` for a placeholder named in lower
+    /// case. Other `SCHEMALANE_` variables are left alone.
+    pub fn from_env_vars<I>(vars: I) -> Result<Self, SchemalaneError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut layer = Self::default();
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(ENV_VAR_PREFIX) else {
+                continue;
+            };
+            let key = key.to_ascii_lowercase();
+            if let Some(placeholder) = key.strip_prefix("placeholders_") {
+                layer.placeholders.insert(placeholder.to_owned(), value);
+            } else if SETTING_KEYS.contains(&key.as_str()) {
+                layer
+                    .set(&key, &value)
+                    .map_err(|err| SchemalaneError::Validation(format!("{name}: {err}")))?;
+            }
+        }
+        layer.check_schemas()
+    }
+
+    /// Settings from the text of a Flyway `flyway.conf`. Relative
+    /// `filesystem:` locations are resolved against `base_dir`; keys with no
+    /// Schemalane equivalent, such as `flyway.url`, end up in
+    /// `ignored_keys`.
+    pub fn from_flyway_conf(text: &str, base_dir: &Path) -> Result<Self, SchemalaneError> {
+        let mut layer = Self::default();
+        for (key, value) in parse_properties(text) {
+            let Some(name) = key.strip_prefix("flyway.") else {
+                layer.ignored_keys.push(key);
+                continue;
+            };
+            if let Some(placeholder) = name.strip_prefix(PLACEHOLDER_KEY_PREFIX) {
+                layer.placeholders.insert(placeholder.to_owned(), value);
+                continue;
+            }
+            match name {
+                "defaultSchema" => layer.set("schema", &value)?,
+                "schemas" => layer.set("schemas", &value)?,
+                "table" => layer.set("history_table", &value)?,
+                "installedBy" => layer.set("installed_by", &value)?,
+                "outOfOrder" => layer.set("out_of_order", &value)?,
+                "placeholderPrefix" => layer.set("placeholder_prefix", &value)?,
+                "placeholderSuffix" => layer.set("placeholder_suffix", &value)?,
+                "placeholderReplacement" => {
+                    if !parse_bool(&key, &value)? {
+                        layer.placeholder_prefix = Some(String::new());
+                    }
+                }
+                "locations" => {
+                    let mut dirs = Vec::new();
+                    for location in split_list(&value) {
+                        match location.strip_prefix("filesystem:") {
+                            Some(dir) => dirs.push(base_dir.join(dir)),
+                            None if location.contains(':') => {
+                                layer.ignored_keys.push(format!("{key}={location}"));
+                            }
+                            None => dirs.push(base_dir.join(location)),
+                        }
+                    }
+                    if !dirs.is_empty() {
+                        layer.migrations_dirs = Some(dirs);
+                    }
+                }
+                _ => layer.ignored_keys.push(key),
+            }
+        }
+        layer.check_schemas()
+    }
+
+    /// `schema` and `schemas` set together must agree on the schema holding
+    /// the history table: the first of `schemas`.
+    fn check_schemas(self) -> Result<Self, SchemalaneError> {
+        match (&self.schema, &self.schemas) {
+            (Some(schema), Some(schemas)) if schemas.first() != Some(schema) => {
+                Err(SchemalaneError::Validation(format!(
+                    "schema '{schema}' conflicts with schemas [{}]: the first of schemas holds the history table",
+                    schemas.join(", ")
+                )))
+            }
+            _ => Ok(self),
+        }
+    }
+
+    /// Let `over` override this layer.
+    pub fn merge(&mut self, over: Self) {
+        fn take<T>(value: &mut Option<T>, over: Option<T>) {
+            if over.is_some() {
+                *value = over;
+            }
+        }
+        // Both name the schema holding the history table, so setting either
+        // replaces both.
+        if over.schema.is_some() || over.schemas.is_some() {
+            self.schema = over.schema;
+            self.schemas = over.schemas;
+        }
+        take(&mut self.history_table, over.history_table);
+        take(&mut self.migrations_dirs, over.migrations_dirs);
+        take(&mut self.installed_by, over.installed_by);
+        take(&mut self.advisory_lock, over.advisory_lock);
+        take(&mut self.lock_timeout, over.lock_timeout);
+        take(
+            &mut self.migration_lock_timeout,
+            over.migration_lock_timeout,
+        );
+        take(&mut self.statement_timeout, over.statement_timeout);
+        take(&mut self.out_of_order, over.out_of_order);
+        take(&mut self.placeholder_prefix, over.placeholder_prefix);
+        take(&mut self.placeholder_suffix, over.placeholder_suffix);
+        take(&mut self.retries, over.retries);
+        take(&mut self.retry_backoff, over.retry_backoff);
+        take(&mut self.retry_max_backoff, over.retry_max_backoff);
+        self.placeholders.extend(over.placeholders);
+        self.ignored_keys.extend(over.ignored_keys);
+    }
+
+    /// Write the settings of this layer into `config`.
+    pub fn apply_to(self, config: &mut SchemalaneConfig) {
+        if let Some(schema) = self.schema {
+            config.schema = schema;
+            config.schemas.clear();
+        }
+        if let Some(schemas) = self.schemas {
+            config.schemas = schemas;
+        }
+        if let Some(history_table) = self.history_table {
+            config.history_table = history_table;
+        }
+        if let Some(dirs) = self.migrations_dirs {
+            config.migrations_dirs = dirs;
+        }
+        if self.installed_by.is_some() {
+            config.installed_by = self.installed_by;
+        }
+        if let Some(advisory_lock) = self.advisory_lock {
+            config.advisory_lock = advisory_lock;
+        }
+        if self.lock_timeout.is_some() {
+            config.lock_timeout = self.lock_timeout;
+        }
+        if self.migration_lock_timeout.is_some() {
+            config.migration_timeouts.lock_timeout = self.migration_lock_timeout;
+        }
+        if self.statement_timeout.is_some() {
+            config.migration_timeouts.statement_timeout = self.statement_timeout;
+        }
+        if let Some(out_of_order) = self.out_of_order {
+            config.out_of_order = out_of_order;
+        }
+        config.placeholders.extend(self.placeholders);
+        if let Some(prefix) = self.placeholder_prefix {
+            config.placeholder_prefix = prefix;
+        }
+        if let Some(suffix) = self.placeholder_suffix {
+            config.placeholder_suffix = suffix;
+        }
+        if let Some(retries) = self.retries {
+            config.retry.max_retries = retries;
+        }
+        if let Some(backoff) = self.retry_backoff {
+            config.retry.initial_backoff = backoff;
+        }
+        if let Some(backoff) = self.retry_max_backoff {
+            config.retry.max_backoff = backoff;
+        }
+    }
+}
+
+const SETTING_KEYS: [&str; 15] = [
+    "schema",
+    "schemas",
+    "history_table",
+    "migrations_dirs",
+    "installed_by",
+    "lock_id",
+    "lock_timeout",
+    "migration_lock_timeout",
+    "statement_timeout",
+    "out_of_order",
+    "placeholder_prefix",
+    "placeholder_suffix",
+    "retries",
+    "retry_backoff",
+    "retry_max_backoff",
+];
+
+/// A parsed `schemalane.toml`:
+///
+/// ```toml
+/// schema = "app"
+/// migrations_dirs = ["migrations", "../shared/migrations"]
+/// lock_id = "derived"
+/// statement_timeout = "2min"
+/// flyway_conf = "flyway.conf"
+///
+/// [placeholders]
+/// owner = "app_owner"
+///
+/// [env.prod]
+/// lock_timeout = "30s"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigFile {
+    /// Top-level settings, over those imported from `flyway_conf`.
+    pub settings: ConfigLayer,
+    /// `[env.<name>]` tables, applied over `settings` when selected.
+    pub profiles: BTreeMap<String, ConfigLayer>,
+}
+
+impl ConfigFile {
+    /// Relative paths in the file, including `flyway_conf`, are resolved
+    /// against the directory of the file.
+    pub fn load(path: &Path) -> Result<Self, SchemalaneError> {
+        let text = std::fs::read_to_string(path)?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        Self::parse(&text, base_dir)
+            .map_err(|err| SchemalaneError::Validation(format!("{}: {err}", path.display())))
+    }
+
+    pub fn parse(text: &str, base_dir: &Path) -> Result<Self, SchemalaneError> {
+        let mut table: toml::Table = toml::from_str(text).map_err(|err| {
+            SchemalaneError::Validation(format!("invalid TOML: {}", err.message()))
+        })?;
+
+        let mut settings = match table.remove("flyway_conf") {
+            Some(toml::Value::String(conf)) => {
+                let path = base_dir.join(conf);
+                let text = std::fs::read_to_string(&path)?;
+                let conf_dir = path.parent().unwrap_or_else(|| Path::new(""));
+                ConfigLayer::from_flyway_conf(&text, conf_dir)?
+            }
+            Some(_) => return Err(expected("flyway_conf", "a path")),
+            None => ConfigLayer::default(),
+        };
+
+        let mut profiles = BTreeMap::new();
+        match table.remove("env") {
+            Some(toml::Value::Table(env)) => {
+                for (name, profile) in env {
+                    let toml::Value::Table(profile) = profile else {
+                        return Err(expected(&format!("env.{name}"), "a table"));
+                    };
+                    let layer = layer_from_table(profile, base_dir)
+                        .map_err(|err| prefix_error(&format!("[env.{name}]"), err))?;
+                    profiles.insert(name, layer);
+                }
+            }
+            Some(_) => return Err(expected("env", "a table of profiles")),
+            None => {}
+        }
+
+        settings.merge(layer_from_table(table, base_dir)?);
+        Ok(Self { settings, profiles })
+    }
+
+    /// `settings`, with the `profile` table over them if one is named.
+    pub fn layer(&self, profile: Option<&str>) -> Result<ConfigLayer, SchemalaneError> {
+        let mut layer = self.settings.clone();
+        if let Some(profile) = profile {
+            let over = self.profiles.get(profile).ok_or_else(|| {
+                SchemalaneError::Validation(format!(
+                    "unknown environment profile '{profile}': expected one of [{}]",
+                    self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+                ))
+            })?;
+            layer.merge(over.clone());
+        }
+        Ok(layer)
+    }
+}
+
+/// Builds a `SchemalaneConfig` from defaults, a config file and environment
+/// variables, each overriding the one before. Command-line flags go on top
+/// as one more `ConfigLayer`.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLoader {
+    file: Option<PathBuf>,
+    profile: Option<String>,
+    env_vars: Vec<(String, String)>,
+}
+
+impl ConfigLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process environment: its `SCHEMALANE_` variables, the file named
+    /// by `SCHEMALANE_CONFIG` or else `schemalane.toml` if it exists, and
+    /// the profile named by `SCHEMALANE_ENV`.
+    pub fn from_process_env() -> Self {
+        let env_vars: Vec<_> = std::env::vars().collect();
+        let lookup = |name: &str| {
+            env_vars
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+        let file = lookup(CONFIG_FILE_ENV_VAR).map(PathBuf::from).or_else(|| {
+            Path::new(DEFAULT_CONFIG_FILE)
+                .is_file()
+                .then(|| PathBuf::from(DEFAULT_CONFIG_FILE))
+        });
+        Self {
+            file,
+            profile: lookup(PROFILE_ENV_VAR),
+            env_vars,
+        }
+    }
+
+    #[must_use]
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_env_vars<I>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        self.env_vars = vars.into_iter().collect();
+        self
+    }
+
+    /// The file (with its profile) and then the environment variables,
+    /// merged into one layer.
+    pub fn layer(&self) -> Result<ConfigLayer, SchemalaneError> {
+        let mut layer = match &self.file {
+            Some(path) => ConfigFile::load(path)?.layer(self.profile.as_deref())?,
+            None if self.profile.is_some() => {
+                return Err(SchemalaneError::Validation(
+                    "an environment profile was selected but no config file was found".to_owned(),
+                ));
+            }
+            None => ConfigLayer::default(),
+        };
+        layer.merge(ConfigLayer::from_env_vars(self.env_vars.iter().cloned())?);
+        Ok(layer)
+    }
+
+    /// `defaults` with the file and environment variables applied.
+    pub fn load(&self, defaults: SchemalaneConfig) -> Result<SchemalaneConfig, SchemalaneError> {
+        let mut config = defaults;
+        self.layer()?.apply_to(&mut config);
+        Ok(config)
+    }
+}
+
+fn layer_from_table(table: toml::Table, base_dir: &Path) -> Result<ConfigLayer, SchemalaneError> {
+    let mut layer = ConfigLayer::default();
+    for (key, value) in table {
+        match (key.as_str(), value) {
+            ("placeholders", toml::Value::Table(placeholders)) => {
+                for (name, value) in placeholders {
+                    let toml::Value::String(value) = value else {
+                        return Err(expected(&format!("placeholders.{name}"), "a string"));
+                    };
+                    layer.placeholders.insert(name, value);
+                }
+            }
+            ("placeholders", _) => return Err(expected("placeholders", "a table")),
+            (_, toml::Value::Array(values)) => {
+                let values = values
+                    .into_iter()
+                    .map(|value| match value {
+                        toml::Value::String(value) => Ok(value),
+                        _ => Err(expected(&key, "a list of strings")),
+                    })
+                    .collect::<Result<_, _>>()?;
+                layer.set_list(&key, values)?;
+            }
+            (_, toml::Value::String(value)) => layer.set(&key, &value)?,
+            (_, toml::Value::Integer(value)) => layer.set(&key, &value.to_string())?,
+            (_, toml::Value::Boolean(value)) => layer.set(&key, &value.to_string())?,
+            (_, _) => return Err(expected(&key, "a string, number, boolean or list")),
+        }
+    }
+    if let Some(dirs) = &mut layer.migrations_dirs {
+        for dir in dirs {
+            *dir = base_dir.join(&*dir);
+        }
+    }
+    layer.check_schemas()
+}
+
+/// `key=value` or `key: value` lines of a Java properties file, skipping
+/// `#` and `!` comments and joining lines continued with `\`.
+fn parse_properties(text: &str) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    let mut logical = String::new();
+    for line in text.lines() {
+        let line = line.trim_start();
+        if logical.is_empty() && (line.is_empty() || line.starts_with(['#', '!'])) {
+            continue;
+        }
+        if let Some(continued) = line.strip_suffix('\\') {
+            logical.push_str(continued);
+            continue;
+        }
+        logical.push_str(line);
+        let entry = std::mem::take(&mut logical);
+        let (key, value) = entry.split_once(['=', ':']).unwrap_or((entry.as_str(), ""));
+        entries.push((key.trim().to_owned(), value.trim().to_owned()));
+    }
+    entries
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, SchemalaneError> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(SchemalaneError::Validation(format!(
+            "invalid {key} '{value}': expected true or false"
+        ))),
+    }
+}
+
+fn expected(key: &str, what: &str) -> SchemalaneError {
+    SchemalaneError::Validation(format!("'{key}' must be {what}"))
+}
+
+fn prefix_error(context: &str, err: SchemalaneError) -> SchemalaneError {
+    match err {
+        SchemalaneError::Validation(message) => {
+            SchemalaneError::Validation(format!("{context}: {message}"))
+        }
+        err => err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigFile, ConfigLayer, ConfigLoader};
+    use crate::{AdvisoryLock, SchemalaneConfig};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    const CONFIG: &str = r#"
+schema = "app"
+history_table = "app_history"
+migrations_dirs = ["migrations", "/srv/shared/migrations"]
+lock_id = "derived"
+statement_timeout = "2min"
+out_of_order = false
+
+[placeholders]
+owner = "app_owner"
+region = "eu"
+
+[env.prod]
+lock_id = 42
+lock_timeout = "30s"
+out_of_order = true
+
+[env.prod.placeholders]
+region = "us"
+"#;
+
+    #[test]
+    fn profiles_override_the_top_level_settings() {
+        let file = ConfigFile::parse(CONFIG, Path::new("/repo")).expect("valid config");
+
+        let mut config = SchemalaneConfig::default();
+        file.layer(None).expect("no profile").apply_to(&mut config);
+        assert_eq!(config.schema, "app");
+        assert_eq!(config.history_table, "app_history");
+        assert_eq!(
+            config.migration_dirs(),
+            [
+                PathBuf::from("/repo/migrations"),
+                PathBuf::from("/srv/shared/migrations")
+            ]
+        );
+        assert_eq!(config.advisory_lock, AdvisoryLock::Derived);
+        assert_eq!(
+            config.migration_timeouts.statement_timeout,
+            Some(Duration::from_mins(2))
+        );
+        assert!(!config.out_of_order);
+        assert_eq!(config.placeholders["region"], "eu");
+
+        let mut config = SchemalaneConfig::default();
+        file.layer(Some("prod"))
+            .expect("prod profile")
+            .apply_to(&mut config);
+        assert_eq!(config.schema, "app");
+        assert_eq!(config.advisory_lock, AdvisoryLock::Fixed(42));
+        assert_eq!(config.lock_timeout, Some(Duration::from_secs(30)));
+        assert!(config.out_of_order);
+        assert_eq!(config.placeholders["owner"], "app_owner");
+        assert_eq!(config.placeholders["region"], "us");
+
+        assert!(
+            file.layer(Some("staging"))
+                .is_err_and(|err| err.to_string().contains("expected one of [prod]"))
+        );
+    }
+
+    #[test]
+    fn environment_variables_override_the_file() {
+        let temp = TempDir::new().expect("temp dir");
+        let path = temp.path().join("schemalane.toml");
+        fs::write(&path, CONFIG).expect("write config");
+
+        let config = ConfigLoader::new()
+            .with_file(&path)
+            .with_profile("prod")
+            .with_env_vars([
+                (
+                    "SCHEMALANE_HISTORY_TABLE".to_owned(),
+                    "env_history".to_owned(),
+                ),
+                ("SCHEMALANE_LOCK_ID".to_owned(), "disabled".to_owned()),
+                ("SCHEMALANE_PLACEHOLDERS_REGION".to_owned(), "ap".to_owned()),
+                ("SCHEMALANE_ENV".to_owned(), "ignored".to_owned()),
+                ("PATH".to_owned(), "/usr/bin".to_owned()),
+            ])
+            .load(SchemalaneConfig::default())
+            .expect("config loads");
+        assert_eq!(config.schema, "app");
+        assert_eq!(config.history_table, "env_history");
+        assert_eq!(config.advisory_lock, AdvisoryLock::Disabled);
+        assert_eq!(config.lock_timeout, Some(Duration::from_secs(30)));
+        assert_eq!(config.placeholders["region"], "ap");
+
+        let err = ConfigLoader::new()
+            .with_env_vars([("SCHEMALANE_LOCK_TIMEOUT".to_owned(), "soon".to_owned())])
+            .layer()
+            .expect_err("invalid timeout");
+        assert!(err.to_string().contains("SCHEMALANE_LOCK_TIMEOUT"));
+    }
+
+    #[test]
+    fn flyway_conf_keys_are_imported_below_the_file() {
+        let temp = TempDir::new().expect("temp dir");
+        fs::write(
+            temp.path().join("flyway.conf"),
+            "# Flyway settings\n\
+             flyway.url=jdbc:postgresql://localhost/app\n\
+             flyway.schemas=app,audit\n\
+             flyway.table=legacy_history\n\
+             flyway.locations=filesystem:sql,\\\n    classpath:db/migration\n\
+             flyway.outOfOrder=true\n\
+             flyway.placeholders.owner=flyway_owner\n\
+             flyway.placeholderReplacement=false\n",
+        )
+        .expect("write flyway.conf");
+
+        let file = ConfigFile::parse(
+            "flyway_conf = \"flyway.conf\"\nhistory_table = \"new_history\"\n",
+            temp.path(),
+        )
+        .expect("valid config");
+        let layer = file.layer(None).expect("no profile");
+        assert_eq!(
+            layer.ignored_keys,
+            ["flyway.url", "flyway.locations=classpath:db/migration"]
+        );
+
+        let mut config = SchemalaneConfig::default();
+        layer.apply_to(&mut config);
+        assert_eq!(config.managed_schemas(), ["app", "audit"]);
+        assert_eq!(config.history_table, "new_history");
+        assert_eq!(config.migration_dirs(), [temp.path().join("sql")]);
+        assert!(config.out_of_order);
+        assert_eq!(config.placeholders["owner"], "flyway_owner");
+        assert_eq!(config.placeholder_prefix, "");
+    }
+
+    #[test]
+    fn schema_and_schemas_replace_each_other() {
+        let file = ConfigFile::parse(
+            "schemas = [\"app\", \"audit\"]\n[env.prod]\nschema = \"prod_app\"\n",
+            Path::new(""),
+        )
+        .expect("valid config");
+        let mut config = SchemalaneConfig::default();
+        file.layer(Some("prod"))
+            .expect("prod profile")
+            .apply_to(&mut config);
+        assert_eq!(config.managed_schemas(), ["prod_app"]);
+
+        ConfigFile::parse(
+            "schema = \"app\"\nschemas = [\"app\", \"audit\"]\n",
+            Path::new(""),
+        )
+        .expect("schema is the first of schemas");
+        let err = ConfigFile::parse(
+            "schema = \"app\"\nschemas = [\"audit\", \"app\"]\n",
+            Path::new(""),
+        )
+        .expect_err("schema is not the first of schemas");
+        assert!(
+            err.to_string()
+                .contains("schema 'app' conflicts with schemas [audit, app]"),
+            "unexpected error: {err}"
+        );
+        assert!(
+            ConfigLayer::from_env_vars([
+                ("SCHEMALANE_SCHEMA".to_owned(), "app".to_owned()),
+                ("SCHEMALANE_SCHEMAS".to_owned(), "audit".to_owned()),
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let parse = |text: &str| {
+            ConfigFile::parse(text, Path::new(""))
+                .map(|_| ())
+                .map_err(|err| err.to_string())
+        };
+        assert_eq!(
+            parse("schmea = \"app\""),
+            Err("Validation error: unknown setting 'schmea'".to_owned())
+        );
+        assert!(parse("statement_timeout = \"2 weeks\"").is_err());
+        assert!(parse("schema = [\"app\"]").is_err());
+        assert!(
+            parse("[env.prod]\nlock_id = \"shared\"")
+                .is_err_and(|err| err.contains("[env.prod]: invalid lock id 'shared'"))
+        );
+
+        let mut layer = ConfigLayer::default();
+        layer.set("schemas", "app, audit").expect("list setting");
+        assert_eq!(
+            layer.schemas,
+            Some(vec!["app".to_owned(), "audit".to_owned()])
+        );
+    }
+}
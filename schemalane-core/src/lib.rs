@@ -2,39 +2,234 @@ use crc32fast::Hasher;
 use regex::Regex;
 use sea_orm::sqlx;
 use sea_orm::{
-    ConnectionTrait, DatabaseConnection, DbBackend, DbErr, Statement, TransactionTrait, Value,
+    ConnectionTrait, DatabaseConnection, DbBackend, DbErr, RuntimeErr, Statement, TransactionTrait,
+    Value,
 };
-use sea_orm_migration::SchemaManager;
+use sea_orm_migration::{IntoSchemaManagerConnection, SchemaManager};
 use serde::Serialize;
+use std::borrow::Cow;
 use std::collections::{BTreeSet, HashMap};
 use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::{Arc, OnceLock};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
+pub use callback::{CallbackContext, CallbackEvent, RustCallback, SqlCallback};
+pub use config_file::{
+    CONFIG_FILE_ENV_VAR, ConfigFile, ConfigLayer, ConfigLoader, DEFAULT_CONFIG_FILE,
+    PROFILE_ENV_VAR,
+};
+pub use schema_objects::{SchemaObject, SchemaObjectKind};
 pub use schemalane_macros::embed_migrations;
+pub use source::{
+    CompositeMigrationSource, Discovery, DiscoveryError, EmbeddedMigration,
+    FilesystemMigrationSource, InMemoryMigrationSource, MigrationSource,
+};
+pub use sql::{SqlStatement, StatementSpan, split_statements};
+pub use targets::{OnFailure, TargetConfig, TargetsConfig};
+pub use tenant::{
+    TenantRunner, TenantSelector, TenantStatus, TenantStatusRollup, format_tenant_status_table,
+};
+
+mod callback;
+mod config_file;
+mod schema_objects;
+mod source;
+mod sql;
+mod targets;
+mod tenant;
 
 const DEFAULT_ADVISORY_LOCK_ID: i64 = 7_333_654_209_921_337;
+/// Flyway's base for advisory lock keys, offset by the Java `hashCode` of the
+/// quoted history table.
+const DERIVED_ADVISORY_LOCK_BASE: i64 = 77_431_708_279_161;
+const DELETE_HISTORY_TYPE: &str = "DELETE";
+const BASELINE_HISTORY_TYPE: &str = "BASELINE";
+const SCHEMA_HISTORY_TYPE: &str = "SCHEMA";
+const SCHEMA_MARKER_DESCRIPTION: &str = "<< Flyway Schema Creation >>";
+/// Appended to the history table name for the table holding what Schemalane
+/// records beyond Flyway's columns.
+const MODE_TABLE_SUFFIX: &str = "_schemalane";
+pub const DEFAULT_BASELINE_DESCRIPTION: &str = "<< Flyway Baseline >>";
+const PLACEHOLDER_DEFAULT_SCHEMA: &str = "schemalane:defaultSchema";
+const PLACEHOLDER_USER: &str = "schemalane:user";
+const PLACEHOLDER_TIMESTAMP: &str = "schemalane:timestamp";
+const SQLSTATE_LOCK_NOT_AVAILABLE: &str = "55P03";
+const SQLSTATE_DEADLOCK_DETECTED: &str = "40P01";
+const SQLSTATE_QUERY_CANCELED: &str = "57014";
 
 #[derive(Debug, Clone)]
 pub struct SchemalaneConfig {
     pub schema: String,
+    /// Schemas Schemalane manages, like Flyway's `schemas`: `up` creates
+    /// missing ones and `fresh` cleans all of them. The first one holds the
+    /// history table and overwrites `schema` in `SchemalaneMigrator::new`, so
+    /// set one or the other; empty manages `schema` alone.
+    pub schemas: Vec<String>,
     pub history_table: String,
     pub migrations_dir: PathBuf,
+    /// Directories migrations are read from, merged in order. The first one
+    /// takes the place of `migrations_dir`; empty reads `migrations_dir`
+    /// alone.
+    pub migrations_dirs: Vec<PathBuf>,
     pub installed_by: Option<String>,
-    pub advisory_lock_id: i64,
+    pub advisory_lock: AdvisoryLock,
+    /// How long to wait for the advisory lock; `None` waits forever and
+    /// `Some(Duration::ZERO)` fails at once if another runner holds it.
+    pub lock_timeout: Option<Duration>,
+    pub checksum_algorithm: ChecksumAlgorithm,
+    /// Allow `up` to apply pending migrations whose version is lower than the
+    /// highest applied one (Flyway's `outOfOrder`). Off by default; such
+    /// migrations then fail validation and show as `Ignored` in `status`.
+    pub out_of_order: bool,
+    /// Run all migrations pending in one `up` call, and their history rows,
+    /// in a single transaction, so either all of them apply or none do.
+    /// Fails validation if any of them runs outside a transaction.
+    pub group_transactions: bool,
+    /// Values substituted for `${name}` in SQL migrations before they run.
+    /// The built-in `schemalane:` placeholders take precedence over entries
+    /// with the same name.
+    pub placeholders: HashMap<String, String>,
+    /// Marks the start of a placeholder; an empty prefix turns substitution
+    /// off.
+    pub placeholder_prefix: String,
+    pub placeholder_suffix: String,
+    /// Default `lock_timeout`/`statement_timeout` for transactional
+    /// migrations; SQL header directives and Rust executors override them.
+    pub migration_timeouts: MigrationTimeouts,
+    /// Retries of transactional migrations that failed on a lock or a
+    /// deadlock; off by default.
+    pub retry: RetryPolicy,
 }
 
 impl Default for SchemalaneConfig {
     fn default() -> Self {
         Self {
             schema: "public".to_owned(),
+            schemas: Vec::new(),
             history_table: "flyway_schema_history".to_owned(),
             migrations_dir: PathBuf::from("./migrations"),
+            migrations_dirs: Vec::new(),
             installed_by: None,
-            advisory_lock_id: DEFAULT_ADVISORY_LOCK_ID,
+            advisory_lock: AdvisoryLock::default(),
+            lock_timeout: None,
+            checksum_algorithm: ChecksumAlgorithm::default(),
+            out_of_order: false,
+            group_transactions: false,
+            placeholders: HashMap::new(),
+            placeholder_prefix: "${".to_owned(),
+            placeholder_suffix: "}".to_owned(),
+            migration_timeouts: MigrationTimeouts::default(),
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+impl SchemalaneConfig {
+    /// `schemas`, or just `schema` if it is empty.
+    pub fn managed_schemas(&self) -> Vec<String> {
+        if self.schemas.is_empty() {
+            vec![self.schema.clone()]
+        } else {
+            self.schemas.clone()
+        }
+    }
+
+    /// `migrations_dirs`, or just `migrations_dir` if it is empty.
+    pub fn migration_dirs(&self) -> Vec<PathBuf> {
+        if self.migrations_dirs.is_empty() {
+            vec![self.migrations_dir.clone()]
+        } else {
+            self.migrations_dirs.clone()
+        }
+    }
+}
+
+/// Which advisory lock key serializes runners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdvisoryLock {
+    /// A fixed key; the default is shared by every Schemalane runner on the
+    /// database.
+    Fixed(i64),
+    /// Derived from the schema and history table as Flyway derives it, so
+    /// runners for different history tables do not block each other and
+    /// Schemalane and Flyway runners on the same one do.
+    Derived,
+    /// No lock. Only safe when a single runner is guaranteed.
+    Disabled,
+}
+
+impl Default for AdvisoryLock {
+    fn default() -> Self {
+        Self::Fixed(DEFAULT_ADVISORY_LOCK_ID)
+    }
+}
+
+impl AdvisoryLock {
+    /// Parses `derived` or a numeric key.
+    pub fn parse(value: &str) -> Result<Self, SchemalaneError> {
+        if value == "derived" {
+            return Ok(Self::Derived);
+        }
+        value.parse::<i64>().map(Self::Fixed).map_err(|_| {
+            SchemalaneError::Validation(format!(
+                "invalid lock id '{value}': expected an integer or 'derived'"
+            ))
+        })
+    }
+}
+
+impl std::str::FromStr for AdvisoryLock {
+    type Err = SchemalaneError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse(value)
+    }
+}
+
+impl std::fmt::Display for AdvisoryLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fixed(lock_id) => write!(f, "{lock_id}"),
+            Self::Derived => f.write_str("derived"),
+            Self::Disabled => f.write_str("disabled"),
+        }
+    }
+}
+
+/// Advisory lock key for `AdvisoryLock::Derived`, the one Flyway takes for
+/// the same history table. Advisory locks are per database, so the database
+/// is not part of the key.
+pub fn derive_advisory_lock_id(schema: &str, history_table: &str) -> i64 {
+    // Java's `String.hashCode` over UTF-16 code units.
+    let hash = qualified_table(schema, history_table)
+        .encode_utf16()
+        .fold(0_i32, |hash, unit| {
+            hash.wrapping_mul(31).wrapping_add(i32::from(unit))
+        });
+    DERIVED_ADVISORY_LOCK_BASE + i64::from(hash)
+}
+
+/// How migration checksums are computed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumAlgorithm {
+    /// CRC32 over the raw file bytes.
+    #[default]
+    Crc32,
+    /// Flyway's line-based CRC32: a leading BOM is stripped and line endings
+    /// are ignored, so values match a history table written by Flyway and do
+    /// not change between LF and CRLF checkouts.
+    Flyway,
+}
+
+impl ChecksumAlgorithm {
+    pub fn checksum(self, bytes: &[u8]) -> i32 {
+        match self {
+            Self::Crc32 => calculate_checksum(bytes),
+            Self::Flyway => calculate_flyway_checksum(bytes),
         }
     }
 }
@@ -59,9 +254,54 @@ pub enum SchemalaneError {
     #[error("Failed migration found in history: {0}")]
     FailedHistory(String),
 
-    #[error("Migration execution failed for {script}: {source}")]
+    #[error(
+        "Migration execution failed for {script}{}: {source}{}{}",
+        format_statement_span(.statement.as_deref()),
+        format_database_error(.database_error.as_deref()),
+        format_attempts(*.attempts)
+    )]
     MigrationExecution {
         script: String,
+        /// The failing statement of a SQL migration.
+        statement: Option<Box<StatementSpan>>,
+        database_error: Option<Box<DatabaseErrorDetails>>,
+        /// Attempts made; more than one when `SchemalaneConfig::retry` ran
+        /// out of retries.
+        attempts: u32,
+        #[source]
+        source: DbErr,
+    },
+
+    #[error(
+        "{event} callback {callback} failed{}: {source}{}{}",
+        format_statement_span(.statement.as_deref()),
+        format_database_error(.database_error.as_deref()),
+        format_handled_error(.handling.as_deref())
+    )]
+    Callback {
+        event: CallbackEvent,
+        /// Script of a SQL callback, or the name a Rust callback was
+        /// registered under.
+        callback: String,
+        statement: Option<Box<StatementSpan>>,
+        database_error: Option<Box<DatabaseErrorDetails>>,
+        /// The failure an `afterMigrateError` callback was running for.
+        handling: Option<Box<Self>>,
+        #[source]
+        source: DbErr,
+    },
+
+    #[error(
+        "Migration {script} exceeded its {timeout}{}: {source}{}",
+        format_statement_span(.statement.as_deref()),
+        format_attempts(*.attempts)
+    )]
+    MigrationTimeout {
+        script: String,
+        timeout: MigrationTimeoutKind,
+        statement: Option<Box<StatementSpan>>,
+        /// Attempts made, as for `MigrationExecution`.
+        attempts: u32,
         #[source]
         source: DbErr,
     },
@@ -74,9 +314,191 @@ pub enum SchemalaneError {
 
     #[error("Only PostgreSQL is supported in Schemalane v1")]
     UnsupportedBackend,
+
+    #[error(
+        "Timed out after {} ms waiting for advisory lock {lock_id}{}",
+        .waited.as_millis(),
+        format_lock_holders(.holders)
+    )]
+    LockTimeout {
+        lock_id: i64,
+        waited: Duration,
+        holders: Vec<LockHolder>,
+    },
+}
+
+/// A session holding the advisory lock, from `pg_locks`/`pg_stat_activity`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LockHolder {
+    pub pid: i32,
+    pub application_name: String,
+    pub client_addr: Option<String>,
+}
+
+impl std::fmt::Display for LockHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "pid {} (application_name '{}', client_addr {})",
+            self.pid,
+            self.application_name,
+            self.client_addr.as_deref().unwrap_or("local")
+        )
+    }
+}
+
+fn format_lock_holders(holders: &[LockHolder]) -> String {
+    if holders.is_empty() {
+        return String::new();
+    }
+    let holders: Vec<_> = holders.iter().map(ToString::to_string).collect();
+    format!("; held by {}", holders.join(", "))
+}
+
+/// What the server reported for a failed statement.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DatabaseErrorDetails {
+    /// SQLSTATE, e.g. `42P01`.
+    pub code: String,
+    pub detail: Option<String>,
+    pub hint: Option<String>,
+}
+
+impl DatabaseErrorDetails {
+    fn from_db_err(err: &DbErr) -> Option<Self> {
+        let (DbErr::Exec(RuntimeErr::SqlxError(err)) | DbErr::Query(RuntimeErr::SqlxError(err))) =
+            err
+        else {
+            return None;
+        };
+        let sqlx::Error::Database(err) = &**err else {
+            return None;
+        };
+        let err = err.try_downcast_ref::<sqlx::postgres::PgDatabaseError>()?;
+        Some(Self {
+            code: err.code().to_owned(),
+            detail: err.detail().map(ToOwned::to_owned),
+            hint: err.hint().map(ToOwned::to_owned),
+        })
+    }
+}
+
+/// Which `MigrationTimeouts` setting cancelled a migration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationTimeoutKind {
+    LockTimeout,
+    StatementTimeout,
+}
+
+impl MigrationTimeoutKind {
+    /// SQLSTATE `55P03` (`lock_not_available`) when `applied` put a
+    /// `lock_timeout` on the migration, or `57014` (`query_canceled`) when it
+    /// put a `statement_timeout` on it. Without one, such as for `NOWAIT`,
+    /// the failure is not a timeout of ours.
+    fn from_failure(
+        details: Option<&DatabaseErrorDetails>,
+        applied: MigrationTimeouts,
+    ) -> Option<Self> {
+        match details?.code.as_str() {
+            SQLSTATE_LOCK_NOT_AVAILABLE
+                if applied
+                    .lock_timeout
+                    .is_some_and(|timeout| !timeout.is_zero()) =>
+            {
+                Some(Self::LockTimeout)
+            }
+            SQLSTATE_QUERY_CANCELED
+                if applied
+                    .statement_timeout
+                    .is_some_and(|timeout| !timeout.is_zero()) =>
+            {
+                Some(Self::StatementTimeout)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for MigrationTimeoutKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::LockTimeout => "lock_timeout",
+            Self::StatementTimeout => "statement_timeout",
+        })
+    }
+}
+
+fn format_statement_span(span: Option<&StatementSpan>) -> String {
+    span.map_or_else(String::new, |span| format!(" at {span}"))
+}
+
+fn format_database_error(details: Option<&DatabaseErrorDetails>) -> String {
+    let Some(details) = details else {
+        return String::new();
+    };
+    let mut parts = vec![format!("SQLSTATE {}", details.code)];
+    parts.extend(
+        details
+            .detail
+            .iter()
+            .map(|detail| format!("detail: {detail}")),
+    );
+    parts.extend(details.hint.iter().map(|hint| format!("hint: {hint}")));
+    format!(" ({})", parts.join("; "))
+}
+
+fn format_attempts(attempts: u32) -> String {
+    if attempts > 1 {
+        format!(" (after {attempts} attempts)")
+    } else {
+        String::new()
+    }
+}
+
+fn format_handled_error(handling: Option<&SchemalaneError>) -> String {
+    handling.map_or_else(String::new, |err| format!(" (while handling: {err})"))
 }
 
 impl SchemalaneError {
+    /// Attach the failure an `afterMigrateError` callback was running for.
+    fn while_handling(mut self, failure: Self) -> Self {
+        if let Self::Callback { handling, .. } = &mut self {
+            *handling = Some(Box::new(failure));
+        }
+        self
+    }
+
+    /// Record how many attempts a migration failure took.
+    const fn after_attempts(mut self, total: u32) -> Self {
+        if let Self::MigrationExecution { attempts, .. } | Self::MigrationTimeout { attempts, .. } =
+            &mut self
+        {
+            *attempts = total;
+        }
+        self
+    }
+
+    /// SQLSTATE of a migration failure worth retrying: `55P03`
+    /// (`lock_not_available`) or `40P01` (`deadlock_detected`).
+    pub fn retryable_sqlstate(&self) -> Option<&str> {
+        match self {
+            Self::MigrationTimeout {
+                timeout: MigrationTimeoutKind::LockTimeout,
+                ..
+            } => Some(SQLSTATE_LOCK_NOT_AVAILABLE),
+            Self::MigrationExecution {
+                database_error: Some(details),
+                ..
+            } if details.code == SQLSTATE_LOCK_NOT_AVAILABLE
+                || details.code == SQLSTATE_DEADLOCK_DETECTED =>
+            {
+                Some(&details.code)
+            }
+            _ => None,
+        }
+    }
+
     pub const fn exit_code(&self) -> i32 {
         match self {
             Self::Validation(_) => 2,
@@ -84,9 +506,34 @@ impl SchemalaneError {
             Self::FailedHistory(_) => 4,
             Self::PendingMigrations(_) => 5,
             Self::FreshRequiresYes => 6,
+            Self::LockTimeout { .. } => 7,
+            Self::MigrationTimeout { .. } => 8,
             _ => 1,
         }
     }
+
+    /// The error a run over several databases or tenants exits with. Errors
+    /// that stopped a run (runtime errors, then migration and lock timeouts)
+    /// outrank what `validate` finds, which ranks as in `ensure_valid`:
+    /// invalid migrations, failed history, then drift. Pending migrations
+    /// rank last. Ties go to the earliest.
+    pub fn most_severe(errors: impl IntoIterator<Item = Self>) -> Option<Self> {
+        errors.into_iter().min_by_key(Self::severity_rank)
+    }
+
+    /// 0 is the most severe.
+    const fn severity_rank(&self) -> u8 {
+        match self {
+            Self::MigrationTimeout { .. } => 1,
+            Self::LockTimeout { .. } => 2,
+            Self::Validation(_) => 3,
+            Self::FreshRequiresYes => 4,
+            Self::FailedHistory(_) => 5,
+            Self::Drift(_) => 6,
+            Self::PendingMigrations(_) => 7,
+            _ => 0,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
@@ -97,6 +544,10 @@ pub enum MigrationState {
     Failed,
     Missing,
     ChecksumMismatch,
+    BelowBaseline,
+    Outdated,
+    Superseded,
+    Ignored,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -110,9 +561,49 @@ pub struct StatusEntry {
     pub installed_rank: Option<i32>,
     pub installed_on: Option<String>,
     pub execution_time_ms: Option<i32>,
+    /// The mode recorded in history, or for pending migrations the mode
+    /// they will run in. `None` for rows written without one.
+    pub transaction_mode: Option<TransactionMode>,
     pub state: MigrationState,
 }
 
+impl StatusEntry {
+    fn from_migration(
+        migration: &DiscoveredMigration,
+        row: Option<&HistoryRow>,
+        state: MigrationState,
+    ) -> Self {
+        Self {
+            version: migration.version_text.clone(),
+            description: migration.description.clone(),
+            migration_type: migration.migration_type.as_history_type().to_owned(),
+            script: migration.script.clone(),
+            checksum: migration.checksum,
+            installed_rank: row.map(|row| row.installed_rank),
+            installed_on: row.map(|row| row.installed_on.clone()),
+            execution_time_ms: row.map(|row| row.execution_time),
+            transaction_mode: row
+                .map_or(Some(migration.transaction_mode), |row| row.transaction_mode),
+            state,
+        }
+    }
+
+    fn from_history(row: &HistoryRow, state: MigrationState) -> Self {
+        Self {
+            version: row.version.clone(),
+            description: row.description.clone(),
+            migration_type: row.migration_type.clone(),
+            script: row.script.clone(),
+            checksum: row.checksum,
+            installed_rank: Some(row.installed_rank),
+            installed_on: Some(row.installed_on.clone()),
+            execution_time_ms: Some(row.execution_time),
+            transaction_mode: row.transaction_mode,
+            state,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Default)]
 pub struct StatusSummary {
     pub success: usize,
@@ -120,19 +611,25 @@ pub struct StatusSummary {
     pub failed: usize,
     pub missing: usize,
     pub checksum_mismatch: usize,
+    pub below_baseline: usize,
+    pub outdated: usize,
+    pub superseded: usize,
+    pub ignored: usize,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct StatusReport {
     pub schema: String,
     pub history_table: String,
+    /// Advisory lock key runners use; `None` when locking is disabled.
+    pub advisory_lock_id: Option<i64>,
     pub migrations: Vec<StatusEntry>,
     pub summary: StatusSummary,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct AppliedMigration {
-    pub version: String,
+    pub version: Option<String>,
     pub description: String,
     #[serde(rename = "type")]
     pub migration_type: String,
@@ -140,108 +637,502 @@ pub struct AppliedMigration {
     pub execution_time_ms: i32,
 }
 
+impl AppliedMigration {
+    fn from_migration(migration: &DiscoveredMigration, execution_time_ms: i32) -> Self {
+        Self {
+            version: migration.version_text.clone(),
+            description: migration.description.clone(),
+            migration_type: migration.migration_type.as_history_type().to_owned(),
+            script: migration.script.clone(),
+            execution_time_ms,
+        }
+    }
+}
+
+/// A migration whose transaction was rolled back, not yet recorded in history.
+struct FailedMigration<'m> {
+    migration: &'m DiscoveredMigration,
+    execution_time_ms: i32,
+    error: SchemalaneError,
+}
+
+/// A pending migration that `up` left alone because of its target.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnappliedMigration {
+    pub version: Option<String>,
+    pub description: String,
+    #[serde(rename = "type")]
+    pub migration_type: String,
+    pub script: String,
+}
+
+impl UnappliedMigration {
+    fn from_migration(migration: &DiscoveredMigration) -> Self {
+        Self {
+            version: migration.version_text.clone(),
+            description: migration.description.clone(),
+            migration_type: migration.migration_type.as_history_type().to_owned(),
+            script: migration.script.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Default)]
 pub struct RunReport {
     pub applied: Vec<AppliedMigration>,
     pub skipped: usize,
+    /// Pending migrations beyond the `MigrationTarget`.
+    pub unapplied: Vec<UnappliedMigration>,
+    /// Attempts rolled back and retried under `SchemalaneConfig::retry`.
+    pub retries: Vec<MigrationRetry>,
+    /// Objects `fresh` dropped before applying migrations.
+    pub dropped_objects: Vec<SchemaObject>,
+    /// Managed schemas that did not exist and were created.
+    pub created_schemas: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Default)]
-pub struct InitReport {
-    pub root: PathBuf,
-    pub created: Vec<PathBuf>,
-    pub overwritten: Vec<PathBuf>,
+/// A transactional migration attempt that failed on a lock or a deadlock
+/// and was rolled back to be tried again.
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationRetry {
+    pub script: String,
+    /// 1 for the first attempt.
+    pub attempt: u32,
+    pub sqlstate: String,
+    pub error: String,
+    /// How long `up` waited before the next attempt.
+    pub backoff_ms: u64,
 }
 
-pub fn init_migration_project(path: &Path, force: bool) -> Result<InitReport, SchemalaneError> {
-    if path.exists() {
-        if !path.is_dir() {
-            return Err(SchemalaneError::Validation(format!(
-                "init target exists and is not a directory: {}",
-                path.display()
-            )));
-        }
+/// What `up` or `fresh` would do, produced by a dry run without executing
+/// any migration or writing to the history table.
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationPlan {
+    pub schema: String,
+    pub history_table: String,
+    /// Objects `fresh` would drop, in drop order; always empty for `up`.
+    pub dropped_objects: Vec<SchemaObject>,
+    /// Migrations in execution order, each with the history row it would add.
+    pub steps: Vec<PlannedMigration>,
+    pub skipped: usize,
+    pub unapplied: Vec<UnappliedMigration>,
+}
 
-        let has_entries = std::fs::read_dir(path)?.next().is_some();
-        if has_entries && !force {
-            return Err(SchemalaneError::Validation(format!(
-                "init target directory is not empty: {} (use --force to overwrite)",
-                path.display()
-            )));
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedMigration {
+    pub installed_rank: i32,
+    pub version: Option<String>,
+    pub description: String,
+    #[serde(rename = "type")]
+    pub migration_type: String,
+    pub script: String,
+    pub checksum: Option<i32>,
+    pub installed_by: String,
+    pub transaction_mode: TransactionMode,
+    /// Full script text; `None` for Rust migrations, which are opaque.
+    pub sql: Option<String>,
+}
+
+impl PlannedMigration {
+    fn from_migration(
+        migration: &DiscoveredMigration,
+        installed_rank: i32,
+        installed_by: &str,
+    ) -> Self {
+        Self {
+            installed_rank,
+            version: migration.version_text.clone(),
+            description: migration.description.clone(),
+            migration_type: migration.migration_type.as_history_type().to_owned(),
+            script: migration.script.clone(),
+            checksum: migration.checksum,
+            installed_by: installed_by.to_owned(),
+            transaction_mode: migration.transaction_mode,
+            sql: match &migration.body {
+                MigrationBody::Sql(sql) => Some(sql.clone().into_owned()),
+                MigrationBody::Rust => None,
+            },
         }
-    } else {
-        std::fs::create_dir_all(path)?;
     }
+}
 
-    let package_name = sanitize_package_name(
-        path.file_name()
-            .and_then(|name| name.to_str())
-            .unwrap_or("migration"),
-    );
-    let lib_ident = package_to_lib_ident(&package_name);
+/// How far `up_to` migrates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MigrationTarget {
+    /// Apply every pending migration.
+    #[default]
+    Latest,
+    /// Apply pending versioned migrations up to and including this version.
+    /// Repeatable migrations still run, as in Flyway.
+    Version(ParsedVersion),
+    /// Apply only the next `n` pending migrations, in execution order.
+    Steps(usize),
+}
 
-    let files = init_template_files(&package_name, &lib_ident);
-    let mut created = Vec::new();
-    let mut overwritten = Vec::new();
+impl MigrationTarget {
+    /// Parses `latest`, `next`, `<n>-steps` or a migration version.
+    pub fn parse(value: &str) -> Result<Self, SchemalaneError> {
+        match value {
+            "latest" => return Ok(Self::Latest),
+            "next" => return Ok(Self::Steps(1)),
+            _ => {}
+        }
 
-    for (relative, content) in files {
-        let target = path.join(relative);
-        write_init_file(&target, &content, force, &mut created, &mut overwritten)?;
+        let Some(steps) = value.strip_suffix("-steps") else {
+            return ParsedVersion::parse(value).map(Self::Version);
+        };
+        match steps.parse::<usize>() {
+            Ok(steps) if steps > 0 => Ok(Self::Steps(steps)),
+            _ => Err(SchemalaneError::Validation(format!(
+                "invalid target '{value}': expected a positive number of steps"
+            ))),
+        }
     }
+}
 
-    created.sort();
-    overwritten.sort();
+impl std::str::FromStr for MigrationTarget {
+    type Err = SchemalaneError;
 
-    Ok(InitReport {
-        root: path.to_path_buf(),
-        created,
-        overwritten,
-    })
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse(value)
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum RustTransactionMode {
-    NoTransaction,
-    Transaction,
+impl std::fmt::Display for MigrationTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Latest => f.write_str("latest"),
+            Self::Version(version) => {
+                let segments: Vec<_> = version.segments().iter().map(u64::to_string).collect();
+                f.write_str(&segments.join("."))
+            }
+            Self::Steps(steps) => write!(f, "{steps}-steps"),
+        }
+    }
 }
 
-pub type RustMigrationFuture<'a> = Pin<Box<dyn Future<Output = Result<(), DbErr>> + Send + 'a>>;
-
-type DynRustMigrationFn =
-    dyn for<'a> Fn(&'a SchemaManager<'a>) -> RustMigrationFuture<'a> + Send + Sync;
-
-#[derive(Clone)]
-pub struct RustMigrationExecutor {
-    transaction_mode: RustTransactionMode,
-    run: Arc<DynRustMigrationFn>,
+#[derive(Debug, Clone, Serialize)]
+pub struct BaselineReport {
+    pub version: String,
+    pub description: String,
+    pub installed_rank: i32,
 }
 
-impl RustMigrationExecutor {
-    pub fn new<F>(run: F) -> Self
-    where
+#[derive(Debug, Clone, Serialize)]
+pub struct RepairedMigration {
+    pub version: Option<String>,
+    pub description: String,
+    pub script: String,
+    pub installed_rank: i32,
+}
+
+impl RepairedMigration {
+    fn from_history(row: &HistoryRow) -> Self {
+        Self {
+            version: row.version.clone(),
+            description: row.description.clone(),
+            script: row.script.clone(),
+            installed_rank: row.installed_rank,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct RepairReport {
+    pub removed_failed: Vec<RepairedMigration>,
+    pub realigned: Vec<RepairedMigration>,
+    pub marked_deleted: Vec<RepairedMigration>,
+}
+
+/// Every problem `validate` found; empty when `up` could proceed.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub const fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    pub kind: ValidationIssueKind,
+    pub script: Option<String>,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn new(kind: ValidationIssueKind, script: Option<&str>, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            script: script.map(str::to_owned),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum ValidationIssueKind {
+    /// Discovery failed, e.g. on an invalid filename.
+    InvalidMigration,
+    DuplicateMigration,
+    MissingRustExecutor,
+    /// Pending migration older than the latest applied one while
+    /// `out_of_order` is off.
+    OutOfOrder,
+    /// Pending SQL migration or SQL callback uses a placeholder with no
+    /// value.
+    UnresolvedPlaceholder,
+    Failed,
+    Missing,
+    ChecksumMismatch,
+}
+
+impl ValidationIssueKind {
+    const fn is_drift(self) -> bool {
+        matches!(self, Self::Missing | Self::ChecksumMismatch)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct InitReport {
+    pub root: PathBuf,
+    pub created: Vec<PathBuf>,
+    pub overwritten: Vec<PathBuf>,
+}
+
+pub fn init_migration_project(path: &Path, force: bool) -> Result<InitReport, SchemalaneError> {
+    if path.exists() {
+        if !path.is_dir() {
+            return Err(SchemalaneError::Validation(format!(
+                "init target exists and is not a directory: {}",
+                path.display()
+            )));
+        }
+
+        let has_entries = std::fs::read_dir(path)?.next().is_some();
+        if has_entries && !force {
+            return Err(SchemalaneError::Validation(format!(
+                "init target directory is not empty: {} (use --force to overwrite)",
+                path.display()
+            )));
+        }
+    } else {
+        std::fs::create_dir_all(path)?;
+    }
+
+    let package_name = sanitize_package_name(
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("migration"),
+    );
+    let lib_ident = package_to_lib_ident(&package_name);
+
+    let files = init_template_files(&package_name, &lib_ident);
+    let mut created = Vec::new();
+    let mut overwritten = Vec::new();
+
+    for (relative, content) in files {
+        let target = path.join(relative);
+        write_init_file(&target, &content, force, &mut created, &mut overwritten)?;
+    }
+
+    created.sort();
+    overwritten.sort();
+
+    Ok(InitReport {
+        root: path.to_path_buf(),
+        created,
+        overwritten,
+    })
+}
+
+/// Whether a migration runs inside a transaction.
+///
+/// SQL migrations default to `Transaction` and opt out with a
+/// `-- schemalane:no-transaction` header or an `executeInTransaction=false`
+/// sidecar; Rust migrations take the mode of their executor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionMode {
+    NoTransaction,
+    Transaction,
+}
+
+impl TransactionMode {
+    const fn as_label(self) -> &'static str {
+        match self {
+            Self::NoTransaction => "no-txn",
+            Self::Transaction => "txn",
+        }
+    }
+}
+
+/// `lock_timeout` and `statement_timeout` for a migration's transaction,
+/// applied with `SET LOCAL`. `None` leaves the server setting alone and
+/// `Some(Duration::ZERO)` disables the timeout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MigrationTimeouts {
+    pub lock_timeout: Option<Duration>,
+    pub statement_timeout: Option<Duration>,
+}
+
+impl MigrationTimeouts {
+    pub const fn is_set(&self) -> bool {
+        self.lock_timeout.is_some() || self.statement_timeout.is_some()
+    }
+
+    /// These timeouts, falling back to `defaults` for unset ones.
+    #[must_use]
+    pub const fn or(self, defaults: Self) -> Self {
+        Self {
+            lock_timeout: match self.lock_timeout {
+                Some(timeout) => Some(timeout),
+                None => defaults.lock_timeout,
+            },
+            statement_timeout: match self.statement_timeout {
+                Some(timeout) => Some(timeout),
+                None => defaults.statement_timeout,
+            },
+        }
+    }
+
+    /// `SET LOCAL` statements for both settings; unset ones go back to the
+    /// server default so a previous migration's value does not carry over.
+    fn set_local_sql(&self) -> String {
+        let setting = |name: &str, timeout: Option<Duration>| {
+            timeout.map_or_else(
+                || format!("SET LOCAL {name} TO DEFAULT"),
+                |timeout| format!("SET LOCAL {name} = '{}'", timeout_setting(timeout)),
+            )
+        };
+        format!(
+            "{}; {}",
+            setting("lock_timeout", self.lock_timeout),
+            setting("statement_timeout", self.statement_timeout)
+        )
+    }
+}
+
+/// How often `up` and `fresh` retry a transactional migration that failed
+/// with SQLSTATE `55P03` (`lock_not_available`) or `40P01`
+/// (`deadlock_detected`), and how long they wait in between.
+///
+/// The wait starts at `initial_backoff` and doubles after each retry, up to
+/// `max_backoff`. In group mode the whole group is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying.
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The wait after failed attempt `attempt`, counting from 1.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+const TIMEOUT_FORMAT: &str = "expected a duration such as 500ms, 5s, 2min or 1h";
+
+/// Parses a timeout such as `500ms`, `5s`, `2min` or `1h`; a bare number is
+/// milliseconds, as in Postgres.
+pub fn parse_timeout(value: &str) -> Result<Duration, SchemalaneError> {
+    parse_timeout_value(value).ok_or_else(|| {
+        SchemalaneError::Validation(format!("invalid timeout '{value}': {TIMEOUT_FORMAT}"))
+    })
+}
+
+fn parse_timeout_value(value: &str) -> Option<Duration> {
+    let digits = value
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(digits);
+    let amount = amount.parse::<u64>().ok()?;
+    let millis = match unit.trim() {
+        "" | "ms" => 1,
+        "s" => 1_000,
+        "min" => 60_000,
+        "h" => 3_600_000,
+        _ => return None,
+    };
+    amount.checked_mul(millis).map(Duration::from_millis)
+}
+
+/// Former name of `TransactionMode`, from when only Rust migrations had one.
+pub type RustTransactionMode = TransactionMode;
+
+pub type RustMigrationFuture<'a> = Pin<Box<dyn Future<Output = Result<(), DbErr>> + Send + 'a>>;
+
+type DynRustMigrationFn =
+    dyn for<'a> Fn(&'a SchemaManager<'a>) -> RustMigrationFuture<'a> + Send + Sync;
+
+#[derive(Clone)]
+pub struct RustMigrationExecutor {
+    transaction_mode: TransactionMode,
+    timeouts: MigrationTimeouts,
+    run: Arc<DynRustMigrationFn>,
+}
+
+impl RustMigrationExecutor {
+    pub fn new<F>(run: F) -> Self
+    where
         F: for<'a> Fn(&'a SchemaManager<'a>) -> RustMigrationFuture<'a> + Send + Sync + 'static,
     {
-        Self::with_mode(RustTransactionMode::NoTransaction, run)
+        Self::with_mode(TransactionMode::NoTransaction, run)
     }
 
     pub fn transactional<F>(run: F) -> Self
     where
         F: for<'a> Fn(&'a SchemaManager<'a>) -> RustMigrationFuture<'a> + Send + Sync + 'static,
     {
-        Self::with_mode(RustTransactionMode::Transaction, run)
+        Self::with_mode(TransactionMode::Transaction, run)
     }
 
-    pub fn with_mode<F>(transaction_mode: RustTransactionMode, run: F) -> Self
+    pub fn with_mode<F>(transaction_mode: TransactionMode, run: F) -> Self
     where
         F: for<'a> Fn(&'a SchemaManager<'a>) -> RustMigrationFuture<'a> + Send + Sync + 'static,
     {
         Self {
             transaction_mode,
+            timeouts: MigrationTimeouts::default(),
             run: Arc::new(run),
         }
     }
 
-    const fn transaction_mode(&self) -> RustTransactionMode {
+    /// Override `SchemalaneConfig::migration_timeouts` for this migration.
+    /// Only applies to transactional executors.
+    #[must_use]
+    pub const fn with_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.lock_timeout = Some(timeout);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_statement_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.statement_timeout = Some(timeout);
+        self
+    }
+
+    const fn transaction_mode(&self) -> TransactionMode {
         self.transaction_mode
     }
 
@@ -250,16 +1141,34 @@ impl RustMigrationExecutor {
     }
 }
 
+#[derive(Clone)]
 pub struct SchemalaneMigrator {
     config: SchemalaneConfig,
     rust_migrations: HashMap<String, RustMigrationExecutor>,
+    callbacks: Vec<RegisteredCallback>,
+    source: Option<Arc<dyn MigrationSource>>,
+}
+
+#[derive(Clone)]
+struct RegisteredCallback {
+    event: CallbackEvent,
+    name: String,
+    callback: RustCallback,
 }
 
 impl SchemalaneMigrator {
-    pub fn new(config: SchemalaneConfig) -> Self {
+    pub fn new(mut config: SchemalaneConfig) -> Self {
+        if let Some(first) = config.schemas.first() {
+            config.schema.clone_from(first);
+        }
+        if let Some(first) = config.migrations_dirs.first() {
+            config.migrations_dir.clone_from(first);
+        }
         Self {
             config,
             rust_migrations: HashMap::new(),
+            callbacks: Vec::new(),
+            source: None,
         }
     }
 
@@ -267,6 +1176,22 @@ impl SchemalaneMigrator {
         &self.config
     }
 
+    /// A copy of this migrator, with the same migrations and callbacks,
+    /// that manages `schema` alone, keeps its history there and runs its
+    /// migrations with `schema` first on the `search_path`. A fixed
+    /// advisory lock becomes a derived one so that tenants do not wait on
+    /// each other.
+    #[must_use]
+    pub fn for_tenant(&self, schema: &str) -> Self {
+        let mut tenant = self.clone();
+        schema.clone_into(&mut tenant.config.schema);
+        tenant.config.schemas.clear();
+        if matches!(tenant.config.advisory_lock, AdvisoryLock::Fixed(_)) {
+            tenant.config.advisory_lock = AdvisoryLock::Derived;
+        }
+        tenant
+    }
+
     pub fn register_rust_migration<S>(&mut self, script: S, migration: RustMigrationExecutor)
     where
         S: Into<String>,
@@ -296,177 +1221,521 @@ impl SchemalaneMigrator {
         self
     }
 
+    /// Run `callback` at `event` during `up` and `fresh`, after any SQL
+    /// callback scripts for the same event. `name` identifies it in errors.
+    pub fn register_callback<S>(&mut self, event: CallbackEvent, name: S, callback: RustCallback)
+    where
+        S: Into<String>,
+    {
+        self.callbacks.push(RegisteredCallback {
+            event,
+            name: name.into(),
+            callback,
+        });
+    }
+
+    #[must_use]
+    pub fn with_callback<S>(mut self, event: CallbackEvent, name: S, callback: RustCallback) -> Self
+    where
+        S: Into<String>,
+    {
+        self.register_callback(event, name, callback);
+        self
+    }
+
+    /// Replace the default filesystem discovery of `migrations_dirs` with a
+    /// custom migration source.
+    pub fn register_source<M>(&mut self, source: M)
+    where
+        M: MigrationSource + 'static,
+    {
+        self.source = Some(Arc::new(source));
+    }
+
+    #[must_use]
+    pub fn with_source<M>(mut self, source: M) -> Self
+    where
+        M: MigrationSource + 'static,
+    {
+        self.register_source(source);
+        self
+    }
+
+    /// Discover migrations from the embedded entries instead of reading
+    /// `migrations_dir` from disk.
+    #[must_use]
+    pub fn with_embedded_migrations(self, migrations: &'static [EmbeddedMigration]) -> Self {
+        self.with_source(InMemoryMigrationSource::from_embedded(migrations))
+    }
+
     pub async fn up(&self, db: &DatabaseConnection) -> Result<RunReport, SchemalaneError> {
+        self.up_to(db, &MigrationTarget::Latest).await
+    }
+
+    /// Like `up`, but stops at `target`; pending migrations past it are listed
+    /// in `RunReport::unapplied`.
+    pub async fn up_to(
+        &self,
+        db: &DatabaseConnection,
+        target: &MigrationTarget,
+    ) -> Result<RunReport, SchemalaneError> {
         Self::ensure_postgres(db)?;
         let migrations = self.discover_migrations()?;
         self.ensure_rust_executors_registered(&migrations)?;
+        ensure_target_exists(&migrations, target)?;
         self.with_advisory_lock(db, async {
-            self.ensure_history_table(db).await?;
+            let created_schemas = self.ensure_schemas(db).await?;
             let installed_by = self.resolve_installed_by(db).await?;
-            let mut history = self.load_history(db).await?;
+            let history = self.load_history(db).await?;
             Self::ensure_no_blocking_history(&migrations, &history)?;
-
-            let mut report = RunReport::default();
-            for migration in &migrations {
-                if is_applied_success(migration, &history) {
-                    report.skipped += 1;
-                    continue;
-                }
-
-                let started = Instant::now();
-                let run_result = self.apply_migration(db, migration).await;
-                let execution_time_ms = millis_i32(started.elapsed().as_millis());
-
-                match run_result {
-                    Ok(()) => {
-                        let installed_rank = self
-                            .insert_history_row(
-                                db,
-                                migration,
+            self.ensure_in_order(&migrations, &history)?;
+
+            let plan = plan_up(&migrations, &history, target);
+            self.ensure_groupable(&plan.pending)?;
+            let placeholders = self.placeholder_values(db).await?;
+            let rendered = self.render_placeholders(&plan.pending, &placeholders)?;
+            let pending: Vec<_> = rendered.iter().collect();
+            let callbacks = self.sql_callbacks(&placeholders)?;
+            let mut retries = Vec::new();
+            let session = self.pinned_session(db).await?;
+            let applied = self
+                .with_migrate_callbacks(
+                    &session,
+                    &callbacks,
+                    Box::pin(async {
+                        if self.config.group_transactions {
+                            self.apply_in_transaction(
+                                &session,
+                                &pending,
                                 &installed_by,
-                                execution_time_ms,
-                                true,
+                                &callbacks,
+                                &mut retries,
                             )
-                            .await?;
-                        history.push(HistoryRow::from_migration(
-                            migration,
-                            execution_time_ms,
-                            true,
-                            installed_rank,
-                        ));
-                        report.applied.push(AppliedMigration {
-                            version: migration.version_text.clone(),
-                            description: migration.description_display.clone(),
-                            migration_type: migration.migration_type.as_history_type().to_owned(),
-                            script: migration.script.clone(),
-                            execution_time_ms,
-                        });
-                    }
-                    Err(source) => {
-                        self.insert_history_row(
-                            db,
-                            migration,
-                            &installed_by,
-                            execution_time_ms,
-                            false,
-                        )
-                        .await?;
-                        return Err(SchemalaneError::MigrationExecution {
-                            script: migration.script.clone(),
-                            source,
-                        });
-                    }
-                }
-            }
-
-            Ok(report)
+                            .await
+                        } else {
+                            self.apply_each(
+                                &session,
+                                &pending,
+                                &installed_by,
+                                &callbacks,
+                                &mut retries,
+                            )
+                            .await
+                        }
+                    }),
+                )
+                .await;
+            let applied = Self::close_session(session, applied).await?;
+
+            Ok(RunReport {
+                applied,
+                skipped: plan.skipped,
+                unapplied: plan
+                    .unapplied
+                    .iter()
+                    .map(|migration| UnappliedMigration::from_migration(migration))
+                    .collect(),
+                retries,
+                dropped_objects: Vec::new(),
+                created_schemas,
+            })
         })
         .await
     }
 
-    pub async fn status(&self, db: &DatabaseConnection) -> Result<StatusReport, SchemalaneError> {
+    /// Plans `up_to` without running it: discovery, history validation and the
+    /// advisory lock are the same, but nothing is executed or written.
+    pub async fn dry_run_up(
+        &self,
+        db: &DatabaseConnection,
+        target: &MigrationTarget,
+    ) -> Result<MigrationPlan, SchemalaneError> {
         Self::ensure_postgres(db)?;
         let migrations = self.discover_migrations()?;
+        self.ensure_rust_executors_registered(&migrations)?;
+        ensure_target_exists(&migrations, target)?;
+        self.with_advisory_lock(db, async {
+            let history = if self.history_table_exists(db).await? {
+                self.load_history(db).await?
+            } else {
+                Vec::new()
+            };
+            Self::ensure_no_blocking_history(&migrations, &history)?;
+            self.ensure_in_order(&migrations, &history)?;
+            let installed_by = self.resolve_installed_by(db).await?;
 
-        let history = if self.history_table_exists(db).await? {
-            self.load_history(db).await?
-        } else {
-            Vec::new()
-        };
-
-        Ok(build_status_report(
-            &self.config.schema,
-            &self.config.history_table,
-            &migrations,
-            &history,
-        ))
+            let plan = plan_up(&migrations, &history, target);
+            self.ensure_groupable(&plan.pending)?;
+            let placeholders = self.placeholder_values(db).await?;
+            let rendered = self.render_placeholders(&plan.pending, &placeholders)?;
+            let pending: Vec<_> = rendered.iter().collect();
+            let next_rank = history
+                .iter()
+                .map(|row| row.installed_rank)
+                .max()
+                .unwrap_or(0)
+                + 1;
+            Ok(MigrationPlan {
+                schema: self.config.schema.clone(),
+                history_table: self.config.history_table.clone(),
+                dropped_objects: Vec::new(),
+                steps: planned_steps(&pending, next_rank, &installed_by),
+                skipped: plan.skipped,
+                unapplied: plan
+                    .unapplied
+                    .iter()
+                    .map(|migration| UnappliedMigration::from_migration(migration))
+                    .collect(),
+            })
+        })
+        .await
     }
 
-    pub async fn fresh(
+    /// Plans `fresh` without dropping anything or running migrations.
+    pub async fn dry_run_fresh(
         &self,
         db: &DatabaseConnection,
-        confirmed: bool,
-    ) -> Result<RunReport, SchemalaneError> {
-        if !confirmed {
-            return Err(SchemalaneError::FreshRequiresYes);
-        }
-
+    ) -> Result<MigrationPlan, SchemalaneError> {
         Self::ensure_postgres(db)?;
         let migrations = self.discover_migrations()?;
         self.ensure_rust_executors_registered(&migrations)?;
 
         self.with_advisory_lock(db, async {
-            self.drop_all_tables(db).await?;
-            self.ensure_history_table(db).await?;
-
             let installed_by = self.resolve_installed_by(db).await?;
-            let mut report = RunReport::default();
-
-            for migration in &migrations {
-                let started = Instant::now();
-                let run_result = self.apply_migration(db, migration).await;
-                let execution_time_ms = millis_i32(started.elapsed().as_millis());
-
-                match run_result {
-                    Ok(()) => {
-                        self.insert_history_row(
-                            db,
-                            migration,
-                            &installed_by,
-                            execution_time_ms,
-                            true,
-                        )
-                        .await?;
-                        report.applied.push(AppliedMigration {
-                            version: migration.version_text.clone(),
-                            description: migration.description_display.clone(),
-                            migration_type: migration.migration_type.as_history_type().to_owned(),
-                            script: migration.script.clone(),
-                            execution_time_ms,
-                        });
-                    }
-                    Err(source) => {
-                        self.insert_history_row(
-                            db,
-                            migration,
-                            &installed_by,
-                            execution_time_ms,
-                            false,
-                        )
-                        .await?;
-                        return Err(SchemalaneError::MigrationExecution {
-                            script: migration.script.clone(),
-                            source,
-                        });
-                    }
-                }
-            }
-
-            Ok(report)
+            let placeholders = self.placeholder_values(db).await?;
+            let rendered =
+                self.render_placeholders(&migrations.iter().collect::<Vec<_>>(), &placeholders)?;
+            let pending: Vec<_> = rendered.iter().collect();
+            Ok(MigrationPlan {
+                schema: self.config.schema.clone(),
+                history_table: self.config.history_table.clone(),
+                dropped_objects: self.schema_objects_to_drop(db).await?,
+                steps: planned_steps(&pending, 1, &installed_by),
+                skipped: 0,
+                unapplied: Vec::new(),
+            })
         })
         .await
     }
 
-    async fn with_advisory_lock<T, F>(
+    /// Read-only check of everything `up` validates, collecting every problem
+    /// instead of stopping at the first. Takes no lock and never creates the
+    /// history table; pass the report to `ensure_valid` for the exit status.
+    pub async fn validate(
         &self,
         db: &DatabaseConnection,
-        fut: F,
-    ) -> Result<T, SchemalaneError>
-    where
-        F: Future<Output = Result<T, SchemalaneError>>,
+    ) -> Result<ValidationReport, SchemalaneError> {
+        Self::ensure_postgres(db)?;
+
+        let history = if self.history_table_exists(db).await? {
+            self.load_history(db).await?
+        } else {
+            Vec::new()
+        };
+
+        let discovery = match self.discover_unchecked() {
+            Ok(discovery) => discovery,
+            Err(SchemalaneError::Validation(message)) => {
+                // Without the local set, e.g. when the directory is missing,
+                // only failed rows can be judged.
+                let mut issues = vec![ValidationIssue::new(
+                    ValidationIssueKind::InvalidMigration,
+                    None,
+                    message,
+                )];
+                issues.extend(
+                    history_issues(&[], &history)
+                        .into_iter()
+                        .filter(|issue| issue.kind == ValidationIssueKind::Failed),
+                );
+                return Ok(ValidationReport { issues });
+            }
+            Err(err) => return Err(err),
+        };
+        let placeholders = self.placeholder_values(db).await?;
+        let callbacks = self.discover_callbacks()?;
+        Ok(ValidationReport {
+            issues: self.validation_issues(&discovery, &history, &placeholders, &callbacks),
+        })
+    }
+
+    /// Every problem `validate` reports. Invalid files are reported one by
+    /// one and the other checks run on the migrations that could be read.
+    fn validation_issues(
+        &self,
+        discovery: &Discovery,
+        history: &[HistoryRow],
+        placeholders: &HashMap<String, String>,
+        callbacks: &[SqlCallback],
+    ) -> Vec<ValidationIssue> {
+        let migrations = &discovery.migrations;
+        let mut issues: Vec<_> = discovery
+            .errors
+            .iter()
+            .map(|error| {
+                ValidationIssue::new(
+                    ValidationIssueKind::InvalidMigration,
+                    Some(&error.script),
+                    error.message.clone(),
+                )
+            })
+            .collect();
+        issues.extend(duplicate_issues(migrations));
+        issues.extend(
+            self.missing_rust_executors(migrations)
+                .into_iter()
+                .map(|script| {
+                    ValidationIssue::new(
+                        ValidationIssueKind::MissingRustExecutor,
+                        Some(script),
+                        "no Rust migration executor registered",
+                    )
+                }),
+        );
+        if !self.config.out_of_order
+            && let Some(max_applied) = max_applied_version(history)
+        {
+            let max_applied = MigrationTarget::Version(max_applied);
+            issues.extend(
+                out_of_order_migrations(migrations, history)
+                    .into_iter()
+                    .map(|migration| {
+                        ValidationIssue::new(
+                            ValidationIssueKind::OutOfOrder,
+                            Some(&migration.script),
+                            format!("older than the latest applied version {max_applied}"),
+                        )
+                    }),
+            );
+        }
+        let pending = plan_up(migrations, history, &MigrationTarget::Latest).pending;
+        let scripts = pending
+            .iter()
+            .filter_map(|migration| match &migration.body {
+                MigrationBody::Sql(sql) => Some((migration.script.as_str(), sql.as_ref())),
+                MigrationBody::Rust => None,
+            })
+            .chain(
+                callbacks
+                    .iter()
+                    .map(|callback| (callback.script.as_str(), callback.sql.as_ref())),
+            );
+        issues.extend(scripts.filter_map(|(script, sql)| {
+            let names = self.replace_placeholders(sql, placeholders).err()?;
+            Some(ValidationIssue::new(
+                ValidationIssueKind::UnresolvedPlaceholder,
+                Some(script),
+                format!("unresolved placeholders: {}", names.join(", ")),
+            ))
+        }));
+        issues.extend(history_issues(migrations, history));
+        issues
+    }
+
+    pub async fn status(&self, db: &DatabaseConnection) -> Result<StatusReport, SchemalaneError> {
+        Self::ensure_postgres(db)?;
+        let migrations = self.discover_migrations()?;
+
+        let history = if self.history_table_exists(db).await? {
+            self.load_history(db).await?
+        } else {
+            Vec::new()
+        };
+
+        let mut report = build_status_report(
+            &self.config.schema,
+            &self.config.history_table,
+            &migrations,
+            &history,
+            self.config.out_of_order,
+        );
+        report.advisory_lock_id = self.advisory_lock_id();
+        Ok(report)
+    }
+
+    pub async fn fresh(
+        &self,
+        db: &DatabaseConnection,
+        confirmed: bool,
+    ) -> Result<RunReport, SchemalaneError> {
+        if !confirmed {
+            return Err(SchemalaneError::FreshRequiresYes);
+        }
+
+        Self::ensure_postgres(db)?;
+        let migrations = self.discover_migrations()?;
+        self.ensure_rust_executors_registered(&migrations)?;
+
+        self.with_advisory_lock(db, async {
+            // Resolve placeholders first so a missing one fails before the drop.
+            let placeholders = self.placeholder_values(db).await?;
+            let rendered =
+                self.render_placeholders(&migrations.iter().collect::<Vec<_>>(), &placeholders)?;
+            let callbacks = self.sql_callbacks(&placeholders)?;
+            let dropped_objects = self.drop_schema_objects(db).await?;
+            let created_schemas = self.ensure_schemas(db).await?;
+
+            let installed_by = self.resolve_installed_by(db).await?;
+            let pending: Vec<_> = rendered.iter().collect();
+            let mut retries = Vec::new();
+            let session = self.pinned_session(db).await?;
+            let applied = self
+                .with_migrate_callbacks(
+                    &session,
+                    &callbacks,
+                    Box::pin(self.apply_each(
+                        &session,
+                        &pending,
+                        &installed_by,
+                        &callbacks,
+                        &mut retries,
+                    )),
+                )
+                .await;
+            let applied = Self::close_session(session, applied).await?;
+            Ok(RunReport {
+                applied,
+                retries,
+                dropped_objects,
+                created_schemas,
+                ..RunReport::default()
+            })
+        })
+        .await
+    }
+
+    /// Clean up the history table so `up` can proceed again: delete failed
+    /// rows, realign checksums and descriptions of applied migrations with the
+    /// local files and, if `mark_missing_deleted` is set, mark applied
+    /// migrations that no longer exist locally as deleted.
+    pub async fn repair(
+        &self,
+        db: &DatabaseConnection,
+        mark_missing_deleted: bool,
+    ) -> Result<RepairReport, SchemalaneError> {
+        Self::ensure_postgres(db)?;
+        let migrations = self.discover_migrations()?;
+
+        self.with_advisory_lock(db, async {
+            if !self.history_table_exists(db).await? {
+                return Ok(RepairReport::default());
+            }
+
+            let history = self.load_history(db).await?;
+            let plan = plan_repair(&migrations, &history, mark_missing_deleted);
+            let installed_by = self.resolve_installed_by(db).await?;
+            let mut report = RepairReport::default();
+
+            // All or nothing, so a failure cannot leave the history half
+            // repaired.
+            let txn = db.begin().await?;
+            if !plan.failed.is_empty() {
+                self.delete_failed_history(&txn).await?;
+                report.removed_failed = plan
+                    .failed
+                    .iter()
+                    .map(|row| RepairedMigration::from_history(row))
+                    .collect();
+            }
+
+            for (row, migration) in &plan.realign {
+                self.realign_history_row(&txn, row.installed_rank, migration)
+                    .await?;
+                report.realigned.push(RepairedMigration::from_history(row));
+            }
+
+            for row in &plan.missing {
+                let marker = HistoryRow {
+                    migration_type: DELETE_HISTORY_TYPE.to_owned(),
+                    execution_time: 0,
+                    success: true,
+                    transaction_mode: None,
+                    ..(*row).clone()
+                };
+                self.insert_history(&txn, &marker, &installed_by).await?;
+                report
+                    .marked_deleted
+                    .push(RepairedMigration::from_history(row));
+            }
+            txn.commit().await?;
+
+            Ok(report)
+        })
+        .await
+    }
+
+    /// Mark an existing database as already migrated up to `version` by
+    /// writing a `BASELINE` row. Local migrations at or below `version` are
+    /// then reported as `BelowBaseline` and skipped by `up`.
+    pub async fn baseline(
+        &self,
+        db: &DatabaseConnection,
+        version: &str,
+        description: &str,
+    ) -> Result<BaselineReport, SchemalaneError> {
+        Self::ensure_postgres(db)?;
+        ParsedVersion::parse(version)?;
+
+        self.with_advisory_lock(db, async {
+            self.ensure_schemas(db).await?;
+            let history = self.load_history(db).await?;
+            let migration_rows = history
+                .iter()
+                .filter(|row| row.migration_type != SCHEMA_HISTORY_TYPE)
+                .count();
+            if migration_rows > 0 {
+                return Err(SchemalaneError::Validation(format!(
+                    "baseline requires an empty history table, found {} row(s) in {}",
+                    migration_rows,
+                    qualified_table(&self.config.schema, &self.config.history_table)
+                )));
+            }
+
+            let installed_by = self.resolve_installed_by(db).await?;
+            let row = HistoryRow {
+                installed_rank: 0,
+                version: Some(version.to_owned()),
+                description: description.to_owned(),
+                migration_type: BASELINE_HISTORY_TYPE.to_owned(),
+                script: description.to_owned(),
+                checksum: None,
+                installed_on: String::new(),
+                execution_time: 0,
+                success: true,
+                transaction_mode: None,
+            };
+            let installed_rank = self.insert_history(db, &row, &installed_by).await?;
+
+            Ok(BaselineReport {
+                version: version.to_owned(),
+                description: description.to_owned(),
+                installed_rank,
+            })
+        })
+        .await
+    }
+
+    async fn with_advisory_lock<T, F>(
+        &self,
+        db: &DatabaseConnection,
+        fut: F,
+    ) -> Result<T, SchemalaneError>
+    where
+        F: Future<Output = Result<T, SchemalaneError>>,
     {
+        let Some(lock_id) = self.advisory_lock_id() else {
+            return fut.await;
+        };
+
         let pool = db.get_postgres_connection_pool();
         let mut lock_conn = pool.acquire().await?;
 
-        sqlx::query("SELECT pg_advisory_lock($1)")
-            .bind(self.config.advisory_lock_id)
-            .execute(&mut *lock_conn)
-            .await?;
+        self.acquire_advisory_lock(&mut lock_conn, lock_id).await?;
 
         let operation_result = fut.await;
 
         let unlock_result = sqlx::query("SELECT pg_advisory_unlock($1)")
-            .bind(self.config.advisory_lock_id)
+            .bind(lock_id)
             .execute(&mut *lock_conn)
             .await;
 
@@ -478,141 +1747,231 @@ impl SchemalaneMigrator {
         }
     }
 
-    fn ensure_postgres(db: &DatabaseConnection) -> Result<(), SchemalaneError> {
-        if db.get_database_backend() != DbBackend::Postgres {
-            return Err(SchemalaneError::UnsupportedBackend);
+    /// A pool of one connection next to `db`'s, so that everything run on it
+    /// shares a session: the statements of a no-transaction file land on
+    /// the same connection, in order, and a `SET` in a `beforeMigrate`
+    /// callback holds for the migrations after it.
+    ///
+    /// The session starts from the `search_path` of a connection in `db`'s
+    /// pool, so a path set up when that pool connects carries over. As in
+    /// Flyway, `schema` goes first on it, so unqualified names in migrations
+    /// and callbacks resolve to it.
+    async fn pinned_session(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<DatabaseConnection, SchemalaneError> {
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT current_setting('search_path') AS search_path, \
+             (current_schemas(false))[1] IS NOT DISTINCT FROM $1 AS schema_first",
+            [self.config.schema.as_str().into()],
+        );
+        let row = db
+            .query_one_raw(stmt)
+            .await?
+            .ok_or_else(|| DbErr::Custom("current_setting() returned no row".to_owned()))?;
+        let mut search_path: String = row.try_get("", "search_path")?;
+        if !row.try_get::<bool>("", "schema_first")? {
+            let schema = quote_ident(&self.config.schema);
+            search_path = if search_path.is_empty() {
+                schema
+            } else {
+                format!("{schema}, {search_path}")
+            };
         }
-        Ok(())
-    }
-
-    fn discover_migrations(&self) -> Result<Vec<DiscoveredMigration>, SchemalaneError> {
-        let mut migrations = self.discover_sql_migrations()?;
-        migrations.extend(self.discover_rust_migrations()?);
 
-        let mut versions = BTreeSet::new();
-        let mut scripts = BTreeSet::new();
+        let options = db.get_postgres_connection_pool().connect_options();
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .test_before_acquire(false)
+            .connect_with((*options).clone())
+            .await
+            .map_err(|err| DbErr::Conn(RuntimeErr::SqlxError(err.into())))?;
+        let session = sea_orm::SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT set_config('search_path', $1, false)",
+            [search_path.into()],
+        );
+        session.execute_raw(stmt).await?;
+        Ok(session)
+    }
 
-        for migration in &migrations {
-            if !versions.insert(migration.version_text.clone()) {
-                return Err(SchemalaneError::Validation(format!(
-                    "duplicate migration version '{}'",
-                    migration.version_text
-                )));
-            }
-            if !scripts.insert(migration.script.clone()) {
-                return Err(SchemalaneError::Validation(format!(
-                    "duplicate migration script '{}'",
-                    migration.script
-                )));
-            }
+    /// Close a `pinned_session` once `result` was produced on it. A failure
+    /// to close only surfaces when `result` is `Ok`, so it never hides the
+    /// error of a migration.
+    async fn close_session<T>(
+        session: DatabaseConnection,
+        result: Result<T, SchemalaneError>,
+    ) -> Result<T, SchemalaneError> {
+        match (result, session.close().await) {
+            (Ok(value), Ok(())) => Ok(value),
+            (Ok(_), Err(err)) => Err(err.into()),
+            (Err(err), _) => Err(err),
         }
-
-        migrations.sort_by(|a, b| {
-            a.version
-                .cmp(&b.version)
-                .then_with(|| a.script.cmp(&b.script))
-        });
-        Ok(migrations)
     }
 
-    fn discover_sql_migrations(&self) -> Result<Vec<DiscoveredMigration>, SchemalaneError> {
-        if !self.config.migrations_dir.exists() {
-            return Err(SchemalaneError::Validation(format!(
-                "migrations directory not found: {}",
-                self.config.migrations_dir.display()
-            )));
+    fn advisory_lock_id(&self) -> Option<i64> {
+        match self.config.advisory_lock {
+            AdvisoryLock::Fixed(lock_id) => Some(lock_id),
+            AdvisoryLock::Disabled => None,
+            AdvisoryLock::Derived => Some(derive_advisory_lock_id(
+                &self.config.schema,
+                &self.config.history_table,
+            )),
         }
+    }
 
-        let mut migrations = Vec::new();
-        for entry in std::fs::read_dir(&self.config.migrations_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            if !path.is_file() {
-                continue;
+    async fn acquire_advisory_lock(
+        &self,
+        lock_conn: &mut sqlx::PgConnection,
+        lock_id: i64,
+    ) -> Result<(), SchemalaneError> {
+        let started = Instant::now();
+
+        let acquired = match self.config.lock_timeout {
+            None => {
+                sqlx::query("SELECT pg_advisory_lock($1)")
+                    .bind(lock_id)
+                    .execute(&mut *lock_conn)
+                    .await?;
+                true
             }
-
-            if path.extension().and_then(|ext| ext.to_str()) != Some("sql") {
-                continue;
+            Some(timeout) if timeout.is_zero() => {
+                sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_lock($1)")
+                    .bind(lock_id)
+                    .fetch_one(&mut *lock_conn)
+                    .await?
             }
+            Some(timeout) => {
+                // `lock_timeout` also bounds advisory lock waits; it is scoped
+                // to this pooled session, so reset it afterwards.
+                sqlx::query("SELECT set_config('lock_timeout', $1, false)")
+                    .bind(timeout_setting(timeout))
+                    .execute(&mut *lock_conn)
+                    .await?;
+                let result = sqlx::query("SELECT pg_advisory_lock($1)")
+                    .bind(lock_id)
+                    .execute(&mut *lock_conn)
+                    .await;
+                sqlx::query("RESET lock_timeout")
+                    .execute(&mut *lock_conn)
+                    .await?;
+                match result {
+                    Ok(_) => true,
+                    Err(sqlx::Error::Database(err))
+                        if err.code().as_deref() == Some(SQLSTATE_LOCK_NOT_AVAILABLE) =>
+                    {
+                        false
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        };
 
-            let file_name = path.file_name().and_then(|n| n.to_str()).ok_or_else(|| {
-                SchemalaneError::Validation("non-utf8 migration filename".to_owned())
-            })?;
-
-            let (version_text, parsed_version, description) = parse_sql_filename(file_name)?;
-            let content = std::fs::read(&path)?;
-            let checksum = Some(calculate_checksum(&content));
-            let description_display = description.replace('_', " ");
-
-            migrations.push(DiscoveredMigration {
-                version: parsed_version,
-                version_text,
-                description_display,
-                script: file_name.to_owned(),
-                checksum,
-                migration_type: MigrationType::Sql,
-                source: MigrationSource::SqlFile(path),
-            });
+        if acquired {
+            return Ok(());
         }
-
-        Ok(migrations)
+        Err(SchemalaneError::LockTimeout {
+            lock_id,
+            waited: started.elapsed(),
+            holders: Self::advisory_lock_holders(lock_conn, lock_id).await?,
+        })
     }
 
-    fn discover_rust_migrations(&self) -> Result<Vec<DiscoveredMigration>, SchemalaneError> {
-        let mut migrations = Vec::new();
+    /// Sessions holding `lock_id`. A bigint advisory key is stored in
+    /// `pg_locks` as its high (`classid`) and low (`objid`) 32 bits.
+    async fn advisory_lock_holders(
+        lock_conn: &mut sqlx::PgConnection,
+        lock_id: i64,
+    ) -> Result<Vec<LockHolder>, SchemalaneError> {
+        let rows: Vec<(i32, Option<String>, Option<String>)> = sqlx::query_as(
+            "SELECT a.pid, a.application_name, host(a.client_addr) AS client_addr \
+             FROM pg_catalog.pg_locks l \
+             JOIN pg_catalog.pg_stat_activity a ON a.pid = l.pid \
+             WHERE l.locktype = 'advisory' AND l.granted AND l.objsubid = 1 \
+               AND l.database = (SELECT oid FROM pg_catalog.pg_database WHERE datname = current_database()) \
+               AND ((l.classid::bigint << 32) | l.objid::bigint) = $1 \
+             ORDER BY a.pid",
+        )
+        .bind(lock_id)
+        .fetch_all(&mut *lock_conn)
+        .await?;
 
-        for entry in std::fs::read_dir(&self.config.migrations_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            if !path.is_file() {
-                continue;
-            }
+        Ok(rows
+            .into_iter()
+            .map(|(pid, application_name, client_addr)| LockHolder {
+                pid,
+                application_name: application_name.unwrap_or_default(),
+                client_addr,
+            })
+            .collect())
+    }
 
-            if path.extension().and_then(|ext| ext.to_str()) != Some("rs") {
-                continue;
-            }
+    fn ensure_postgres(db: &DatabaseConnection) -> Result<(), SchemalaneError> {
+        if db.get_database_backend() != DbBackend::Postgres {
+            return Err(SchemalaneError::UnsupportedBackend);
+        }
+        Ok(())
+    }
 
-            let file_name = path.file_name().and_then(|n| n.to_str()).ok_or_else(|| {
-                SchemalaneError::Validation("non-utf8 migration filename".to_owned())
-            })?;
+    fn discover_migrations(&self) -> Result<Vec<DiscoveredMigration>, SchemalaneError> {
+        let mut migrations = self.discover_unchecked()?.into_migrations()?;
+        if let Some(issue) = duplicate_issues(&migrations).into_iter().next() {
+            return Err(SchemalaneError::Validation(issue.message));
+        }
 
-            let (version_text, parsed_version, description) = parse_rust_filename(file_name)?;
-            let content = std::fs::read(&path)?;
-            let checksum = Some(calculate_checksum(&content));
-            let description_display = description.replace('_', " ");
+        // Versioned migrations first, then repeatables in description order.
+        migrations.sort_by(|a, b| {
+            a.is_repeatable()
+                .cmp(&b.is_repeatable())
+                .then_with(|| a.version.cmp(&b.version))
+                .then_with(|| a.description.cmp(&b.description))
+                .then_with(|| a.script.cmp(&b.script))
+        });
 
-            migrations.push(DiscoveredMigration {
-                version: parsed_version,
-                version_text,
-                description_display,
-                script: file_name.to_owned(),
-                checksum,
-                migration_type: MigrationType::Rust,
-                source: MigrationSource::RustFile(path),
-            });
+        for migration in &mut migrations {
+            if let Some(executor) = self.rust_migrations.get(migration.script.as_str()) {
+                migration.transaction_mode = executor.transaction_mode();
+                migration.timeouts = executor.timeouts;
+            }
         }
-
         Ok(migrations)
     }
 
+    fn discover_unchecked(&self) -> Result<Discovery, SchemalaneError> {
+        let algorithm = self.config.checksum_algorithm;
+        self.source.as_ref().map_or_else(
+            || {
+                CompositeMigrationSource::from_dirs(self.config.migration_dirs())
+                    .discover(algorithm)
+            },
+            |source| source.discover(algorithm),
+        )
+    }
+
+    fn discover_callbacks(&self) -> Result<Vec<SqlCallback>, SchemalaneError> {
+        let mut callbacks = match &self.source {
+            Some(source) => source.discover_callbacks()?,
+            None => CompositeMigrationSource::from_dirs(self.config.migration_dirs())
+                .discover_callbacks()?,
+        };
+        callbacks.sort_by(|a, b| a.script.cmp(&b.script));
+        Ok(callbacks)
+    }
+
     fn ensure_rust_executors_registered(
         &self,
         migrations: &[DiscoveredMigration],
     ) -> Result<(), SchemalaneError> {
-        let mut missing_scripts = Vec::new();
-
-        for migration in migrations {
-            if migration.migration_type == MigrationType::Rust
-                && !self.rust_migrations.contains_key(migration.script.as_str())
-            {
-                missing_scripts.push(migration.script.clone());
-            }
-        }
+        let mut missing_scripts = self.missing_rust_executors(migrations);
 
         if missing_scripts.is_empty() {
             Ok(())
         } else {
-            missing_scripts.sort();
+            missing_scripts.sort_unstable();
             Err(SchemalaneError::Validation(format!(
                 "missing Rust migration executor(s) for script(s): {}",
                 missing_scripts.join(", ")
@@ -620,99 +1979,440 @@ impl SchemalaneMigrator {
         }
     }
 
-    fn ensure_no_blocking_history(
+    fn missing_rust_executors<'a>(&self, migrations: &'a [DiscoveredMigration]) -> Vec<&'a str> {
+        migrations
+            .iter()
+            .filter(|migration| {
+                migration.migration_type == MigrationType::Rust
+                    && !self.rust_migrations.contains_key(migration.script.as_str())
+            })
+            .map(|migration| migration.script.as_str())
+            .collect()
+    }
+
+    fn ensure_in_order(
+        &self,
         migrations: &[DiscoveredMigration],
         history: &[HistoryRow],
     ) -> Result<(), SchemalaneError> {
-        let latest = latest_history_by_script(history);
-        let local_by_script: HashMap<&str, &DiscoveredMigration> =
-            migrations.iter().map(|m| (m.script.as_str(), m)).collect();
-
-        let mut failed = Vec::new();
-        let mut missing = Vec::new();
-        let mut checksum_mismatch = Vec::new();
+        if self.config.out_of_order {
+            return Ok(());
+        }
 
-        for row in latest.values() {
-            if !row.success {
-                failed.push(row.script.clone());
-            }
-            if row.success && !local_by_script.contains_key(row.script.as_str()) {
-                missing.push(row.script.clone());
-            }
+        let ignored = out_of_order_migrations(migrations, history);
+        if let Some(max_applied) = max_applied_version(history)
+            && !ignored.is_empty()
+        {
+            let scripts: Vec<_> = ignored.iter().map(|m| m.script.as_str()).collect();
+            return Err(SchemalaneError::Validation(format!(
+                "pending migrations are older than the latest applied version {}: {} (enable out_of_order to apply them)",
+                MigrationTarget::Version(max_applied),
+                scripts.join(", ")
+            )));
         }
+        Ok(())
+    }
 
-        for migration in migrations {
-            if let Some(row) = latest.get(migration.script.as_str())
-                && row.success && row.checksum != migration.checksum
-            {
-                checksum_mismatch.push(migration.script.clone());
-            }
+    fn ensure_groupable(&self, pending: &[&DiscoveredMigration]) -> Result<(), SchemalaneError> {
+        if !self.config.group_transactions {
+            return Ok(());
         }
 
-        if !failed.is_empty() {
-            failed.sort();
-            return Err(SchemalaneError::FailedHistory(failed.join(", ")));
+        let scripts: Vec<_> = pending
+            .iter()
+            .filter(|migration| migration.transaction_mode == TransactionMode::NoTransaction)
+            .map(|migration| migration.script.as_str())
+            .collect();
+        if scripts.is_empty() {
+            return Ok(());
         }
+        Err(SchemalaneError::Validation(format!(
+            "cannot group migrations that run outside a transaction: {} (drop the no-transaction directive, register Rust migrations with RustMigrationExecutor::transactional, or run without group_transactions)",
+            scripts.join(", ")
+        )))
+    }
 
-        let mut drift_items = Vec::new();
-        if !missing.is_empty() {
-            missing.sort();
-            drift_items.push(format!("missing: {}", missing.join(", ")));
+    /// Copies of `pending` with placeholders in SQL bodies replaced, or an
+    /// error listing every unresolved one. Checksums keep covering the raw
+    /// text.
+    fn render_placeholders(
+        &self,
+        pending: &[&DiscoveredMigration],
+        values: &HashMap<String, String>,
+    ) -> Result<Vec<DiscoveredMigration>, SchemalaneError> {
+        let mut rendered = Vec::with_capacity(pending.len());
+        let mut unresolved = Vec::new();
+        for migration in pending {
+            let mut migration = (*migration).clone();
+            if let MigrationBody::Sql(sql) = &migration.body {
+                match self.replace_placeholders(sql, values) {
+                    Ok(sql) => migration.body = MigrationBody::Sql(Cow::Owned(sql)),
+                    Err(names) => {
+                        unresolved.push(format!("{} ({})", migration.script, names.join(", ")));
+                    }
+                }
+            }
+            rendered.push(migration);
         }
-        if !checksum_mismatch.is_empty() {
-            checksum_mismatch.sort();
-            drift_items.push(format!(
-                "checksum mismatch: {}",
-                checksum_mismatch.join(", ")
-            ));
+        if unresolved.is_empty() {
+            return Ok(rendered);
         }
+        Err(unresolved_placeholders_error(&unresolved))
+    }
 
-        if !drift_items.is_empty() {
-            return Err(SchemalaneError::Drift(drift_items.join("; ")));
+    /// SQL callbacks from the migration source in script order, with
+    /// placeholders replaced.
+    fn sql_callbacks(
+        &self,
+        values: &HashMap<String, String>,
+    ) -> Result<Vec<SqlCallback>, SchemalaneError> {
+        let mut callbacks = self.discover_callbacks()?;
+        let mut unresolved = Vec::new();
+        for callback in &mut callbacks {
+            match self.replace_placeholders(&callback.sql, values) {
+                Ok(sql) => callback.sql = Cow::Owned(sql),
+                Err(names) => {
+                    unresolved.push(format!("{} ({})", callback.script, names.join(", ")));
+                }
+            }
         }
+        if unresolved.is_empty() {
+            return Ok(callbacks);
+        }
+        Err(unresolved_placeholders_error(&unresolved))
+    }
 
-        Ok(())
+    fn replace_placeholders(
+        &self,
+        sql: &str,
+        values: &HashMap<String, String>,
+    ) -> Result<String, Vec<String>> {
+        let prefix = &self.config.placeholder_prefix;
+        let suffix = &self.config.placeholder_suffix;
+        replace_placeholders(sql, values, prefix, suffix).map_err(|names| {
+            names
+                .into_iter()
+                .map(|name| format!("{prefix}{name}{suffix}"))
+                .collect()
+        })
+    }
+
+    fn ensure_no_blocking_history(
+        migrations: &[DiscoveredMigration],
+        history: &[HistoryRow],
+    ) -> Result<(), SchemalaneError> {
+        ensure_valid(&ValidationReport {
+            issues: history_issues(migrations, history),
+        })
     }
 
-    async fn apply_migration(
+    /// Apply `pending` one by one, recording each in history as it finishes;
+    /// stops at the first failure, which is recorded as a failed row.
+    ///
+    /// Transactional migrations write their success row before their own
+    /// commit, so a crash in between cannot leave one applied but unrecorded.
+    async fn apply_each(
         &self,
         db: &DatabaseConnection,
-        migration: &DiscoveredMigration,
-    ) -> Result<(), DbErr> {
-        match &migration.source {
-            MigrationSource::SqlFile(path) => {
-                let sql = std::fs::read_to_string(path).map_err(|err| {
-                    DbErr::Custom(format!(
-                        "failed to read SQL migration {}: {err}",
-                        path.display()
-                    ))
-                })?;
-                let manager = SchemaManager::new(db);
-                execute_sql_migration(&manager, &sql).await
+        pending: &[&DiscoveredMigration],
+        installed_by: &str,
+        callbacks: &[SqlCallback],
+        retries: &mut Vec<MigrationRetry>,
+    ) -> Result<Vec<AppliedMigration>, SchemalaneError> {
+        let mut applied = Vec::new();
+        for migration in pending {
+            if migration.transaction_mode == TransactionMode::Transaction {
+                applied.extend(
+                    self.apply_in_transaction(db, &[*migration], installed_by, callbacks, retries)
+                        .await?,
+                );
+                continue;
             }
-            MigrationSource::RustFile(path) => {
-                let executor = self
-                    .rust_migrations
-                    .get(migration.script.as_str())
-                    .ok_or_else(|| {
-                        DbErr::Custom(format!(
-                            "missing Rust migration executor for script {} ({})",
-                            migration.script,
-                            path.display()
-                        ))
-                    })?;
-                let manager = SchemaManager::new(db);
-                execute_rust_migration(&manager, executor).await
+
+            let started = Instant::now();
+            let run_result = self.apply_with_callbacks(db, migration, callbacks).await;
+            let execution_time_ms = millis_i32(started.elapsed().as_millis());
+
+            if let Err(err) = run_result {
+                self.insert_history_row(db, migration, installed_by, execution_time_ms, false)
+                    .await?;
+                return Err(err);
             }
+            self.insert_history_row(db, migration, installed_by, execution_time_ms, true)
+                .await?;
+            applied.push(AppliedMigration::from_migration(
+                migration,
+                execution_time_ms,
+            ));
         }
+        Ok(applied)
     }
 
-    async fn ensure_history_table(&self, db: &DatabaseConnection) -> Result<(), DbErr> {
-        let table = qualified_table(&self.config.schema, &self.config.history_table);
-        let success_idx = quote_ident(&format!("{}_s_idx", self.config.history_table));
-        let version_idx = quote_ident(&format!("{}_v_idx", self.config.history_table));
+    /// Apply `pending` and their success history rows in one transaction. On
+    /// failure everything is rolled back; lock and deadlock failures are
+    /// retried under `SchemalaneConfig::retry`, and only the last failure is
+    /// recorded as a failed row.
+    async fn apply_in_transaction(
+        &self,
+        db: &DatabaseConnection,
+        pending: &[&DiscoveredMigration],
+        installed_by: &str,
+        callbacks: &[SqlCallback],
+        retries: &mut Vec<MigrationRetry>,
+    ) -> Result<Vec<AppliedMigration>, SchemalaneError> {
+        let policy = self.config.retry;
+        let mut attempt = 1;
+        loop {
+            let failure = match self
+                .try_in_transaction(db, pending, installed_by, callbacks)
+                .await?
+            {
+                Ok(applied) => return Ok(applied),
+                Err(failure) => failure,
+            };
+            let retryable = failure.error.retryable_sqlstate().map(str::to_owned);
+            match retryable {
+                Some(sqlstate) if attempt <= policy.max_retries => {
+                    let backoff = policy.backoff(attempt);
+                    retries.push(MigrationRetry {
+                        script: failure.migration.script.clone(),
+                        attempt,
+                        sqlstate,
+                        error: failure.error.to_string(),
+                        backoff_ms: u64::try_from(backoff.as_millis()).unwrap_or(u64::MAX),
+                    });
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                _ => {
+                    self.insert_history_row(
+                        db,
+                        failure.migration,
+                        installed_by,
+                        failure.execution_time_ms,
+                        false,
+                    )
+                    .await?;
+                    return Err(failure.error.after_attempts(attempt));
+                }
+            }
+        }
+    }
 
-        let ddl = format!(
+    /// One attempt of `apply_in_transaction`. A failed migration is rolled
+    /// back and returned in the inner `Err` without recording it.
+    async fn try_in_transaction<'m>(
+        &self,
+        db: &DatabaseConnection,
+        pending: &[&'m DiscoveredMigration],
+        installed_by: &str,
+        callbacks: &[SqlCallback],
+    ) -> Result<Result<Vec<AppliedMigration>, FailedMigration<'m>>, SchemalaneError> {
+        let defaults = self.config.migration_timeouts;
+        let timeouts_in_use =
+            defaults.is_set() || pending.iter().any(|migration| migration.timeouts.is_set());
+        let txn = db.begin().await?;
+        let mut applied = Vec::new();
+        for migration in pending {
+            if timeouts_in_use {
+                txn.execute_unprepared(&migration.timeouts.or(defaults).set_local_sql())
+                    .await?;
+            }
+            let started = Instant::now();
+            let run_result = self.apply_with_callbacks(&txn, migration, callbacks).await;
+            let execution_time_ms = millis_i32(started.elapsed().as_millis());
+
+            if let Err(error) = run_result {
+                txn.rollback().await?;
+                return Ok(Err(FailedMigration {
+                    migration,
+                    execution_time_ms,
+                    error,
+                }));
+            }
+            self.insert_history_row(&txn, migration, installed_by, execution_time_ms, true)
+                .await?;
+            applied.push(AppliedMigration::from_migration(
+                migration,
+                execution_time_ms,
+            ));
+        }
+        txn.commit().await?;
+        Ok(Ok(applied))
+    }
+
+    /// Run `apply` between the `beforeMigrate` and `afterMigrate` callbacks.
+    /// If any of them fails, `afterMigrateError` runs before the error is
+    /// returned.
+    async fn with_migrate_callbacks<T, F>(
+        &self,
+        db: &DatabaseConnection,
+        callbacks: &[SqlCallback],
+        apply: F,
+    ) -> Result<T, SchemalaneError>
+    where
+        F: Future<Output = Result<T, SchemalaneError>>,
+    {
+        let result = async {
+            self.run_callbacks(db, CallbackEvent::BeforeMigrate, None, callbacks)
+                .await?;
+            let value = apply.await?;
+            self.run_callbacks(db, CallbackEvent::AfterMigrate, None, callbacks)
+                .await?;
+            Ok(value)
+        }
+        .await;
+
+        let Err(err) = result else {
+            return result;
+        };
+        let failed_script = match &err {
+            SchemalaneError::MigrationExecution { script, .. } => Some(script.as_str()),
+            _ => None,
+        };
+        match self
+            .run_callbacks(
+                db,
+                CallbackEvent::AfterMigrateError,
+                failed_script,
+                callbacks,
+            )
+            .await
+        {
+            Ok(()) => Err(err),
+            Err(callback_err) => Err(callback_err.while_handling(err)),
+        }
+    }
+
+    /// Run one migration between its `beforeEachMigrate` and
+    /// `afterEachMigrate` callbacks, all on `db`.
+    async fn apply_with_callbacks<C>(
+        &self,
+        db: &C,
+        migration: &DiscoveredMigration,
+        callbacks: &[SqlCallback],
+    ) -> Result<(), SchemalaneError>
+    where
+        for<'c> &'c C: IntoSchemaManagerConnection<'c>,
+    {
+        let script = Some(migration.script.as_str());
+        self.run_callbacks(db, CallbackEvent::BeforeEachMigrate, script, callbacks)
+            .await?;
+        self.apply_migration(db, migration).await?;
+        self.run_callbacks(db, CallbackEvent::AfterEachMigrate, script, callbacks)
+            .await
+    }
+
+    /// Run the SQL callbacks for `event`, then the Rust ones in registration
+    /// order. SQL callbacks run in a transaction (a savepoint inside one)
+    /// unless they carry the no-transaction directive.
+    async fn run_callbacks<C>(
+        &self,
+        db: &C,
+        event: CallbackEvent,
+        script: Option<&str>,
+        sql_callbacks: &[SqlCallback],
+    ) -> Result<(), SchemalaneError>
+    where
+        for<'c> &'c C: IntoSchemaManagerConnection<'c>,
+    {
+        for callback in sql_callbacks
+            .iter()
+            .filter(|callback| callback.event == event)
+        {
+            let manager = SchemaManager::new(db);
+            let mode =
+                header_transaction_mode(&callback.sql).unwrap_or(TransactionMode::Transaction);
+            execute_sql_migration(&manager, &callback.sql, mode)
+                .await
+                .map_err(|failure| failure.into_callback_error(event, &callback.script))?;
+        }
+
+        let context = CallbackContext {
+            event,
+            script: script.map(str::to_owned),
+        };
+        for registered in self
+            .callbacks
+            .iter()
+            .filter(|registered| registered.event == event)
+        {
+            let manager = SchemaManager::new(db);
+            registered
+                .callback
+                .run(&manager, &context)
+                .await
+                .map_err(|source| {
+                    SqlFailure::from(source).into_callback_error(event, &registered.name)
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Run one migration on `db`. Inside an outer transaction the
+    /// migration's own transaction becomes a savepoint.
+    async fn apply_migration<C>(
+        &self,
+        db: &C,
+        migration: &DiscoveredMigration,
+    ) -> Result<(), SchemalaneError>
+    where
+        for<'c> &'c C: IntoSchemaManagerConnection<'c>,
+    {
+        let result = match &migration.body {
+            MigrationBody::Sql(sql) => {
+                let manager = SchemaManager::new(db);
+                execute_sql_migration(&manager, sql, migration.transaction_mode).await
+            }
+            MigrationBody::Rust => match self.rust_migrations.get(migration.script.as_str()) {
+                Some(executor) => {
+                    let manager = SchemaManager::new(db);
+                    execute_rust_migration(&manager, executor)
+                        .await
+                        .map_err(SqlFailure::from)
+                }
+                None => Err(SqlFailure::from(DbErr::Custom(format!(
+                    "missing Rust migration executor for script {}",
+                    migration.script
+                )))),
+            },
+        };
+
+        result.map_err(|failure| {
+            let script = migration.script.clone();
+            let statement = failure.statement.map(Box::new);
+            let database_error = DatabaseErrorDetails::from_db_err(&failure.source);
+            // Timeouts are only set inside the migration's transaction.
+            let applied = if migration.transaction_mode == TransactionMode::Transaction {
+                migration.timeouts.or(self.config.migration_timeouts)
+            } else {
+                MigrationTimeouts::default()
+            };
+            match MigrationTimeoutKind::from_failure(database_error.as_ref(), applied) {
+                Some(timeout) => SchemalaneError::MigrationTimeout {
+                    script,
+                    timeout,
+                    statement,
+                    attempts: 1,
+                    source: failure.source,
+                },
+                None => SchemalaneError::MigrationExecution {
+                    script,
+                    statement,
+                    database_error: database_error.map(Box::new),
+                    attempts: 1,
+                    source: failure.source,
+                },
+            }
+        })
+    }
+
+    async fn ensure_history_table(&self, db: &DatabaseConnection) -> Result<(), DbErr> {
+        let table = qualified_table(&self.config.schema, &self.config.history_table);
+        let success_idx = quote_ident(&format!("{}_s_idx", self.config.history_table));
+        let version_idx = quote_ident(&format!("{}_v_idx", self.config.history_table));
+
+        let ddl = format!(
             "\
 CREATE TABLE IF NOT EXISTS {table} (\
 \"installed_rank\" INTEGER NOT NULL,\
@@ -728,16 +2428,34 @@ CREATE TABLE IF NOT EXISTS {table} (\
 CONSTRAINT {pk} PRIMARY KEY (\"installed_rank\")\
 );\
 CREATE INDEX IF NOT EXISTS {success_idx} ON {table} (\"success\");\
-CREATE INDEX IF NOT EXISTS {version_idx} ON {table} (\"version\");",
+CREATE INDEX IF NOT EXISTS {version_idx} ON {table} (\"version\");\
+CREATE TABLE IF NOT EXISTS {mode_table} (\
+\"installed_rank\" INTEGER NOT NULL,\
+\"script\" VARCHAR(1000) NOT NULL,\
+\"transactional\" BOOLEAN NOT NULL,\
+CONSTRAINT {mode_pk} PRIMARY KEY (\"installed_rank\")\
+);",
             pk = quote_ident(&format!("{}_pk", self.config.history_table)),
+            mode_table = qualified_table(&self.config.schema, &self.mode_table()),
+            mode_pk = quote_ident(&format!("{}_pk", self.mode_table())),
         );
 
         db.execute_unprepared(&ddl).await?;
         Ok(())
     }
 
+    /// Holds the transaction mode of each history row, which the Flyway
+    /// table has no column for.
+    fn mode_table(&self) -> String {
+        format!("{}{MODE_TABLE_SUFFIX}", self.config.history_table)
+    }
+
     async fn history_table_exists(&self, db: &DatabaseConnection) -> Result<bool, DbErr> {
-        let regclass = format!("{}.{}", self.config.schema, self.config.history_table);
+        self.table_exists(db, &self.config.history_table).await
+    }
+
+    async fn table_exists(&self, db: &DatabaseConnection, table: &str) -> Result<bool, DbErr> {
+        let regclass = format!("{}.{}", self.config.schema, table);
         let stmt = Statement::from_sql_and_values(
             DbBackend::Postgres,
             "SELECT to_regclass($1) IS NOT NULL AS exists",
@@ -753,8 +2471,17 @@ CREATE INDEX IF NOT EXISTS {version_idx} ON {table} (\"version\");",
 
     async fn load_history(&self, db: &DatabaseConnection) -> Result<Vec<HistoryRow>, DbErr> {
         let table = qualified_table(&self.config.schema, &self.config.history_table);
+        // A table written only by Flyway has no mode table yet.
+        let modes = if self.table_exists(db, &self.mode_table()).await? {
+            format!(
+                "LEFT JOIN {} m ON m.\"installed_rank\" = h.\"installed_rank\" AND m.\"script\" = h.\"script\"",
+                qualified_table(&self.config.schema, &self.mode_table())
+            )
+        } else {
+            "LEFT JOIN (SELECT NULL::boolean AS \"transactional\") m ON FALSE".to_owned()
+        };
         let query = format!(
-            "SELECT \"installed_rank\", \"version\", \"description\", \"type\", \"script\", \"checksum\", \"installed_by\", \"installed_on\"::text AS \"installed_on\", \"execution_time\", \"success\" FROM {table} ORDER BY \"installed_rank\" ASC"
+            "SELECT h.\"installed_rank\", h.\"version\", h.\"description\", h.\"type\", h.\"script\", h.\"checksum\", h.\"installed_by\", h.\"installed_on\"::text AS \"installed_on\", h.\"execution_time\", h.\"success\", m.\"transactional\" FROM {table} h {modes} ORDER BY h.\"installed_rank\" ASC"
         );
 
         let stmt = Statement::from_string(DbBackend::Postgres, query);
@@ -772,11 +2499,47 @@ CREATE INDEX IF NOT EXISTS {version_idx} ON {table} (\"version\");",
                     installed_on: row.try_get("", "installed_on")?,
                     execution_time: row.try_get("", "execution_time")?,
                     success: row.try_get("", "success")?,
+                    transaction_mode: row.try_get::<Option<bool>>("", "transactional")?.map(
+                        |transactional| {
+                            if transactional {
+                                TransactionMode::Transaction
+                            } else {
+                                TransactionMode::NoTransaction
+                            }
+                        },
+                    ),
                 })
             })
             .collect()
     }
 
+    /// The configured placeholders plus the built-in `schemalane:` ones.
+    async fn placeholder_values(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<HashMap<String, String>, DbErr> {
+        let stmt = Statement::from_string(
+            DbBackend::Postgres,
+            "SELECT current_user::text AS \"user\", to_char(now(), 'YYYY-MM-DD HH24:MI:SS') AS \"timestamp\"".to_owned(),
+        );
+        let row = db
+            .query_one_raw(stmt)
+            .await?
+            .ok_or_else(|| DbErr::Custom("could not resolve built-in placeholders".to_owned()))?;
+
+        let mut values = self.config.placeholders.clone();
+        values.insert(
+            PLACEHOLDER_DEFAULT_SCHEMA.to_owned(),
+            self.config.schema.clone(),
+        );
+        values.insert(PLACEHOLDER_USER.to_owned(), row.try_get("", "user")?);
+        values.insert(
+            PLACEHOLDER_TIMESTAMP.to_owned(),
+            row.try_get("", "timestamp")?,
+        );
+        Ok(values)
+    }
+
     async fn resolve_installed_by(&self, db: &DatabaseConnection) -> Result<String, DbErr> {
         if let Some(installed_by) = &self.config.installed_by {
             return Ok(installed_by.clone());
@@ -794,7 +2557,7 @@ CREATE INDEX IF NOT EXISTS {version_idx} ON {table} (\"version\");",
         row.try_get("", "current_user")
     }
 
-    async fn next_installed_rank(&self, db: &DatabaseConnection) -> Result<i32, DbErr> {
+    async fn next_installed_rank<C: ConnectionTrait>(&self, db: &C) -> Result<i32, DbErr> {
         let table = qualified_table(&self.config.schema, &self.config.history_table);
         let stmt = Statement::from_string(
             DbBackend::Postgres,
@@ -809,13 +2572,25 @@ CREATE INDEX IF NOT EXISTS {version_idx} ON {table} (\"version\");",
         row.try_get("", "next_rank")
     }
 
-    async fn insert_history_row(
+    async fn insert_history_row<C: ConnectionTrait>(
         &self,
-        db: &DatabaseConnection,
+        db: &C,
         migration: &DiscoveredMigration,
         installed_by: &str,
         execution_time: i32,
         success: bool,
+    ) -> Result<i32, DbErr> {
+        let row = HistoryRow::from_migration(migration, execution_time, success, 0);
+        self.insert_history(db, &row, installed_by).await
+    }
+
+    /// Insert `row` with the next `installed_rank` (its own rank is ignored)
+    /// and return the assigned rank.
+    async fn insert_history<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        row: &HistoryRow,
+        installed_by: &str,
     ) -> Result<i32, DbErr> {
         let installed_rank = self.next_installed_rank(db).await?;
         let table = qualified_table(&self.config.schema, &self.config.history_table);
@@ -826,41 +2601,217 @@ CREATE INDEX IF NOT EXISTS {version_idx} ON {table} (\"version\");",
 
         let values = vec![
             Value::from(installed_rank),
-            Value::from(Some(migration.version_text.clone())),
-            Value::from(migration.description_display.clone()),
-            Value::from(migration.migration_type.as_history_type().to_owned()),
-            Value::from(migration.script.clone()),
-            Value::from(migration.checksum),
+            Value::from(row.version.clone()),
+            Value::from(row.description.clone()),
+            Value::from(row.migration_type.clone()),
+            Value::from(row.script.clone()),
+            Value::from(row.checksum),
             Value::from(installed_by.to_owned()),
-            Value::from(execution_time),
-            Value::from(success),
+            Value::from(row.execution_time),
+            Value::from(row.success),
         ];
 
         let stmt = Statement::from_sql_and_values(DbBackend::Postgres, sql, values);
         db.execute_raw(stmt).await?;
+
+        if let Some(mode) = row.transaction_mode {
+            // A rank freed by `repair` may still have a row here.
+            let mode_table = qualified_table(&self.config.schema, &self.mode_table());
+            let stmt = Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                format!(
+                    "INSERT INTO {mode_table} (\"installed_rank\", \"script\", \"transactional\") VALUES ($1,$2,$3) \
+                     ON CONFLICT (\"installed_rank\") DO UPDATE SET \"script\" = EXCLUDED.\"script\", \"transactional\" = EXCLUDED.\"transactional\""
+                ),
+                [
+                    Value::from(installed_rank),
+                    Value::from(row.script.clone()),
+                    Value::from(mode == TransactionMode::Transaction),
+                ],
+            );
+            db.execute_raw(stmt).await?;
+        }
         Ok(installed_rank)
     }
 
-    async fn drop_all_tables(&self, db: &DatabaseConnection) -> Result<(), DbErr> {
+    async fn delete_failed_history<C: ConnectionTrait>(&self, db: &C) -> Result<(), DbErr> {
+        let table = qualified_table(&self.config.schema, &self.config.history_table);
+        db.execute_unprepared(&format!("DELETE FROM {table} WHERE \"success\" = FALSE"))
+            .await?;
+        Ok(())
+    }
+
+    async fn realign_history_row<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        installed_rank: i32,
+        migration: &DiscoveredMigration,
+    ) -> Result<(), DbErr> {
+        let table = qualified_table(&self.config.schema, &self.config.history_table);
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            format!(
+                "UPDATE {table} SET \"checksum\" = $1, \"description\" = $2 WHERE \"installed_rank\" = $3"
+            ),
+            [
+                Value::from(migration.checksum),
+                Value::from(migration.description.clone()),
+                Value::from(installed_rank),
+            ],
+        );
+        db.execute_raw(stmt).await?;
+        Ok(())
+    }
+
+    async fn schema_exists(&self, db: &DatabaseConnection, schema: &str) -> Result<bool, DbErr> {
         let stmt = Statement::from_sql_and_values(
             DbBackend::Postgres,
-            "SELECT tablename FROM pg_catalog.pg_tables WHERE schemaname = $1 ORDER BY tablename",
-            [self.config.schema.clone().into()],
+            "SELECT EXISTS (SELECT 1 FROM pg_catalog.pg_namespace WHERE nspname = $1) AS exists",
+            [schema.into()],
         );
 
-        let rows = db.query_all_raw(stmt).await?;
-        for row in rows {
-            let table_name: String = row.try_get("", "tablename")?;
-            let sql = format!(
-                "DROP TABLE IF EXISTS {}.{} CASCADE",
-                quote_ident(&self.config.schema),
-                quote_ident(&table_name)
-            );
-            db.execute_unprepared(&sql).await?;
+        let row = db
+            .query_one_raw(stmt)
+            .await?
+            .ok_or_else(|| DbErr::Custom("failed to evaluate schema existence".to_owned()))?;
+
+        row.try_get("", "exists")
+    }
+
+    /// Create the managed schemas that do not exist yet, then the history
+    /// table. Like Flyway, created schemas are recorded in a `SCHEMA` row so
+    /// `fresh` knows it may drop them.
+    async fn ensure_schemas(&self, db: &DatabaseConnection) -> Result<Vec<String>, DbErr> {
+        let mut created = Vec::new();
+        for schema in self.config.managed_schemas() {
+            if !self.schema_exists(db, &schema).await? {
+                db.execute_unprepared(&format!("CREATE SCHEMA {}", quote_ident(&schema)))
+                    .await?;
+                created.push(schema);
+            }
         }
+        self.ensure_history_table(db).await?;
 
-        Ok(())
+        if !created.is_empty() {
+            let installed_by = self.resolve_installed_by(db).await?;
+            let row = HistoryRow {
+                installed_rank: 0,
+                version: None,
+                description: SCHEMA_MARKER_DESCRIPTION.to_owned(),
+                migration_type: SCHEMA_HISTORY_TYPE.to_owned(),
+                script: format_schema_list(&created),
+                checksum: None,
+                installed_on: String::new(),
+                execution_time: 0,
+                success: true,
+                transaction_mode: None,
+            };
+            self.insert_history(db, &row, &installed_by).await?;
+        }
+        Ok(created)
+    }
+
+    async fn list_schema_objects(
+        &self,
+        db: &DatabaseConnection,
+        schema: &str,
+    ) -> Result<Vec<SchemaObject>, DbErr> {
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            schema_objects::SCHEMA_OBJECTS_SQL,
+            [schema.into()],
+        );
+
+        let mut objects = Vec::new();
+        for row in db.query_all_raw(stmt).await? {
+            let kind: String = row.try_get("", "kind")?;
+            let object = SchemaObject::from_catalog(
+                schema,
+                &kind,
+                row.try_get("", "name")?,
+                row.try_get("", "arguments")?,
+            )
+            .ok_or_else(|| DbErr::Custom(format!("unknown schema object kind '{kind}'")))?;
+            objects.push(object);
+        }
+        Ok(objects)
+    }
+
+    /// What `fresh` drops, in order: managed schemas that a `SCHEMA` history
+    /// row says Schemalane created go as a whole; the others are emptied
+    /// object by object and kept with their owner and grants.
+    async fn schema_objects_to_drop(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<SchemaObject>, DbErr> {
+        let created = if self.history_table_exists(db).await? {
+            created_schemas(&self.load_history(db).await?)
+        } else {
+            BTreeSet::new()
+        };
+
+        let mut objects = Vec::new();
+        for schema in self.config.managed_schemas() {
+            if !created.contains(&schema) {
+                objects.extend(self.list_schema_objects(db, &schema).await?);
+            } else if self.schema_exists(db, &schema).await? {
+                objects.push(SchemaObject::whole_schema(&schema));
+            }
+        }
+        Ok(objects)
+    }
+
+    async fn drop_schema_objects(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<SchemaObject>, DbErr> {
+        let objects = self.schema_objects_to_drop(db).await?;
+        for object in &objects {
+            db.execute_unprepared(&object.drop_sql()).await?;
+        }
+
+        Ok(objects)
+    }
+}
+
+/// Schemas named by successful `SCHEMA` history rows.
+fn created_schemas(history: &[HistoryRow]) -> BTreeSet<String> {
+    history
+        .iter()
+        .filter(|row| row.migration_type == SCHEMA_HISTORY_TYPE && row.success)
+        .flat_map(|row| parse_schema_list(&row.script))
+        .collect()
+}
+
+/// Flyway's `SCHEMA` row script: quoted names separated by commas, e.g.
+/// `"audit","billing"`.
+fn format_schema_list(schemas: &[String]) -> String {
+    schemas
+        .iter()
+        .map(|schema| quote_ident(schema))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_schema_list(script: &str) -> Vec<String> {
+    let mut schemas = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = script.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                current.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => schemas.push(std::mem::take(&mut current)),
+            _ => current.push(ch),
+        }
     }
+    schemas.push(current);
+    schemas.retain(|schema| !schema.is_empty());
+    schemas
 }
 
 fn build_status_report(
@@ -868,8 +2819,11 @@ fn build_status_report(
     history_table: &str,
     migrations: &[DiscoveredMigration],
     history: &[HistoryRow],
+    out_of_order: bool,
 ) -> StatusReport {
     let latest = latest_history_by_script(history);
+    let baseline = baseline_version(history);
+    let max_applied = max_applied_version(history);
     let local_by_script: HashMap<&str, &DiscoveredMigration> =
         migrations.iter().map(|m| (m.script.as_str(), m)).collect();
 
@@ -877,50 +2831,21 @@ fn build_status_report(
 
     for migration in migrations {
         let entry = match latest.get(migration.script.as_str()) {
-            Some(row) if !row.success => StatusEntry {
-                version: row.version.clone(),
-                description: row.description.clone(),
-                migration_type: row.migration_type.clone(),
-                script: row.script.clone(),
-                checksum: row.checksum,
-                installed_rank: Some(row.installed_rank),
-                installed_on: Some(row.installed_on.clone()),
-                execution_time_ms: Some(row.execution_time),
-                state: MigrationState::Failed,
-            },
-            Some(row) if row.checksum != migration.checksum => StatusEntry {
-                version: Some(migration.version_text.clone()),
-                description: migration.description_display.clone(),
-                migration_type: migration.migration_type.as_history_type().to_owned(),
-                script: migration.script.clone(),
-                checksum: migration.checksum,
-                installed_rank: Some(row.installed_rank),
-                installed_on: Some(row.installed_on.clone()),
-                execution_time_ms: Some(row.execution_time),
-                state: MigrationState::ChecksumMismatch,
-            },
-            Some(row) => StatusEntry {
-                version: Some(migration.version_text.clone()),
-                description: migration.description_display.clone(),
-                migration_type: migration.migration_type.as_history_type().to_owned(),
-                script: migration.script.clone(),
-                checksum: migration.checksum,
-                installed_rank: Some(row.installed_rank),
-                installed_on: Some(row.installed_on.clone()),
-                execution_time_ms: Some(row.execution_time),
-                state: MigrationState::Success,
-            },
-            None => StatusEntry {
-                version: Some(migration.version_text.clone()),
-                description: migration.description_display.clone(),
-                migration_type: migration.migration_type.as_history_type().to_owned(),
-                script: migration.script.clone(),
-                checksum: migration.checksum,
-                installed_rank: None,
-                installed_on: None,
-                execution_time_ms: None,
-                state: MigrationState::Pending,
-            },
+            Some(row) if !row.success => StatusEntry::from_history(row, MigrationState::Failed),
+            Some(row) if row.checksum != migration.checksum && migration.is_repeatable() => {
+                StatusEntry::from_migration(migration, Some(row), MigrationState::Outdated)
+            }
+            Some(row) if row.checksum != migration.checksum => {
+                StatusEntry::from_migration(migration, Some(row), MigrationState::ChecksumMismatch)
+            }
+            Some(row) => StatusEntry::from_migration(migration, Some(row), MigrationState::Success),
+            None if is_below_baseline(migration, baseline.as_ref()) => {
+                StatusEntry::from_migration(migration, None, MigrationState::BelowBaseline)
+            }
+            None if !out_of_order && is_out_of_order(migration, max_applied.as_ref()) => {
+                StatusEntry::from_migration(migration, None, MigrationState::Ignored)
+            }
+            None => StatusEntry::from_migration(migration, None, MigrationState::Pending),
         };
 
         entries.push(entry);
@@ -928,20 +2853,27 @@ fn build_status_report(
 
     for row in latest.values() {
         if row.success && !local_by_script.contains_key(row.script.as_str()) {
-            entries.push(StatusEntry {
-                version: row.version.clone(),
-                description: row.description.clone(),
-                migration_type: row.migration_type.clone(),
-                script: row.script.clone(),
-                checksum: row.checksum,
-                installed_rank: Some(row.installed_rank),
-                installed_on: Some(row.installed_on.clone()),
-                execution_time_ms: Some(row.execution_time),
-                state: MigrationState::Missing,
-            });
+            entries.push(StatusEntry::from_history(row, MigrationState::Missing));
         }
     }
 
+    for row in history.iter().filter(|row| {
+        row.migration_type == BASELINE_HISTORY_TYPE || row.migration_type == SCHEMA_HISTORY_TYPE
+    }) {
+        entries.push(StatusEntry::from_history(row, MigrationState::Success));
+    }
+
+    // Earlier successful runs of a repeatable migration that has run again since.
+    for row in history.iter().filter(|row| {
+        row.success
+            && row.version.is_none()
+            && latest
+                .get(row.script.as_str())
+                .is_some_and(|latest| latest.installed_rank != row.installed_rank)
+    }) {
+        entries.push(StatusEntry::from_history(row, MigrationState::Superseded));
+    }
+
     entries.sort_by(|a, b| {
         let a_version = a
             .version
@@ -952,8 +2884,11 @@ fn build_status_report(
             .as_ref()
             .and_then(|v| ParsedVersion::parse(v).ok());
 
-        a_version
-            .cmp(&b_version)
+        // Unversioned (repeatable) entries go last, as they run last.
+        a.version
+            .is_none()
+            .cmp(&b.version.is_none())
+            .then_with(|| a_version.cmp(&b_version))
             .then_with(|| a.script.cmp(&b.script))
             .then_with(|| a.installed_rank.cmp(&b.installed_rank))
     });
@@ -966,23 +2901,65 @@ fn build_status_report(
             MigrationState::Failed => summary.failed += 1,
             MigrationState::Missing => summary.missing += 1,
             MigrationState::ChecksumMismatch => summary.checksum_mismatch += 1,
+            MigrationState::BelowBaseline => summary.below_baseline += 1,
+            MigrationState::Outdated => summary.outdated += 1,
+            MigrationState::Superseded => summary.superseded += 1,
+            MigrationState::Ignored => summary.ignored += 1,
         }
     }
 
     StatusReport {
         schema: schema.to_owned(),
         history_table: history_table.to_owned(),
+        advisory_lock_id: None,
         migrations: entries,
         summary,
     }
 }
 
-async fn execute_sql_migration(manager: &SchemaManager<'_>, sql: &str) -> Result<(), DbErr> {
+/// A migration failure, with the failing statement when it is known.
+struct SqlFailure {
+    statement: Option<StatementSpan>,
+    source: DbErr,
+}
+
+impl From<DbErr> for SqlFailure {
+    fn from(source: DbErr) -> Self {
+        Self {
+            statement: None,
+            source,
+        }
+    }
+}
+
+impl SqlFailure {
+    fn into_callback_error(self, event: CallbackEvent, callback: &str) -> SchemalaneError {
+        SchemalaneError::Callback {
+            event,
+            callback: callback.to_owned(),
+            statement: self.statement.map(Box::new),
+            database_error: DatabaseErrorDetails::from_db_err(&self.source).map(Box::new),
+            handling: None,
+            source: self.source,
+        }
+    }
+}
+
+async fn execute_sql_migration(
+    manager: &SchemaManager<'_>,
+    sql: &str,
+    transaction_mode: TransactionMode,
+) -> Result<(), SqlFailure> {
+    let statements = split_statements(sql);
     let db = manager.get_connection();
+    if transaction_mode == TransactionMode::NoTransaction {
+        return execute_statements(db, &statements).await;
+    }
+
     let txn = db.begin().await?;
 
-    match txn.execute_unprepared(sql).await {
-        Ok(_) => txn.commit().await,
+    match execute_statements(&txn, &statements).await {
+        Ok(()) => Ok(txn.commit().await?),
         Err(err) => {
             let _ = txn.rollback().await;
             Err(err)
@@ -990,13 +2967,39 @@ async fn execute_sql_migration(manager: &SchemaManager<'_>, sql: &str) -> Result
     }
 }
 
+/// Runs statements one at a time so a failure can name its statement.
+async fn execute_statements<C: ConnectionTrait>(
+    db: &C,
+    statements: &[SqlStatement],
+) -> Result<(), SqlFailure> {
+    for statement in statements {
+        let failed = |source| SqlFailure {
+            statement: Some(statement.span),
+            source,
+        };
+
+        if statement.copy_data.is_some() {
+            let inserts = sql::copy_as_inserts(statement)
+                .map_err(|message| failed(DbErr::Custom(message)))?;
+            for insert in inserts {
+                db.execute_unprepared(&insert).await.map_err(failed)?;
+            }
+        } else {
+            db.execute_unprepared(&statement.sql)
+                .await
+                .map_err(failed)?;
+        }
+    }
+    Ok(())
+}
+
 async fn execute_rust_migration(
     manager: &SchemaManager<'_>,
     migration: &RustMigrationExecutor,
 ) -> Result<(), DbErr> {
     match migration.transaction_mode() {
-        RustTransactionMode::NoTransaction => migration.up(manager).await,
-        RustTransactionMode::Transaction => {
+        TransactionMode::NoTransaction => migration.up(manager).await,
+        TransactionMode::Transaction => {
             let db = manager.get_connection();
             let txn = db.begin().await?;
             let txn_manager = SchemaManager::new(&txn);
@@ -1012,57 +3015,376 @@ async fn execute_rust_migration(
     }
 }
 
+struct UpPlan<'a> {
+    pending: Vec<&'a DiscoveredMigration>,
+    unapplied: Vec<&'a DiscoveredMigration>,
+    skipped: usize,
+}
+
+fn unresolved_placeholders_error(unresolved: &[String]) -> SchemalaneError {
+    SchemalaneError::Validation(format!(
+        "unresolved placeholders: {} (define them in the placeholders config)",
+        unresolved.join(", ")
+    ))
+}
+
+/// Replaces each `prefix name suffix` in `sql` with the value of `name`. A
+/// name is everything up to the next suffix and must be non-empty without
+/// whitespace; anything else is left as written. Returns the unresolved names
+/// instead if there are any.
+fn replace_placeholders(
+    sql: &str,
+    values: &HashMap<String, String>,
+    prefix: &str,
+    suffix: &str,
+) -> Result<String, Vec<String>> {
+    if prefix.is_empty() || suffix.is_empty() {
+        return Ok(sql.to_owned());
+    }
+
+    let mut rendered = String::with_capacity(sql.len());
+    let mut unresolved: Vec<String> = Vec::new();
+    let mut rest = sql;
+    while let Some(start) = rest.find(prefix) {
+        let after_prefix = &rest[start + prefix.len()..];
+        let name = after_prefix
+            .find(suffix)
+            .map(|end| &after_prefix[..end])
+            .filter(|name| !name.is_empty() && !name.contains(char::is_whitespace));
+        let Some(name) = name else {
+            rendered.push_str(&rest[..start + prefix.len()]);
+            rest = after_prefix;
+            continue;
+        };
+
+        rendered.push_str(&rest[..start]);
+        match values.get(name) {
+            Some(value) => rendered.push_str(value),
+            None => {
+                if !unresolved.iter().any(|known| known == name) {
+                    unresolved.push(name.to_owned());
+                }
+            }
+        }
+        rest = &after_prefix[name.len() + suffix.len()..];
+    }
+    rendered.push_str(rest);
+
+    if unresolved.is_empty() {
+        Ok(rendered)
+    } else {
+        Err(unresolved)
+    }
+}
+
+/// Splits local migrations into those `up` applies now, those left pending
+/// because of `target`, and a count of those skipped as already applied.
+fn plan_up<'a>(
+    migrations: &'a [DiscoveredMigration],
+    history: &[HistoryRow],
+    target: &MigrationTarget,
+) -> UpPlan<'a> {
+    let baseline = baseline_version(history);
+    let mut plan = UpPlan {
+        pending: Vec::new(),
+        unapplied: Vec::new(),
+        skipped: 0,
+    };
+
+    for migration in migrations {
+        if is_applied_success(migration, history) || is_below_baseline(migration, baseline.as_ref())
+        {
+            plan.skipped += 1;
+            continue;
+        }
+
+        let within_target = match target {
+            MigrationTarget::Latest => true,
+            MigrationTarget::Version(target) => migration
+                .version
+                .as_ref()
+                .is_none_or(|version| version <= target),
+            MigrationTarget::Steps(steps) => plan.pending.len() < *steps,
+        };
+        if within_target {
+            plan.pending.push(migration);
+        } else {
+            plan.unapplied.push(migration);
+        }
+    }
+
+    plan
+}
+
+fn planned_steps(
+    pending: &[&DiscoveredMigration],
+    first_rank: i32,
+    installed_by: &str,
+) -> Vec<PlannedMigration> {
+    pending
+        .iter()
+        .zip(first_rank..)
+        .map(|(migration, rank)| PlannedMigration::from_migration(migration, rank, installed_by))
+        .collect()
+}
+
+fn ensure_target_exists(
+    migrations: &[DiscoveredMigration],
+    target: &MigrationTarget,
+) -> Result<(), SchemalaneError> {
+    if let MigrationTarget::Version(version) = target
+        && !migrations
+            .iter()
+            .any(|migration| migration.version.as_ref() == Some(version))
+    {
+        return Err(SchemalaneError::Validation(format!(
+            "target version '{target}' does not match any migration"
+        )));
+    }
+    Ok(())
+}
+
 fn is_applied_success(migration: &DiscoveredMigration, history: &[HistoryRow]) -> bool {
     latest_history_by_script(history)
         .get(migration.script.as_str())
         .is_some_and(|row| row.success && row.checksum == migration.checksum)
 }
 
+/// Latest history row per script. A `DELETE` marker written by `repair`
+/// hides every earlier row for its script; `BASELINE` and `SCHEMA` rows are
+/// not tied to a local script and are skipped.
 fn latest_history_by_script(history: &[HistoryRow]) -> HashMap<&str, &HistoryRow> {
     let mut latest = HashMap::new();
     for row in history {
-        latest.insert(row.script.as_str(), row);
+        if row.migration_type == BASELINE_HISTORY_TYPE || row.migration_type == SCHEMA_HISTORY_TYPE
+        {
+            continue;
+        }
+        if row.migration_type == DELETE_HISTORY_TYPE {
+            latest.remove(row.script.as_str());
+        } else {
+            latest.insert(row.script.as_str(), row);
+        }
     }
     latest
 }
 
-fn parse_sql_filename(file_name: &str) -> Result<(String, ParsedVersion, String), SchemalaneError> {
-    let captures = sql_migration_regex().captures(file_name).ok_or_else(|| {
-        SchemalaneError::Validation(format!(
-            "invalid SQL migration filename '{file_name}': expected V<version>__<description>.sql"
-        ))
-    })?;
+fn baseline_version(history: &[HistoryRow]) -> Option<ParsedVersion> {
+    history
+        .iter()
+        .rev()
+        .filter(|row| row.migration_type == BASELINE_HISTORY_TYPE && row.success)
+        .find_map(|row| {
+            row.version
+                .as_deref()
+                .and_then(|v| ParsedVersion::parse(v).ok())
+        })
+}
 
-    let version_text = captures
-        .name("version")
-        .ok_or_else(|| SchemalaneError::Validation("missing version capture".to_owned()))?
-        .as_str()
-        .to_owned();
+fn duplicate_issues(migrations: &[DiscoveredMigration]) -> Vec<ValidationIssue> {
+    let mut versions = BTreeSet::new();
+    let mut repeatables = BTreeSet::new();
+    let mut scripts = BTreeSet::new();
+    let mut issues = Vec::new();
 
-    let description = captures
-        .name("description")
-        .ok_or_else(|| SchemalaneError::Validation("missing description capture".to_owned()))?
-        .as_str()
-        .to_owned();
+    for migration in migrations {
+        let message = match &migration.version_text {
+            Some(version_text) if !versions.insert(version_text.as_str()) => {
+                Some(format!("duplicate migration version '{version_text}'"))
+            }
+            None if !repeatables.insert(migration.description.as_str()) => Some(format!(
+                "duplicate repeatable migration description '{}'",
+                migration.description
+            )),
+            _ if !scripts.insert(migration.script.as_str()) => {
+                Some(format!("duplicate migration script '{}'", migration.script))
+            }
+            _ => None,
+        };
+        if let Some(message) = message {
+            issues.push(ValidationIssue::new(
+                ValidationIssueKind::DuplicateMigration,
+                Some(&migration.script),
+                message,
+            ));
+        }
+    }
 
-    let parsed = ParsedVersion::parse(&version_text)?;
-    Ok((version_text, parsed, description))
+    issues
 }
 
-fn parse_rust_filename(
-    file_name: &str,
-) -> Result<(String, ParsedVersion, String), SchemalaneError> {
-    let captures = rust_migration_regex().captures(file_name).ok_or_else(|| {
-        SchemalaneError::Validation(format!(
-            "invalid Rust migration filename '{file_name}': expected V<version>__<description>.rs"
+/// Failed rows and drift between the history table and local migrations.
+fn history_issues(
+    migrations: &[DiscoveredMigration],
+    history: &[HistoryRow],
+) -> Vec<ValidationIssue> {
+    let latest = latest_history_by_script(history);
+    let local_by_script: HashMap<&str, &DiscoveredMigration> =
+        migrations.iter().map(|m| (m.script.as_str(), m)).collect();
+    let mut issues = Vec::new();
+
+    for row in latest.values() {
+        if !row.success {
+            issues.push(ValidationIssue::new(
+                ValidationIssueKind::Failed,
+                Some(&row.script),
+                format!("failed at installed_rank {}", row.installed_rank),
+            ));
+        } else if !local_by_script.contains_key(row.script.as_str()) {
+            issues.push(ValidationIssue::new(
+                ValidationIssueKind::Missing,
+                Some(&row.script),
+                "applied migration not found locally",
+            ));
+        }
+    }
+
+    for migration in migrations {
+        if let Some(row) = latest.get(migration.script.as_str())
+            && row.success
+            && row.checksum != migration.checksum
+            && !migration.is_repeatable()
+        {
+            issues.push(ValidationIssue::new(
+                ValidationIssueKind::ChecksumMismatch,
+                Some(&migration.script),
+                format!(
+                    "checksum {} in history, {} locally",
+                    format_checksum(row.checksum),
+                    format_checksum(migration.checksum)
+                ),
+            ));
+        }
+    }
+
+    issues.sort_by(|a, b| a.script.cmp(&b.script));
+    issues
+}
+
+fn format_checksum(checksum: Option<i32>) -> String {
+    checksum.map_or_else(|| "-".to_owned(), |checksum| checksum.to_string())
+}
+
+/// Highest version among applied migrations and the baseline.
+fn max_applied_version(history: &[HistoryRow]) -> Option<ParsedVersion> {
+    let applied = latest_history_by_script(history)
+        .into_values()
+        .filter(|row| row.success)
+        .filter_map(|row| {
+            row.version
+                .as_deref()
+                .and_then(|v| ParsedVersion::parse(v).ok())
+        });
+    applied.chain(baseline_version(history)).max()
+}
+
+fn is_out_of_order(migration: &DiscoveredMigration, max_applied: Option<&ParsedVersion>) -> bool {
+    match (max_applied, &migration.version) {
+        (Some(max_applied), Some(version)) => version < max_applied,
+        _ => false,
+    }
+}
+
+/// Versioned migrations that were never applied but sort below the highest
+/// applied version.
+fn out_of_order_migrations<'a>(
+    migrations: &'a [DiscoveredMigration],
+    history: &[HistoryRow],
+) -> Vec<&'a DiscoveredMigration> {
+    let latest = latest_history_by_script(history);
+    let baseline = baseline_version(history);
+    let max_applied = max_applied_version(history);
+    migrations
+        .iter()
+        .filter(|migration| {
+            !latest.contains_key(migration.script.as_str())
+                && !is_below_baseline(migration, baseline.as_ref())
+                && is_out_of_order(migration, max_applied.as_ref())
+        })
+        .collect()
+}
+
+fn is_below_baseline(migration: &DiscoveredMigration, baseline: Option<&ParsedVersion>) -> bool {
+    match (baseline, &migration.version) {
+        (Some(baseline), Some(version)) => version <= baseline,
+        _ => false,
+    }
+}
+
+struct RepairPlan<'a> {
+    failed: Vec<&'a HistoryRow>,
+    realign: Vec<(&'a HistoryRow, &'a DiscoveredMigration)>,
+    missing: Vec<&'a HistoryRow>,
+}
+
+fn plan_repair<'a>(
+    migrations: &'a [DiscoveredMigration],
+    history: &'a [HistoryRow],
+    mark_missing_deleted: bool,
+) -> RepairPlan<'a> {
+    let latest = latest_history_by_script(history);
+    let local_by_script: HashMap<&str, &DiscoveredMigration> =
+        migrations.iter().map(|m| (m.script.as_str(), m)).collect();
+
+    let failed = history.iter().filter(|row| !row.success).collect();
+
+    let mut realign = Vec::new();
+    let mut missing = Vec::new();
+    for row in latest.values().filter(|row| row.success) {
+        match local_by_script.get(row.script.as_str()) {
+            Some(migration) => {
+                if row.checksum != migration.checksum || row.description != migration.description {
+                    realign.push((*row, *migration));
+                }
+            }
+            None => {
+                if mark_missing_deleted {
+                    missing.push(*row);
+                }
+            }
+        }
+    }
+
+    realign.sort_by_key(|(row, _)| row.installed_rank);
+    missing.sort_by_key(|row| row.installed_rank);
+
+    RepairPlan {
+        failed,
+        realign,
+        missing,
+    }
+}
+
+/// Parses `V<version>__<description>.sql` or `R__<description>.sql`; the
+/// version is `None` for repeatable migrations.
+fn parse_sql_filename(file_name: &str) -> Result<ParsedFilename, SchemalaneError> {
+    let captures = sql_migration_regex().captures(file_name).ok_or_else(|| {
+        SchemalaneError::Validation(format!(
+            "invalid SQL migration filename '{file_name}': expected V<version>__<description>.sql or R__<description>.sql"
         ))
     })?;
 
+    parse_filename_captures(&captures)
+}
+
+fn parse_rust_filename(file_name: &str) -> Result<ParsedFilename, SchemalaneError> {
+    let captures = rust_migration_regex().captures(file_name).ok_or_else(|| {
+        SchemalaneError::Validation(format!(
+            "invalid Rust migration filename '{file_name}': expected V<version>__<description>.rs or R__<description>.rs"
+        ))
+    })?;
+
+    parse_filename_captures(&captures)
+}
+
+type ParsedFilename = (Option<String>, Option<ParsedVersion>, String);
+
+fn parse_filename_captures(
+    captures: &regex::Captures<'_>,
+) -> Result<ParsedFilename, SchemalaneError> {
     let version_text = captures
         .name("version")
-        .ok_or_else(|| SchemalaneError::Validation("missing version capture".to_owned()))?
-        .as_str()
-        .to_owned();
+        .map(|version| version.as_str().to_owned());
 
     let description = captures
         .name("description")
@@ -1070,10 +3392,93 @@ fn parse_rust_filename(
         .as_str()
         .to_owned();
 
-    let parsed = ParsedVersion::parse(&version_text)?;
+    let parsed = version_text
+        .as_deref()
+        .map(ParsedVersion::parse)
+        .transpose()?;
     Ok((version_text, parsed, description))
 }
 
+const NO_TRANSACTION_DIRECTIVE: &str = "schemalane:no-transaction";
+const LOCK_TIMEOUT_DIRECTIVE: &str = "schemalane:lock-timeout";
+const STATEMENT_TIMEOUT_DIRECTIVE: &str = "schemalane:statement-timeout";
+
+/// The comment lines at the top of a SQL script, before its first statement,
+/// without their `--`.
+fn header_comments(sql: &str) -> impl Iterator<Item = &str> {
+    sql.trim_start_matches('\u{feff}')
+        .lines()
+        .map(str::trim)
+        .take_while(|line| line.is_empty() || line.starts_with("--"))
+        .filter_map(|line| line.strip_prefix("--"))
+        .map(str::trim)
+}
+
+/// Looks for `-- schemalane:no-transaction` in the header comments.
+fn header_transaction_mode(sql: &str) -> Option<TransactionMode> {
+    header_comments(sql)
+        .any(|comment| comment == NO_TRANSACTION_DIRECTIVE)
+        .then_some(TransactionMode::NoTransaction)
+}
+
+/// Reads `-- schemalane:lock-timeout=<duration>` and
+/// `-- schemalane:statement-timeout=<duration>` from the header comments.
+fn header_timeouts(script: &str, sql: &str) -> Result<MigrationTimeouts, SchemalaneError> {
+    let mut timeouts = MigrationTimeouts::default();
+    for comment in header_comments(sql) {
+        let Some((directive, value)) = comment.split_once('=') else {
+            continue;
+        };
+        let directive = directive.trim();
+        let slot = match directive {
+            LOCK_TIMEOUT_DIRECTIVE => &mut timeouts.lock_timeout,
+            STATEMENT_TIMEOUT_DIRECTIVE => &mut timeouts.statement_timeout,
+            _ => continue,
+        };
+        let value = value.trim();
+        *slot = Some(parse_timeout_value(value).ok_or_else(|| {
+            SchemalaneError::Validation(format!(
+                "{script}: invalid {directive} '{value}': {TIMEOUT_FORMAT}"
+            ))
+        })?);
+    }
+    Ok(timeouts)
+}
+
+/// Parses a Flyway-style `<script>.conf` sidecar. Only
+/// `executeInTransaction` is supported; blank lines and `#` comments are
+/// skipped.
+fn parse_script_config(
+    script: &str,
+    conf: &str,
+) -> Result<Option<TransactionMode>, SchemalaneError> {
+    let mut mode = None;
+    for line in conf.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = |reason: String| {
+            SchemalaneError::Validation(format!("invalid {script}.conf line '{line}': {reason}"))
+        };
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid("expected key=value".to_owned()))?;
+        if key.trim() != "executeInTransaction" {
+            return Err(invalid(format!(
+                "unsupported key '{}', only executeInTransaction is supported",
+                key.trim()
+            )));
+        }
+        mode = Some(match value.trim() {
+            "true" => TransactionMode::Transaction,
+            "false" => TransactionMode::NoTransaction,
+            other => return Err(invalid(format!("expected true or false, got '{other}'"))),
+        });
+    }
+    Ok(mode)
+}
+
 fn validate_version(version: &str) -> Result<(), SchemalaneError> {
     if version_regex().is_match(version) {
         Ok(())
@@ -1088,7 +3493,7 @@ fn validate_version(version: &str) -> Result<(), SchemalaneError> {
 fn sql_migration_regex() -> &'static Regex {
     static REGEX: OnceLock<Regex> = OnceLock::new();
     REGEX.get_or_init(|| {
-        Regex::new(r"^V(?P<version>[0-9]+(?:[._][0-9]+)*)__(?P<description>[a-z0-9_]+)\.sql$")
+        Regex::new(r"^(?:V(?P<version>[0-9]+(?:[._][0-9]+)*)|R)__(?P<description>[a-z0-9_]+)\.sql$")
             .expect("valid SQL migration regex")
     })
 }
@@ -1097,7 +3502,7 @@ fn sql_migration_regex() -> &'static Regex {
 fn rust_migration_regex() -> &'static Regex {
     static REGEX: OnceLock<Regex> = OnceLock::new();
     REGEX.get_or_init(|| {
-        Regex::new(r"^V(?P<version>[0-9]+(?:[._][0-9]+)*)__(?P<description>[a-z0-9_]+)\.rs$")
+        Regex::new(r"^(?:V(?P<version>[0-9]+(?:[._][0-9]+)*)|R)__(?P<description>[a-z0-9_]+)\.rs$")
             .expect("valid Rust migration regex")
     })
 }
@@ -1116,7 +3521,10 @@ fn qualified_table(schema: &str, table: &str) -> String {
     format!("{}.{}", quote_ident(schema), quote_ident(table))
 }
 
-#[expect(clippy::cast_possible_truncation, reason = "guarded by the preceding bounds check")]
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "guarded by the preceding bounds check"
+)]
 const fn millis_i32(millis: u128) -> i32 {
     if millis > i32::MAX as u128 {
         i32::MAX
@@ -1125,12 +3533,29 @@ const fn millis_i32(millis: u128) -> i32 {
     }
 }
 
+/// A timeout as a Postgres setting value. Rounds up to whole milliseconds,
+/// so a sub-millisecond timeout does not become `0`, which disables it.
+fn timeout_setting(timeout: Duration) -> String {
+    format!("{}ms", timeout.as_nanos().div_ceil(1_000_000))
+}
+
 fn calculate_checksum(bytes: &[u8]) -> i32 {
     let mut hasher = Hasher::new();
     hasher.update(bytes);
     i32::from_be_bytes(hasher.finalize().to_be_bytes())
 }
 
+/// Flyway feeds each line to CRC32 without its terminator (`\n`, `\r` or
+/// `\r\n`) after dropping a UTF-8 BOM from the first line.
+fn calculate_flyway_checksum(bytes: &[u8]) -> i32 {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let mut hasher = Hasher::new();
+    for line in bytes.split(|byte| matches!(byte, b'\n' | b'\r')) {
+        hasher.update(line);
+    }
+    i32::from_be_bytes(hasher.finalize().to_be_bytes())
+}
+
 fn write_init_file(
     path: &Path,
     content: &str,
@@ -1269,6 +3694,9 @@ This crate runs Schemalane migrations programmatically and exposes a small CLI:
 - `up`
 - `status`
 - `fresh`
+- `repair`
+- `baseline`
+- `validate`
 
 Rust migration registration is automatic via `embed_migrations!("./migrations")`.
 
@@ -1325,14 +3753,14 @@ INSERT INTO cake(name) VALUES ('chocolate');
 }
 "##;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum MigrationType {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum MigrationType {
     Sql,
     Rust,
 }
 
 impl MigrationType {
-    const fn as_history_type(self) -> &'static str {
+    pub const fn as_history_type(self) -> &'static str {
         match self {
             Self::Sql => "SQL",
             Self::Rust => "RUST",
@@ -1340,21 +3768,126 @@ impl MigrationType {
     }
 }
 
-#[derive(Clone)]
-struct DiscoveredMigration {
-    version: ParsedVersion,
-    version_text: String,
-    description_display: String,
-    script: String,
-    checksum: Option<i32>,
-    migration_type: MigrationType,
-    source: MigrationSource,
+/// A migration as produced by a `MigrationSource`, before it is matched
+/// against the history table.
+///
+/// Repeatable (`R__`) migrations have no version.
+#[derive(Debug, Clone)]
+pub struct DiscoveredMigration {
+    pub version: Option<ParsedVersion>,
+    pub version_text: Option<String>,
+    pub description: String,
+    pub script: String,
+    pub checksum: Option<i32>,
+    pub migration_type: MigrationType,
+    pub transaction_mode: TransactionMode,
+    /// Overrides of `SchemalaneConfig::migration_timeouts` for this migration.
+    pub timeouts: MigrationTimeouts,
+    pub body: MigrationBody,
 }
 
-#[derive(Clone)]
-enum MigrationSource {
-    SqlFile(PathBuf),
-    RustFile(PathBuf),
+impl DiscoveredMigration {
+    /// Build a SQL migration from its script name and text, computing the
+    /// checksum from the text.
+    pub fn sql<S>(
+        script: &str,
+        sql: S,
+        checksum_algorithm: ChecksumAlgorithm,
+    ) -> Result<Self, SchemalaneError>
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        let sql = sql.into();
+        let checksum = checksum_algorithm.checksum(sql.as_bytes());
+        Self::sql_with_checksum(script, sql, checksum)
+    }
+
+    /// Build a SQL migration whose checksum was computed ahead of time.
+    pub fn sql_with_checksum<S>(
+        script: &str,
+        sql: S,
+        checksum: i32,
+    ) -> Result<Self, SchemalaneError>
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        let (version_text, version, description) = parse_sql_filename(script)?;
+        let sql = sql.into();
+        let migration = Self {
+            version,
+            version_text,
+            description: description.replace('_', " "),
+            script: script.to_owned(),
+            checksum: Some(checksum),
+            migration_type: MigrationType::Sql,
+            transaction_mode: header_transaction_mode(&sql).unwrap_or(TransactionMode::Transaction),
+            timeouts: header_timeouts(script, &sql)?,
+            body: MigrationBody::Sql(sql),
+        };
+        migration.ensure_timeouts_have_transaction()?;
+        Ok(migration)
+    }
+
+    /// `SET LOCAL` only works inside a transaction, so timeout directives
+    /// contradict a no-transaction script.
+    fn ensure_timeouts_have_transaction(&self) -> Result<(), SchemalaneError> {
+        if self.transaction_mode == TransactionMode::NoTransaction && self.timeouts.is_set() {
+            return Err(SchemalaneError::Validation(format!(
+                "{} sets a timeout directive but runs outside a transaction, where it cannot apply",
+                self.script
+            )));
+        }
+        Ok(())
+    }
+
+    /// Apply a Flyway-style `<script>.conf` sidecar (`executeInTransaction=false`).
+    ///
+    /// Fails if the sidecar contradicts a `-- schemalane:no-transaction` header.
+    pub fn with_script_config(mut self, conf: &str) -> Result<Self, SchemalaneError> {
+        let Some(mode) = parse_script_config(&self.script, conf)? else {
+            return Ok(self);
+        };
+
+        if let MigrationBody::Sql(sql) = &self.body
+            && header_transaction_mode(sql).is_some_and(|header| header != mode)
+        {
+            return Err(SchemalaneError::Validation(format!(
+                "{}.conf sets executeInTransaction=true but the script has a -- {NO_TRANSACTION_DIRECTIVE} header",
+                self.script
+            )));
+        }
+
+        self.transaction_mode = mode;
+        self.ensure_timeouts_have_transaction()?;
+        Ok(self)
+    }
+
+    /// Build a Rust migration; its executor is looked up by `script` at run
+    /// time and decides the transaction mode.
+    pub fn rust(script: &str, checksum: i32) -> Result<Self, SchemalaneError> {
+        let (version_text, version, description) = parse_rust_filename(script)?;
+        Ok(Self {
+            version,
+            version_text,
+            description: description.replace('_', " "),
+            script: script.to_owned(),
+            checksum: Some(checksum),
+            migration_type: MigrationType::Rust,
+            transaction_mode: TransactionMode::NoTransaction,
+            timeouts: MigrationTimeouts::default(),
+            body: MigrationBody::Rust,
+        })
+    }
+
+    pub const fn is_repeatable(&self) -> bool {
+        self.version.is_none()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum MigrationBody {
+    Sql(Cow<'static, str>),
+    Rust,
 }
 
 #[derive(Debug, Clone)]
@@ -1368,6 +3901,9 @@ struct HistoryRow {
     installed_on: String,
     execution_time: i32,
     success: bool,
+    /// `None` for rows that ran no script (`BASELINE`, `DELETE`) or were
+    /// written by Flyway.
+    transaction_mode: Option<TransactionMode>,
 }
 
 impl HistoryRow {
@@ -1379,23 +3915,24 @@ impl HistoryRow {
     ) -> Self {
         Self {
             installed_rank,
-            version: Some(migration.version_text.clone()),
-            description: migration.description_display.clone(),
+            version: migration.version_text.clone(),
+            description: migration.description.clone(),
             migration_type: migration.migration_type.as_history_type().to_owned(),
             script: migration.script.clone(),
             checksum: migration.checksum,
             installed_on: String::new(),
             execution_time,
             success,
+            transaction_mode: Some(migration.transaction_mode),
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-struct ParsedVersion(Vec<u64>);
+pub struct ParsedVersion(Vec<u64>);
 
 impl ParsedVersion {
-    fn parse(value: &str) -> Result<Self, SchemalaneError> {
+    pub fn parse(value: &str) -> Result<Self, SchemalaneError> {
         validate_version(value)?;
 
         let mut segments = Vec::new();
@@ -1408,28 +3945,94 @@ impl ParsedVersion {
 
         Ok(Self(segments))
     }
+
+    pub fn segments(&self) -> &[u64] {
+        &self.0
+    }
+}
+
+pub fn format_migration_plan(plan: &MigrationPlan) -> String {
+    let mut lines = Vec::new();
+    lines.push(format!(
+        "-- dry run: schema={}, history_table={}",
+        plan.schema, plan.history_table
+    ));
+    for object in &plan.dropped_objects {
+        lines.push(format!(
+            "-- would drop {} {}",
+            object.kind,
+            object.qualified_name()
+        ));
+    }
+    lines.push(format!(
+        "-- would apply {} migration(s), skip {}",
+        plan.steps.len(),
+        plan.skipped
+    ));
+
+    for step in &plan.steps {
+        lines.push(String::new());
+        lines.push(format!(
+            "-- [{}] {} {} ({}) type={} checksum={} installed_by={} transaction={}",
+            step.installed_rank,
+            step.version.as_deref().unwrap_or("R"),
+            step.description,
+            step.script,
+            step.migration_type,
+            step.checksum
+                .map_or_else(|| "-".to_owned(), |checksum| checksum.to_string()),
+            step.installed_by,
+            step.transaction_mode.as_label()
+        ));
+        match &step.sql {
+            Some(sql) => lines.push(sql.trim_end().to_owned()),
+            None => lines.push("-- Rust migration: runs its registered executor".to_owned()),
+        }
+    }
+
+    if !plan.unapplied.is_empty() {
+        lines.push(String::new());
+        lines.push(format!(
+            "-- would leave {} pending migration(s) beyond the target:",
+            plan.unapplied.len()
+        ));
+        for migration in &plan.unapplied {
+            lines.push(format!("--   {}", migration.script));
+        }
+    }
+
+    lines.join("\n")
 }
 
 pub fn format_status_table(report: &StatusReport) -> String {
     let mut lines = Vec::new();
     lines.push(format!(
-        "schema={}, history_table={}",
-        report.schema, report.history_table
+        "schema={}, history_table={}, advisory_lock_id={}",
+        report.schema,
+        report.history_table,
+        report
+            .advisory_lock_id
+            .map_or_else(|| "disabled".to_owned(), |lock_id| lock_id.to_string())
     ));
     lines.push(
-        "version | description | type | script | state | rank | execution_time_ms".to_owned(),
+        "version | description | type | script | transaction | state | rank | execution_time_ms"
+            .to_owned(),
     );
     lines.push(
-        "--------|-------------|------|--------|-------|------|------------------".to_owned(),
+        "--------|-------------|------|--------|-------------|-------|------|------------------"
+            .to_owned(),
     );
 
     for migration in &report.migrations {
         lines.push(format!(
-            "{} | {} | {} | {} | {:?} | {} | {}",
+            "{} | {} | {} | {} | {} | {:?} | {} | {}",
             migration.version.as_deref().unwrap_or("-"),
             migration.description,
             migration.migration_type,
             migration.script,
+            migration
+                .transaction_mode
+                .map_or("-", TransactionMode::as_label),
             migration.state,
             migration
                 .installed_rank
@@ -1442,17 +4045,92 @@ pub fn format_status_table(report: &StatusReport) -> String {
 
     lines.push(String::new());
     lines.push(format!(
-        "summary: success={}, pending={}, failed={}, missing={}, checksum_mismatch={}",
+        "summary: success={}, pending={}, failed={}, missing={}, checksum_mismatch={}, below_baseline={}, outdated={}, superseded={}, ignored={}",
         report.summary.success,
         report.summary.pending,
         report.summary.failed,
         report.summary.missing,
-        report.summary.checksum_mismatch
+        report.summary.checksum_mismatch,
+        report.summary.below_baseline,
+        report.summary.outdated,
+        report.summary.superseded,
+        report.summary.ignored
     ));
 
     lines.join("\n")
 }
 
+/// Maps a `ValidationReport` to the error (and exit code) `up` would fail
+/// with: local validation problems first, then failed rows, then drift.
+pub fn ensure_valid(report: &ValidationReport) -> Result<(), SchemalaneError> {
+    let scripts_of = |kind: ValidationIssueKind| -> Vec<&str> {
+        report
+            .issues
+            .iter()
+            .filter(|issue| issue.kind == kind)
+            .filter_map(|issue| issue.script.as_deref())
+            .collect()
+    };
+
+    let invalid: Vec<_> = report
+        .issues
+        .iter()
+        .filter(|issue| !issue.kind.is_drift() && issue.kind != ValidationIssueKind::Failed)
+        .map(|issue| {
+            issue.script.as_ref().map_or_else(
+                || issue.message.clone(),
+                |script| format!("{script}: {}", issue.message),
+            )
+        })
+        .collect();
+    if !invalid.is_empty() {
+        return Err(SchemalaneError::Validation(invalid.join("; ")));
+    }
+
+    let failed = scripts_of(ValidationIssueKind::Failed);
+    if !failed.is_empty() {
+        return Err(SchemalaneError::FailedHistory(failed.join(", ")));
+    }
+
+    let mut drift_items = Vec::new();
+    let missing = scripts_of(ValidationIssueKind::Missing);
+    if !missing.is_empty() {
+        drift_items.push(format!("missing: {}", missing.join(", ")));
+    }
+    let checksum_mismatch = scripts_of(ValidationIssueKind::ChecksumMismatch);
+    if !checksum_mismatch.is_empty() {
+        drift_items.push(format!(
+            "checksum mismatch: {}",
+            checksum_mismatch.join(", ")
+        ));
+    }
+    if !drift_items.is_empty() {
+        return Err(SchemalaneError::Drift(drift_items.join("; ")));
+    }
+
+    Ok(())
+}
+
+pub fn format_validation_report(report: &ValidationReport) -> String {
+    if report.is_valid() {
+        return "validation passed".to_owned();
+    }
+
+    let mut lines = vec![format!(
+        "validation failed: {} problem(s)",
+        report.issues.len()
+    )];
+    for issue in &report.issues {
+        lines.push(format!(
+            "- [{:?}] {}: {}",
+            issue.kind,
+            issue.script.as_deref().unwrap_or("-"),
+            issue.message
+        ));
+    }
+    lines.join("\n")
+}
+
 pub const fn should_fail_on_pending(report: &StatusReport) -> Result<(), SchemalaneError> {
     if report.summary.pending > 0 {
         Err(SchemalaneError::PendingMigrations(report.summary.pending))
@@ -1468,25 +4146,431 @@ pub fn migrations_dir_exists(path: &Path) -> bool {
 #[cfg(test)]
 mod tests {
     use super::{
-        ParsedVersion, SchemalaneError, init_migration_project, parse_rust_filename,
-        parse_sql_filename,
+        AdvisoryLock, BASELINE_HISTORY_TYPE, CallbackEvent, ChecksumAlgorithm,
+        DEFAULT_BASELINE_DESCRIPTION, DELETE_HISTORY_TYPE, DatabaseErrorDetails,
+        DiscoveredMigration, EmbeddedMigration, FilesystemMigrationSource, HistoryRow,
+        InMemoryMigrationSource, LockHolder, MigrationBody, MigrationPlan, MigrationSource,
+        MigrationState, MigrationTarget, MigrationTimeoutKind, MigrationTimeouts, MigrationType,
+        ParsedVersion, RetryPolicy, RustMigrationExecutor, SCHEMA_HISTORY_TYPE, SchemaObject,
+        SchemaObjectKind, SchemalaneConfig, SchemalaneError, SchemalaneMigrator, StatementSpan,
+        TransactionMode, ValidationIssueKind, ValidationReport, build_status_report,
+        created_schemas, derive_advisory_lock_id, duplicate_issues, ensure_target_exists,
+        ensure_valid, format_migration_plan, format_schema_list, history_issues,
+        init_migration_project, parse_rust_filename, parse_sql_filename, parse_timeout,
+        plan_repair, plan_up, planned_steps,
     };
+    use sea_orm::DbErr;
+    use std::collections::HashMap;
     use std::fs;
+    use std::path::PathBuf;
+    use std::time::Duration;
     use tempfile::TempDir;
 
     #[test]
     fn parses_sql_filename() {
         let (version, parsed, description) =
             parse_sql_filename("V2026.02.24.1__price_histories.sql").expect("valid filename");
-        assert_eq!(version, "2026.02.24.1");
+        assert_eq!(version.as_deref(), Some("2026.02.24.1"));
         assert_eq!(description, "price_histories");
         assert_eq!(
             parsed,
-            ParsedVersion(vec![2026, 2, 24, 1]),
+            Some(ParsedVersion(vec![2026, 2, 24, 1])),
             "version segments should parse numerically"
         );
     }
 
+    #[test]
+    fn parses_repeatable_filenames_without_version() {
+        let (version, parsed, description) =
+            parse_sql_filename("R__price_view.sql").expect("valid repeatable filename");
+        assert_eq!(version, None);
+        assert_eq!(parsed, None);
+        assert_eq!(description, "price_view");
+
+        let (version, _, description) =
+            parse_rust_filename("R__grants.rs").expect("valid repeatable filename");
+        assert_eq!(version, None);
+        assert_eq!(description, "grants");
+
+        parse_sql_filename("R1__price_view.sql").expect_err("repeatables take no version");
+    }
+
+    #[test]
+    fn sql_transaction_mode_comes_from_header_or_sidecar() {
+        let temp = TempDir::new().expect("temp dir");
+        fs::write(
+            temp.path().join("V1__create_cake.sql"),
+            "CREATE TABLE cake ();",
+        )
+        .expect("write V1");
+        fs::write(
+            temp.path().join("V2__index_cake.sql"),
+            "-- Builds without blocking writes.\n-- schemalane:no-transaction\n\nCREATE INDEX CONCURRENTLY cake_idx ON cake (id);",
+        )
+        .expect("write V2");
+        fs::write(temp.path().join("V3__vacuum_cake.sql"), "VACUUM cake;").expect("write V3");
+        fs::write(
+            temp.path().join("V3__vacuum_cake.sql.conf"),
+            "# copied from Flyway\nexecuteInTransaction=false\n",
+        )
+        .expect("write V3 sidecar");
+
+        let migrations = FilesystemMigrationSource::new(temp.path())
+            .discover(ChecksumAlgorithm::Crc32)
+            .expect("discovery succeeds")
+            .migrations;
+        let mut modes: Vec<_> = migrations
+            .iter()
+            .map(|m| (m.script.as_str(), m.transaction_mode))
+            .collect();
+        modes.sort_unstable_by_key(|(script, _)| *script);
+        assert_eq!(
+            modes,
+            [
+                ("V1__create_cake.sql", TransactionMode::Transaction),
+                ("V2__index_cake.sql", TransactionMode::NoTransaction),
+                ("V3__vacuum_cake.sql", TransactionMode::NoTransaction),
+            ]
+        );
+
+        let late_directive = DiscoveredMigration::sql(
+            "V4__late.sql",
+            "SELECT 1;\n-- schemalane:no-transaction\n",
+            ChecksumAlgorithm::Crc32,
+        )
+        .expect("valid migration");
+        assert_eq!(
+            late_directive.transaction_mode,
+            TransactionMode::Transaction
+        );
+
+        let header = DiscoveredMigration::sql(
+            "V5__header.sql",
+            "-- schemalane:no-transaction\nVACUUM;",
+            ChecksumAlgorithm::Crc32,
+        )
+        .expect("valid migration");
+        header
+            .clone()
+            .with_script_config("executeInTransaction=true")
+            .expect_err("sidecar contradicts header");
+        let err = header
+            .with_script_config("encoding=UTF-8")
+            .expect_err("unsupported sidecar key");
+        assert!(
+            err.to_string()
+                .contains("only executeInTransaction is supported"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    #[expect(
+        clippy::literal_string_with_formatting_args,
+        reason = "the SQL uses placeholders"
+    )]
+    fn placeholders_are_substituted_without_changing_checksums() {
+        let source = InMemoryMigrationSource::new()
+            .with_sql(
+                "V1__grant_app.sql",
+                "GRANT SELECT ON ${schemalane:defaultSchema}.cake TO ${app_role};\nSELECT '${not a placeholder}', '$${app_role}';",
+            )
+            .with_sql("V2__missing.sql", "SELECT ${tenant}, ${region}, ${tenant};")
+            .with_rust("V3__seed_cake.rs", 7);
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            schema: "bakery".to_owned(),
+            placeholders: HashMap::from([("app_role".to_owned(), "baker".to_owned())]),
+            ..SchemalaneConfig::default()
+        })
+        .with_source(source)
+        .with_rust_migration(
+            "V3__seed_cake.rs",
+            RustMigrationExecutor::new(|_| Box::pin(async { Ok(()) })),
+        );
+        let migrations = migrator.discover_migrations().expect("discovery succeeds");
+        let values = HashMap::from([
+            ("app_role".to_owned(), "baker".to_owned()),
+            ("schemalane:defaultSchema".to_owned(), "bakery".to_owned()),
+        ]);
+
+        let rendered = migrator
+            .render_placeholders(&[&migrations[0], &migrations[2]], &values)
+            .expect("all placeholders resolve");
+        assert!(matches!(
+            &rendered[0].body,
+            MigrationBody::Sql(sql) if sql == "GRANT SELECT ON bakery.cake TO baker;\nSELECT '${not a placeholder}', '$baker';"
+        ));
+        assert_eq!(rendered[0].checksum, migrations[0].checksum);
+        assert!(matches!(rendered[1].body, MigrationBody::Rust));
+
+        let err = migrator
+            .render_placeholders(&[&migrations[0], &migrations[1]], &values)
+            .expect_err("V2 uses undefined placeholders");
+        assert_eq!(err.exit_code(), 2);
+        assert!(
+            err.to_string()
+                .contains("unresolved placeholders: V2__missing.sql (${tenant}, ${region})"),
+            "unexpected error: {err}"
+        );
+
+        let custom = SchemalaneMigrator::new(SchemalaneConfig {
+            placeholder_prefix: "{{".to_owned(),
+            placeholder_suffix: "}}".to_owned(),
+            ..SchemalaneConfig::default()
+        });
+        assert_eq!(
+            custom.replace_placeholders("SELECT '${app_role}', {{app_role}};", &values),
+            Ok("SELECT '${app_role}', baker;".to_owned())
+        );
+        let disabled = SchemalaneMigrator::new(SchemalaneConfig {
+            placeholder_prefix: String::new(),
+            ..SchemalaneConfig::default()
+        });
+        assert_eq!(
+            disabled.replace_placeholders("SELECT ${tenant};", &values),
+            Ok("SELECT ${tenant};".to_owned())
+        );
+    }
+
+    #[test]
+    fn callback_errors_name_the_event_and_callback() {
+        assert_eq!(
+            CallbackEvent::parse("afterMigrateError").expect("valid event"),
+            CallbackEvent::AfterMigrateError
+        );
+        CallbackEvent::parse("afterUndo").expect_err("unsupported event");
+        assert_eq!(
+            CallbackEvent::from_script("beforeEachMigrate__set_timeouts.sql"),
+            Some(CallbackEvent::BeforeEachMigrate)
+        );
+        assert_eq!(CallbackEvent::from_script("beforeMigrate.rs"), None);
+        assert_eq!(CallbackEvent::from_script("V1__beforeMigrate.sql"), None);
+
+        let failure = SchemalaneError::MigrationExecution {
+            script: "V2__seed_cake.sql".to_owned(),
+            statement: None,
+            database_error: None,
+            attempts: 1,
+            source: DbErr::Custom("boom".to_owned()),
+        };
+        let err = SchemalaneError::Callback {
+            event: CallbackEvent::AfterMigrateError,
+            callback: "afterMigrateError.sql".to_owned(),
+            statement: Some(Box::new(StatementSpan {
+                start_line: 2,
+                start_column: 1,
+                end_line: 2,
+                end_column: 30,
+            })),
+            database_error: None,
+            handling: None,
+            source: DbErr::Custom("audit table missing".to_owned()),
+        }
+        .while_handling(failure);
+        assert_eq!(
+            err.to_string(),
+            "afterMigrateError callback afterMigrateError.sql failed at line 2, columns 1-30: Custom Error: audit table missing (while handling: Migration execution failed for V2__seed_cake.sql: Custom Error: boom)"
+        );
+        assert_eq!(err.exit_code(), 1);
+    }
+
+    #[test]
+    fn timeout_directives_override_config_defaults() {
+        let migration = DiscoveredMigration::sql(
+            "V2__add_cake_column.sql",
+            "-- Waits at most 3s for the table lock.\n-- schemalane:lock-timeout=3s\nALTER TABLE cake ADD COLUMN price INT;",
+            ChecksumAlgorithm::Crc32,
+        )
+        .expect("valid directive");
+        let defaults = MigrationTimeouts {
+            lock_timeout: Some(Duration::from_secs(10)),
+            statement_timeout: Some(Duration::from_mins(5)),
+        };
+        let effective = migration.timeouts.or(defaults);
+        assert_eq!(effective.lock_timeout, Some(Duration::from_secs(3)));
+        assert_eq!(effective.statement_timeout, Some(Duration::from_mins(5)));
+        assert_eq!(
+            effective.set_local_sql(),
+            "SET LOCAL lock_timeout = '3000ms'; SET LOCAL statement_timeout = '300000ms'"
+        );
+        assert_eq!(
+            MigrationTimeouts::default().set_local_sql(),
+            "SET LOCAL lock_timeout TO DEFAULT; SET LOCAL statement_timeout TO DEFAULT"
+        );
+        let sub_millisecond = MigrationTimeouts {
+            lock_timeout: Some(Duration::from_micros(1)),
+            statement_timeout: Some(Duration::from_micros(1_500)),
+        };
+        assert_eq!(
+            sub_millisecond.set_local_sql(),
+            "SET LOCAL lock_timeout = '1ms'; SET LOCAL statement_timeout = '2ms'"
+        );
+
+        let err = DiscoveredMigration::sql(
+            "V3__slow.sql",
+            "-- schemalane:statement-timeout=soon\nSELECT 1;",
+            ChecksumAlgorithm::Crc32,
+        )
+        .expect_err("invalid duration");
+        assert!(
+            err.to_string()
+                .contains("V3__slow.sql: invalid schemalane:statement-timeout 'soon'"),
+            "unexpected error: {err}"
+        );
+        let err = DiscoveredMigration::sql(
+            "V4__index.sql",
+            "-- schemalane:no-transaction\n-- schemalane:lock-timeout=1s\nCREATE INDEX CONCURRENTLY i ON cake (id);",
+            ChecksumAlgorithm::Crc32,
+        )
+        .expect_err("SET LOCAL needs a transaction");
+        assert!(
+            err.to_string().contains("runs outside a transaction"),
+            "unexpected error: {err}"
+        );
+
+        assert_eq!(
+            parse_timeout("250").expect("bare millis"),
+            Duration::from_millis(250)
+        );
+        assert_eq!(parse_timeout("1h").expect("hours"), Duration::from_hours(1));
+        parse_timeout("5 weeks").expect_err("unsupported unit");
+
+        let lock_not_available = DatabaseErrorDetails {
+            code: "55P03".to_owned(),
+            detail: None,
+            hint: None,
+        };
+        let lock_timeout = MigrationTimeouts {
+            lock_timeout: Some(Duration::from_secs(1)),
+            statement_timeout: None,
+        };
+        assert_eq!(
+            MigrationTimeoutKind::from_failure(Some(&lock_not_available), lock_timeout),
+            Some(MigrationTimeoutKind::LockTimeout)
+        );
+        // NOWAIT fails with the same SQLSTATE without any timeout.
+        assert_eq!(
+            MigrationTimeoutKind::from_failure(
+                Some(&lock_not_available),
+                MigrationTimeouts::default()
+            ),
+            None
+        );
+        let cancelled = DatabaseErrorDetails {
+            code: "57014".to_owned(),
+            ..lock_not_available
+        };
+        let statement_timeout = |timeout| MigrationTimeouts {
+            lock_timeout: None,
+            statement_timeout: Some(timeout),
+        };
+        assert_eq!(
+            MigrationTimeoutKind::from_failure(
+                Some(&cancelled),
+                statement_timeout(Duration::from_secs(1))
+            ),
+            Some(MigrationTimeoutKind::StatementTimeout)
+        );
+        // Without a timeout of ours, the cancel came from someone else.
+        assert_eq!(
+            MigrationTimeoutKind::from_failure(Some(&cancelled), MigrationTimeouts::default()),
+            None
+        );
+        assert_eq!(
+            MigrationTimeoutKind::from_failure(Some(&cancelled), statement_timeout(Duration::ZERO)),
+            None
+        );
+    }
+
+    #[test]
+    fn lock_and_deadlock_failures_are_retried_with_capped_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(1),
+        };
+        let waits: Vec<_> = (1..=5).map(|attempt| policy.backoff(attempt)).collect();
+        assert_eq!(
+            waits,
+            [200, 400, 800, 1_000, 1_000].map(Duration::from_millis)
+        );
+
+        let failure = |code: &str| SchemalaneError::MigrationExecution {
+            script: "V2__add_cake_column.sql".to_owned(),
+            statement: None,
+            database_error: Some(Box::new(DatabaseErrorDetails {
+                code: code.to_owned(),
+                detail: None,
+                hint: None,
+            })),
+            attempts: 1,
+            source: DbErr::Custom("failed".to_owned()),
+        };
+        assert_eq!(failure("40P01").retryable_sqlstate(), Some("40P01"));
+        assert_eq!(failure("42P01").retryable_sqlstate(), None);
+        let timeout = |timeout| SchemalaneError::MigrationTimeout {
+            script: "V2__add_cake_column.sql".to_owned(),
+            timeout,
+            statement: None,
+            attempts: 3,
+            source: DbErr::Custom("canceled".to_owned()),
+        };
+        assert_eq!(
+            timeout(MigrationTimeoutKind::LockTimeout).retryable_sqlstate(),
+            Some("55P03")
+        );
+        assert_eq!(
+            timeout(MigrationTimeoutKind::StatementTimeout).retryable_sqlstate(),
+            None
+        );
+        assert_eq!(
+            timeout(MigrationTimeoutKind::LockTimeout).to_string(),
+            "Migration V2__add_cake_column.sql exceeded its lock_timeout: Custom Error: canceled (after 3 attempts)"
+        );
+    }
+
+    #[test]
+    fn grouping_refuses_migrations_outside_a_transaction() {
+        let source = InMemoryMigrationSource::new()
+            .with_sql("V1__create_cake.sql", "CREATE TABLE cake ();")
+            .with_sql(
+                "V2__index_cake.sql",
+                "-- schemalane:no-transaction\nCREATE INDEX CONCURRENTLY cake_idx ON cake (id);",
+            )
+            .with_rust("V3__seed_cake.rs", 7)
+            .with_rust("V4__price_cake.rs", 8);
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            group_transactions: true,
+            ..SchemalaneConfig::default()
+        })
+        .with_source(source)
+        .with_rust_migration(
+            "V3__seed_cake.rs",
+            RustMigrationExecutor::transactional(|_| Box::pin(async { Ok(()) })),
+        )
+        .with_rust_migration(
+            "V4__price_cake.rs",
+            RustMigrationExecutor::new(|_| Box::pin(async { Ok(()) })),
+        );
+        let migrations = migrator.discover_migrations().expect("discovery succeeds");
+        let pending: Vec<_> = migrations.iter().collect();
+
+        let err = migrator
+            .ensure_groupable(&pending)
+            .expect_err("no-transaction migrations cannot be grouped");
+        assert_eq!(err.exit_code(), 2);
+        assert!(
+            err.to_string().contains(
+                "cannot group migrations that run outside a transaction: V2__index_cake.sql, V4__price_cake.rs"
+            ),
+            "unexpected error: {err}"
+        );
+
+        migrator
+            .ensure_groupable(&[&migrations[0], &migrations[2]])
+            .expect("transactional migrations can be grouped");
+    }
+
     #[test]
     fn rejects_invalid_sql_filename() {
         let err = parse_sql_filename("2026_02_24_price_histories.sql")
@@ -1501,11 +4585,11 @@ mod tests {
     fn parses_rust_filename() {
         let (version, parsed, description) =
             parse_rust_filename("V2026.02.24.2__seed_reference_data.rs").expect("valid filename");
-        assert_eq!(version, "2026.02.24.2");
+        assert_eq!(version.as_deref(), Some("2026.02.24.2"));
         assert_eq!(description, "seed_reference_data");
         assert_eq!(
             parsed,
-            ParsedVersion(vec![2026, 2, 24, 2]),
+            Some(ParsedVersion(vec![2026, 2, 24, 2])),
             "version segments should parse numerically"
         );
     }
@@ -1520,6 +4604,571 @@ mod tests {
         );
     }
 
+    #[test]
+    fn flyway_checksum_ignores_line_endings_and_bom() {
+        let lf = ChecksumAlgorithm::Flyway.checksum(b"CREATE TABLE cake ();\nSELECT 1;\n");
+        let crlf = ChecksumAlgorithm::Flyway.checksum(b"CREATE TABLE cake ();\r\nSELECT 1;\r\n");
+        let bom =
+            ChecksumAlgorithm::Flyway.checksum(b"\xEF\xBB\xBFCREATE TABLE cake ();\nSELECT 1;");
+        assert_eq!(lf, crlf);
+        assert_eq!(lf, bom);
+        assert_eq!(
+            lf,
+            ChecksumAlgorithm::Crc32.checksum(b"CREATE TABLE cake ();SELECT 1;"),
+            "Flyway hashes lines back to back without terminators"
+        );
+        assert_ne!(
+            ChecksumAlgorithm::Crc32.checksum(b"SELECT 1;\n"),
+            ChecksumAlgorithm::Crc32.checksum(b"SELECT 1;\r\n"),
+        );
+    }
+
+    #[test]
+    fn repair_plan_covers_failed_drifted_and_missing_rows() {
+        let migrations = vec![
+            DiscoveredMigration::rust("V1__create_cake.rs", 10).expect("V1"),
+            DiscoveredMigration::rust("V2__seed_cake.rs", 20).expect("V2"),
+            DiscoveredMigration::rust("V3__add_price.rs", 30).expect("V3"),
+        ];
+        let mut drifted = HistoryRow::from_migration(&migrations[0], 0, true, 1);
+        drifted.checksum = Some(11);
+        let history = vec![
+            drifted,
+            HistoryRow::from_migration(&migrations[1], 0, true, 2),
+            history_row(3, "V0__removed.rs", Some(5), true),
+            HistoryRow::from_migration(&migrations[2], 0, false, 4),
+        ];
+
+        let plan = plan_repair(&migrations, &history, false);
+        let ranks =
+            |rows: &[&HistoryRow]| rows.iter().map(|r| r.installed_rank).collect::<Vec<_>>();
+        assert_eq!(ranks(&plan.failed), [4]);
+        assert_eq!(plan.realign.len(), 1);
+        assert_eq!(plan.realign[0].0.installed_rank, 1);
+        assert_eq!(plan.realign[0].1.checksum, Some(10));
+        assert!(plan.missing.is_empty(), "missing rows are opt-in");
+
+        let plan = plan_repair(&migrations, &history, true);
+        assert_eq!(ranks(&plan.missing), [3]);
+    }
+
+    #[test]
+    fn delete_marker_hides_earlier_history_rows() {
+        let mut marker = history_row(2, "V0__removed.rs", Some(5), true);
+        marker.migration_type = DELETE_HISTORY_TYPE.to_owned();
+        let history = vec![history_row(1, "V0__removed.rs", Some(5), true), marker];
+
+        let report = build_status_report("public", "flyway_schema_history", &[], &history, false);
+        assert!(report.migrations.is_empty(), "deleted rows are not Missing");
+    }
+
+    #[test]
+    fn schema_creation_rows_name_the_schemas_fresh_may_drop() {
+        let script = format_schema_list(&["audit".to_owned(), "odd,\"name".to_owned()]);
+        assert_eq!(script, "\"audit\",\"odd,\"\"name\"");
+        let mut marker = history_row(1, &script, None, true);
+        marker.migration_type = SCHEMA_HISTORY_TYPE.to_owned();
+        let history = vec![marker];
+        assert_eq!(
+            created_schemas(&history).into_iter().collect::<Vec<_>>(),
+            ["audit", "odd,\"name"]
+        );
+
+        let report = build_status_report("public", "flyway_schema_history", &[], &history, false);
+        assert_eq!(report.migrations.len(), 1);
+        assert_eq!(report.migrations[0].state, MigrationState::Success);
+        assert!(
+            history_issues(&[], &history).is_empty(),
+            "not a Missing migration"
+        );
+
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            schemas: vec!["app".to_owned(), "audit".to_owned()],
+            ..SchemalaneConfig::default()
+        });
+        assert_eq!(migrator.config().schema, "app");
+    }
+
+    #[test]
+    fn status_marks_local_migrations_below_baseline() {
+        let migrations = vec![
+            DiscoveredMigration::rust("V1__create_cake.rs", 10).expect("V1"),
+            DiscoveredMigration::rust("V2__seed_cake.rs", 20).expect("V2"),
+            DiscoveredMigration::rust("V3__add_price.rs", 30).expect("V3"),
+        ];
+        let mut baseline = history_row(1, DEFAULT_BASELINE_DESCRIPTION, None, true);
+        baseline.version = Some("2".to_owned());
+        baseline.migration_type = BASELINE_HISTORY_TYPE.to_owned();
+
+        let report = build_status_report(
+            "public",
+            "flyway_schema_history",
+            &migrations,
+            &[baseline],
+            false,
+        );
+        let states: Vec<_> = report
+            .migrations
+            .iter()
+            .map(|entry| (entry.script.as_str(), entry.state))
+            .collect();
+        assert_eq!(
+            states,
+            [
+                ("V1__create_cake.rs", MigrationState::BelowBaseline),
+                (DEFAULT_BASELINE_DESCRIPTION, MigrationState::Success),
+                ("V2__seed_cake.rs", MigrationState::BelowBaseline),
+                ("V3__add_price.rs", MigrationState::Pending),
+            ]
+        );
+        assert_eq!(report.summary.below_baseline, 2);
+        assert_eq!(report.summary.missing, 0);
+    }
+
+    #[test]
+    fn repeatables_run_last_and_report_outdated_and_superseded() {
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig::default()).with_source(
+            InMemoryMigrationSource::new()
+                .with_sql(
+                    "R__price_view.sql",
+                    "CREATE OR REPLACE VIEW price AS SELECT 2;",
+                )
+                .with_sql("R__grants.sql", "GRANT SELECT ON price TO app;")
+                .with_sql("V1__create_cake.sql", "CREATE TABLE cake ();"),
+        );
+        let migrations = migrator.discover_migrations().expect("discover");
+        let scripts: Vec<_> = migrations.iter().map(|m| m.script.as_str()).collect();
+        assert_eq!(
+            scripts,
+            ["V1__create_cake.sql", "R__grants.sql", "R__price_view.sql"]
+        );
+
+        let grants = &migrations[1];
+        let view = &migrations[2];
+        let history = vec![
+            HistoryRow::from_migration(&migrations[0], 0, true, 1),
+            HistoryRow::from_migration(grants, 0, true, 2),
+            HistoryRow {
+                checksum: Some(1),
+                ..HistoryRow::from_migration(view, 0, true, 3)
+            },
+            HistoryRow {
+                checksum: Some(2),
+                ..HistoryRow::from_migration(view, 0, true, 4)
+            },
+        ];
+
+        let report = build_status_report(
+            "public",
+            "flyway_schema_history",
+            &migrations,
+            &history,
+            false,
+        );
+        let states: Vec<_> = report
+            .migrations
+            .iter()
+            .map(|entry| (entry.script.as_str(), entry.installed_rank, entry.state))
+            .collect();
+        assert_eq!(
+            states,
+            [
+                ("V1__create_cake.sql", Some(1), MigrationState::Success),
+                ("R__grants.sql", Some(2), MigrationState::Success),
+                ("R__price_view.sql", Some(3), MigrationState::Superseded),
+                ("R__price_view.sql", Some(4), MigrationState::Outdated),
+            ]
+        );
+        assert_eq!(report.summary.outdated, 1);
+        assert_eq!(report.summary.superseded, 1);
+        assert_eq!(report.summary.checksum_mismatch, 0);
+        SchemalaneMigrator::ensure_no_blocking_history(&migrations, &history)
+            .expect("a changed repeatable is not drift");
+    }
+
+    #[test]
+    fn up_plan_stops_at_target() {
+        let migrations = vec![
+            DiscoveredMigration::rust("V1__create_cake.rs", 10).expect("V1"),
+            DiscoveredMigration::rust("V2__seed_cake.rs", 20).expect("V2"),
+            DiscoveredMigration::rust("V3__add_price.rs", 30).expect("V3"),
+            DiscoveredMigration::rust("R__cake_view.rs", 40).expect("R"),
+        ];
+        let history = vec![HistoryRow::from_migration(&migrations[0], 0, true, 1)];
+        let scripts = |plan: &[&DiscoveredMigration]| -> Vec<String> {
+            plan.iter().map(|m| m.script.clone()).collect()
+        };
+
+        let plan = plan_up(
+            &migrations,
+            &history,
+            &MigrationTarget::parse("2").expect("target"),
+        );
+        assert_eq!(plan.skipped, 1);
+        assert_eq!(
+            scripts(&plan.pending),
+            ["V2__seed_cake.rs", "R__cake_view.rs"]
+        );
+        assert_eq!(scripts(&plan.unapplied), ["V3__add_price.rs"]);
+
+        let plan = plan_up(&migrations, &history, &MigrationTarget::Steps(1));
+        assert_eq!(scripts(&plan.pending), ["V2__seed_cake.rs"]);
+        assert_eq!(
+            scripts(&plan.unapplied),
+            ["V3__add_price.rs", "R__cake_view.rs"]
+        );
+
+        let plan = plan_up(&migrations, &history, &MigrationTarget::Latest);
+        assert_eq!(plan.pending.len(), 3);
+        assert!(plan.unapplied.is_empty());
+
+        ensure_target_exists(&migrations, &MigrationTarget::parse("4").expect("target"))
+            .expect_err("unknown target version should fail");
+    }
+
+    #[test]
+    fn migration_plan_lists_sql_and_opaque_rust_steps() {
+        let migrations = [
+            DiscoveredMigration::sql(
+                "V1__create_cake.sql",
+                "CREATE TABLE cake ();\n",
+                ChecksumAlgorithm::Crc32,
+            )
+            .expect("V1"),
+            DiscoveredMigration::rust("V2__seed_cake.rs", 20).expect("V2"),
+        ];
+        let pending: Vec<_> = migrations.iter().collect();
+        let plan = MigrationPlan {
+            schema: "public".to_owned(),
+            history_table: "flyway_schema_history".to_owned(),
+            dropped_objects: vec![SchemaObject {
+                kind: SchemaObjectKind::Table,
+                schema: "public".to_owned(),
+                name: "cake".to_owned(),
+                arguments: None,
+            }],
+            steps: planned_steps(&pending, 3, "deployer"),
+            skipped: 2,
+            unapplied: Vec::new(),
+        };
+
+        assert_eq!(plan.steps[0].installed_rank, 3);
+        assert_eq!(plan.steps[1].installed_rank, 4);
+        assert_eq!(plan.steps[1].sql, None);
+
+        let text = format_migration_plan(&plan);
+        assert!(text.contains("-- would drop table \"public\".\"cake\""));
+        assert!(text.contains("-- would apply 2 migration(s), skip 2"));
+        assert!(text.contains("-- [3] 1 create cake (V1__create_cake.sql) type=SQL"));
+        assert!(text.contains("CREATE TABLE cake ();"));
+        assert!(text.contains("-- [4] 2 seed cake (V2__seed_cake.rs) type=RUST checksum=20"));
+        assert!(text.contains("-- Rust migration: runs its registered executor"));
+    }
+
+    #[test]
+    fn out_of_order_migrations_are_ignored_unless_allowed() {
+        let migrations = vec![
+            DiscoveredMigration::rust("V1__create_cake.rs", 10).expect("V1"),
+            DiscoveredMigration::rust("V2__hotfix.rs", 20).expect("V2"),
+            DiscoveredMigration::rust("V3__add_price.rs", 30).expect("V3"),
+            DiscoveredMigration::rust("V4__add_stock.rs", 40).expect("V4"),
+        ];
+        let history = vec![
+            HistoryRow::from_migration(&migrations[0], 0, true, 1),
+            HistoryRow::from_migration(&migrations[2], 0, true, 2),
+        ];
+        let state_of = |report: &super::StatusReport, script: &str| {
+            report
+                .migrations
+                .iter()
+                .find(|entry| entry.script == script)
+                .map(|entry| entry.state)
+        };
+
+        let report = build_status_report(
+            "public",
+            "flyway_schema_history",
+            &migrations,
+            &history,
+            false,
+        );
+        assert_eq!(
+            state_of(&report, "V2__hotfix.rs"),
+            Some(MigrationState::Ignored)
+        );
+        assert_eq!(
+            state_of(&report, "V4__add_stock.rs"),
+            Some(MigrationState::Pending)
+        );
+        assert_eq!(report.summary.ignored, 1);
+
+        let strict = SchemalaneMigrator::new(SchemalaneConfig::default());
+        let err = strict
+            .ensure_in_order(&migrations, &history)
+            .expect_err("out-of-order migration should fail validation");
+        assert!(
+            err.to_string().contains("V2__hotfix.rs"),
+            "unexpected error: {err}"
+        );
+
+        let lenient = SchemalaneMigrator::new(SchemalaneConfig {
+            out_of_order: true,
+            ..Default::default()
+        });
+        lenient
+            .ensure_in_order(&migrations, &history)
+            .expect("out_of_order allows older pending migrations");
+        let report = build_status_report(
+            "public",
+            "flyway_schema_history",
+            &migrations,
+            &history,
+            true,
+        );
+        assert_eq!(
+            state_of(&report, "V2__hotfix.rs"),
+            Some(MigrationState::Pending)
+        );
+    }
+
+    #[test]
+    fn validation_collects_every_issue_and_maps_exit_codes() {
+        let migrations = vec![
+            DiscoveredMigration::rust("V1__create_cake.rs", 10).expect("V1"),
+            DiscoveredMigration::rust("V2__seed_cake.rs", 20).expect("V2"),
+            DiscoveredMigration::rust("V2__seed_price.rs", 21).expect("V2 dup"),
+        ];
+        let history = vec![
+            history_row(1, "V1__create_cake.rs", Some(99), true),
+            history_row(2, "V0__removed.rs", Some(5), true),
+            history_row(3, "V2__seed_cake.rs", Some(20), false),
+        ];
+
+        let mut issues = duplicate_issues(&migrations);
+        issues.extend(history_issues(&migrations, &history));
+        let kinds: Vec<_> = issues
+            .iter()
+            .map(|issue| (issue.kind, issue.script.as_deref()))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (
+                    ValidationIssueKind::DuplicateMigration,
+                    Some("V2__seed_price.rs")
+                ),
+                (ValidationIssueKind::Missing, Some("V0__removed.rs")),
+                (
+                    ValidationIssueKind::ChecksumMismatch,
+                    Some("V1__create_cake.rs")
+                ),
+                (ValidationIssueKind::Failed, Some("V2__seed_cake.rs")),
+            ]
+        );
+
+        let mut report = ValidationReport { issues };
+        let exit_code = |report: &ValidationReport| {
+            ensure_valid(report).map_or_else(|err| err.exit_code(), |()| 0)
+        };
+        assert_eq!(exit_code(&report), 2);
+        report.issues.remove(0);
+        assert_eq!(exit_code(&report), 4);
+        report.issues.pop();
+        assert_eq!(exit_code(&report), 3);
+        report.issues.clear();
+        assert_eq!(exit_code(&report), 0);
+    }
+
+    #[test]
+    fn validation_reports_each_invalid_file_and_checks_the_rest() {
+        let temp = TempDir::new().expect("temp dir");
+        fs::write(
+            temp.path().join("V1__create_cake.sql"),
+            "CREATE TABLE cake ();",
+        )
+        .expect("write V1");
+        fs::write(temp.path().join("V2_seed_cake.sql"), "SELECT 1;").expect("write V2");
+        fs::write(temp.path().join("V3__vacuum_cake.sql"), "VACUUM cake;").expect("write V3");
+        fs::write(
+            temp.path().join("V3__vacuum_cake.sql.conf"),
+            "executeInTransaction=maybe\n",
+        )
+        .expect("write V3 sidecar");
+
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            migrations_dir: temp.path().to_path_buf(),
+            ..SchemalaneConfig::default()
+        });
+        let discovery = migrator.discover_unchecked().expect("discovery");
+        assert_eq!(discovery.migrations.len(), 1);
+        let history = vec![history_row(1, "V1__create_cake.sql", Some(99), true)];
+
+        let issues = migrator.validation_issues(&discovery, &history, &HashMap::new(), &[]);
+        let kinds: Vec<_> = issues
+            .iter()
+            .map(|issue| (issue.kind, issue.script.as_deref()))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (
+                    ValidationIssueKind::InvalidMigration,
+                    Some("V2_seed_cake.sql")
+                ),
+                (
+                    ValidationIssueKind::InvalidMigration,
+                    Some("V3__vacuum_cake.sql")
+                ),
+                (
+                    ValidationIssueKind::ChecksumMismatch,
+                    Some("V1__create_cake.sql")
+                ),
+            ]
+        );
+        assert!(issues[1].message.contains("expected true or false"));
+        migrator
+            .discover_migrations()
+            .expect_err("up still stops at the first invalid file");
+    }
+
+    #[test]
+    fn most_severe_error_ranks_failures_above_findings() {
+        let most_severe = |errors: Vec<SchemalaneError>| {
+            SchemalaneError::most_severe(errors).map(|err| err.exit_code())
+        };
+        let lock_timeout = || SchemalaneError::LockTimeout {
+            lock_id: 42,
+            waited: std::time::Duration::from_secs(1),
+            holders: Vec::new(),
+        };
+        assert_eq!(
+            most_severe(vec![
+                SchemalaneError::PendingMigrations(2),
+                SchemalaneError::Drift("V1".to_owned()),
+                SchemalaneError::Validation("first".to_owned()),
+                SchemalaneError::Validation("second".to_owned()),
+            ]),
+            Some(2)
+        );
+        assert_eq!(
+            most_severe(vec![
+                SchemalaneError::Drift("V1".to_owned()),
+                SchemalaneError::FailedHistory("V2".to_owned()),
+            ]),
+            Some(4)
+        );
+        assert_eq!(
+            most_severe(vec![SchemalaneError::PendingMigrations(1), lock_timeout()]),
+            Some(7)
+        );
+        assert_eq!(
+            most_severe(vec![
+                SchemalaneError::Validation("invalid".to_owned()),
+                lock_timeout(),
+                SchemalaneError::Db(DbErr::Custom("down".to_owned())),
+            ]),
+            Some(1)
+        );
+        assert_eq!(
+            SchemalaneError::most_severe([
+                SchemalaneError::Validation("first".to_owned()),
+                SchemalaneError::Validation("second".to_owned()),
+            ])
+            .map(|err| err.to_string()),
+            Some("Validation error: first".to_owned())
+        );
+        assert_eq!(most_severe(Vec::new()), None);
+    }
+
+    #[test]
+    fn lock_timeout_names_the_holders() {
+        let err = SchemalaneError::LockTimeout {
+            lock_id: 42,
+            waited: std::time::Duration::from_millis(1500),
+            holders: vec![LockHolder {
+                pid: 4711,
+                application_name: "deploy-7f9c".to_owned(),
+                client_addr: Some("10.0.0.12".to_owned()),
+            }],
+        };
+        assert_eq!(err.exit_code(), 7);
+        assert_eq!(
+            err.to_string(),
+            "Timed out after 1500 ms waiting for advisory lock 42; held by pid 4711 (application_name 'deploy-7f9c', client_addr 10.0.0.12)"
+        );
+    }
+
+    #[test]
+    fn migration_errors_name_the_statement_and_server_details() {
+        let err = SchemalaneError::MigrationExecution {
+            script: "V3__add_price.sql".to_owned(),
+            statement: Some(Box::new(StatementSpan {
+                start_line: 37,
+                start_column: 1,
+                end_line: 39,
+                end_column: 12,
+            })),
+            database_error: Some(Box::new(DatabaseErrorDetails {
+                code: "42P01".to_owned(),
+                detail: None,
+                hint: Some("Create the table first.".to_owned()),
+            })),
+            attempts: 1,
+            source: DbErr::Custom("relation \"price\" does not exist".to_owned()),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Migration execution failed for V3__add_price.sql at line 37, column 1 to line 39, column 12: Custom Error: relation \"price\" does not exist (SQLSTATE 42P01; hint: Create the table first.)"
+        );
+    }
+
+    #[test]
+    fn derived_lock_ids_match_flyway() {
+        // Flyway's key for "public"."flyway_schema_history".
+        let public = derive_advisory_lock_id("public", "flyway_schema_history");
+        assert_eq!(public, 77_433_833_903_597);
+        assert_eq!(
+            derive_advisory_lock_id("tenant_é", "history"),
+            77_432_396_071_015,
+            "hashes UTF-16 code units"
+        );
+        assert_ne!(
+            public,
+            derive_advisory_lock_id("billing", "flyway_schema_history")
+        );
+        assert_ne!(public, derive_advisory_lock_id("public", "history"));
+
+        assert_eq!(
+            AdvisoryLock::parse("derived").ok(),
+            Some(AdvisoryLock::Derived)
+        );
+        assert_eq!(
+            AdvisoryLock::parse("-5").ok(),
+            Some(AdvisoryLock::Fixed(-5))
+        );
+        AdvisoryLock::parse("shared").expect_err("lock id must be numeric or derived");
+    }
+
+    fn history_row(
+        installed_rank: i32,
+        script: &str,
+        checksum: Option<i32>,
+        success: bool,
+    ) -> HistoryRow {
+        HistoryRow {
+            installed_rank,
+            version: None,
+            description: script.to_owned(),
+            migration_type: "RUST".to_owned(),
+            script: script.to_owned(),
+            checksum,
+            installed_on: String::new(),
+            execution_time: 0,
+            success,
+            transaction_mode: None,
+        }
+    }
+
     #[test]
     fn compares_versions_numerically() {
         let v1 = ParsedVersion::parse("2.10").expect("parse");
@@ -1527,6 +5176,51 @@ mod tests {
         assert!(v1 > v2);
     }
 
+    #[test]
+    fn discovers_embedded_migrations_without_reading_disk() {
+        const EMBEDDED: &[EmbeddedMigration] = &[
+            EmbeddedMigration::rust("V2__seed_cake.rs", 7, 8),
+            EmbeddedMigration::sql("V1__create_cake.sql", "CREATE TABLE cake ();", 42, 43),
+        ];
+
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            migrations_dir: PathBuf::from("/nonexistent/schemalane/migrations"),
+            ..Default::default()
+        })
+        .with_embedded_migrations(EMBEDDED);
+
+        let migrations = migrator
+            .discover_migrations()
+            .expect("embedded discovery should not touch the filesystem");
+        let scripts: Vec<_> = migrations.iter().map(|m| m.script.as_str()).collect();
+        assert_eq!(scripts, ["V1__create_cake.sql", "V2__seed_cake.rs"]);
+        assert_eq!(migrations[0].checksum, Some(42));
+        assert_eq!(migrations[0].migration_type, MigrationType::Sql);
+        assert_eq!(migrations[1].checksum, Some(7));
+        assert_eq!(migrations[1].migration_type, MigrationType::Rust);
+
+        let flyway = SchemalaneMigrator::new(SchemalaneConfig {
+            checksum_algorithm: ChecksumAlgorithm::Flyway,
+            ..Default::default()
+        })
+        .with_embedded_migrations(EMBEDDED)
+        .discover_migrations()
+        .expect("embedded discovery");
+        assert_eq!(flyway[0].checksum, Some(43));
+        assert_eq!(flyway[1].checksum, Some(8));
+
+        let err = migrator
+            .with_source(FilesystemMigrationSource::new(
+                "/nonexistent/schemalane/migrations",
+            ))
+            .discover_migrations()
+            .expect_err("filesystem discovery should see the missing directory");
+        assert!(
+            err.to_string().contains("migrations directory not found"),
+            "unexpected error: {err}"
+        );
+    }
+
     #[test]
     fn init_scaffold_creates_expected_files() {
         let temp = TempDir::new().expect("temp dir");
@@ -0,0 +1,268 @@
+use crate::quote_ident;
+use serde::Serialize;
+
+/// Kinds of objects `fresh` drops from the managed schemas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaObjectKind {
+    /// A whole schema that Schemalane created.
+    Schema,
+    Extension,
+    MaterializedView,
+    View,
+    Table,
+    ForeignTable,
+    Sequence,
+    Aggregate,
+    Function,
+    Procedure,
+    Domain,
+    Type,
+}
+
+impl SchemaObjectKind {
+    pub const ALL: [Self; 12] = [
+        Self::Schema,
+        Self::Extension,
+        Self::MaterializedView,
+        Self::View,
+        Self::Table,
+        Self::ForeignTable,
+        Self::Sequence,
+        Self::Aggregate,
+        Self::Function,
+        Self::Procedure,
+        Self::Domain,
+        Self::Type,
+    ];
+
+    /// The name used in `DROP` statements, lowercased.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Schema => "schema",
+            Self::Extension => "extension",
+            Self::MaterializedView => "materialized view",
+            Self::View => "view",
+            Self::Table => "table",
+            Self::ForeignTable => "foreign table",
+            Self::Sequence => "sequence",
+            Self::Aggregate => "aggregate",
+            Self::Function => "function",
+            Self::Procedure => "procedure",
+            Self::Domain => "domain",
+            Self::Type => "type",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+}
+
+impl std::fmt::Display for SchemaObjectKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An object `fresh` drops, or would drop in a dry run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SchemaObject {
+    pub kind: SchemaObjectKind,
+    /// The schema the object lives in; for `Schema`, its own name.
+    pub schema: String,
+    pub name: String,
+    /// Argument types of a function, procedure or aggregate, e.g.
+    /// `integer, text`.
+    pub arguments: Option<String>,
+}
+
+impl SchemaObject {
+    pub(crate) fn from_catalog(
+        schema: &str,
+        kind: &str,
+        name: String,
+        arguments: Option<String>,
+    ) -> Option<Self> {
+        Some(Self {
+            kind: SchemaObjectKind::parse(kind)?,
+            schema: schema.to_owned(),
+            name,
+            arguments,
+        })
+    }
+
+    pub(crate) fn whole_schema(schema: &str) -> Self {
+        Self {
+            kind: SchemaObjectKind::Schema,
+            schema: schema.to_owned(),
+            name: schema.to_owned(),
+            arguments: None,
+        }
+    }
+
+    /// Schemas and extensions have no schema of their own to qualify with.
+    const fn is_unqualified(&self) -> bool {
+        matches!(
+            self.kind,
+            SchemaObjectKind::Schema | SchemaObjectKind::Extension
+        )
+    }
+
+    /// The name as written in SQL: schema-qualified except for schemas and
+    /// extensions, with the argument list of routines.
+    pub fn qualified_name(&self) -> String {
+        let name = if self.is_unqualified() {
+            quote_ident(&self.name)
+        } else {
+            format!("{}.{}", quote_ident(&self.schema), quote_ident(&self.name))
+        };
+        match &self.arguments {
+            Some(arguments) => format!("{name}({arguments})"),
+            None => name,
+        }
+    }
+
+    /// `IF EXISTS` because dropping an earlier object may already have
+    /// cascaded to this one.
+    pub(crate) fn drop_sql(&self) -> String {
+        format!(
+            "DROP {} IF EXISTS {} CASCADE",
+            self.kind.as_str().to_uppercase(),
+            self.qualified_name()
+        )
+    }
+}
+
+impl std::fmt::Display for SchemaObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_unqualified() {
+            write!(f, "{} {}", self.kind, self.name)?;
+        } else {
+            write!(f, "{} {}.{}", self.kind, self.schema, self.name)?;
+        }
+        self.arguments
+            .as_ref()
+            .map_or(Ok(()), |arguments| write!(f, "({arguments})"))
+    }
+}
+
+/// Objects in schema `$1`, in the order `fresh` drops them: extensions first,
+/// since their member objects cannot be dropped on their own, then views
+/// before the tables they read, tables before the sequences, routines and
+/// types they use. Members of extensions, partitions and sequences owned by
+/// a column go with their parent and are not listed.
+pub const SCHEMA_OBJECTS_SQL: &str = r"
+WITH ns AS (
+    SELECT oid FROM pg_catalog.pg_namespace WHERE nspname = $1
+)
+SELECT kind, name, arguments FROM (
+    SELECT 0 AS phase, 'extension' AS kind, e.extname::text AS name, NULL::text AS arguments
+    FROM pg_catalog.pg_extension e
+    WHERE e.extnamespace IN (SELECT oid FROM ns)
+    UNION ALL
+    SELECT
+        CASE c.relkind WHEN 'm' THEN 1 WHEN 'v' THEN 2 WHEN 'S' THEN 4 ELSE 3 END,
+        CASE c.relkind
+            WHEN 'm' THEN 'materialized view'
+            WHEN 'v' THEN 'view'
+            WHEN 'f' THEN 'foreign table'
+            WHEN 'S' THEN 'sequence'
+            ELSE 'table'
+        END,
+        c.relname::text,
+        NULL
+    FROM pg_catalog.pg_class c
+    WHERE c.relnamespace IN (SELECT oid FROM ns)
+      AND c.relkind IN ('r', 'p', 'v', 'm', 'f', 'S')
+      AND NOT c.relispartition
+      AND NOT EXISTS (
+          SELECT 1 FROM pg_catalog.pg_depend d
+          WHERE d.classid = 'pg_catalog.pg_class'::regclass
+            AND d.objid = c.oid
+            AND (d.deptype = 'e' OR (c.relkind = 'S' AND d.deptype IN ('a', 'i')))
+      )
+    UNION ALL
+    SELECT
+        CASE p.prokind WHEN 'a' THEN 5 ELSE 6 END,
+        CASE p.prokind WHEN 'a' THEN 'aggregate' WHEN 'p' THEN 'procedure' ELSE 'function' END,
+        p.proname::text,
+        pg_catalog.pg_get_function_identity_arguments(p.oid)
+    FROM pg_catalog.pg_proc p
+    WHERE p.pronamespace IN (SELECT oid FROM ns)
+      AND NOT EXISTS (
+          SELECT 1 FROM pg_catalog.pg_depend d
+          WHERE d.classid = 'pg_catalog.pg_proc'::regclass
+            AND d.objid = p.oid
+            AND d.deptype = 'e'
+      )
+    UNION ALL
+    SELECT
+        CASE t.typtype WHEN 'd' THEN 7 ELSE 8 END,
+        CASE t.typtype WHEN 'd' THEN 'domain' ELSE 'type' END,
+        t.typname::text,
+        NULL
+    FROM pg_catalog.pg_type t
+    LEFT JOIN pg_catalog.pg_class c ON c.oid = t.typrelid
+    WHERE t.typnamespace IN (SELECT oid FROM ns)
+      AND (t.typtype IN ('e', 'r', 'd') OR (t.typtype = 'c' AND c.relkind = 'c'))
+      AND NOT EXISTS (
+          SELECT 1 FROM pg_catalog.pg_depend d
+          WHERE d.classid = 'pg_catalog.pg_type'::regclass
+            AND d.objid = t.oid
+            AND d.deptype = 'e'
+      )
+) objects
+ORDER BY phase, name, arguments
+";
+
+#[cfg(test)]
+mod tests {
+    use super::{SchemaObject, SchemaObjectKind};
+
+    #[test]
+    fn drop_statements_quote_names_and_keep_routine_arguments() {
+        let function = SchemaObject::from_catalog(
+            "app",
+            "function",
+            "add".to_owned(),
+            Some("integer, integer".to_owned()),
+        )
+        .expect("known kind");
+        assert_eq!(function.to_string(), "function app.add(integer, integer)");
+        assert_eq!(
+            function.drop_sql(),
+            "DROP FUNCTION IF EXISTS \"app\".\"add\"(integer, integer) CASCADE"
+        );
+
+        let view = SchemaObject::from_catalog(
+            "public",
+            "materialized view",
+            "cake \"stats\"".to_owned(),
+            None,
+        )
+        .expect("known kind");
+        assert_eq!(
+            view.drop_sql(),
+            "DROP MATERIALIZED VIEW IF EXISTS \"public\".\"cake \"\"stats\"\"\" CASCADE"
+        );
+
+        let extension =
+            SchemaObject::from_catalog("public", "extension", "citext".to_owned(), None)
+                .expect("known kind");
+        assert_eq!(extension.kind, SchemaObjectKind::Extension);
+        assert_eq!(
+            extension.drop_sql(),
+            "DROP EXTENSION IF EXISTS \"citext\" CASCADE"
+        );
+        assert_eq!(
+            SchemaObject::whole_schema("audit").drop_sql(),
+            "DROP SCHEMA IF EXISTS \"audit\" CASCADE"
+        );
+
+        assert_eq!(
+            SchemaObject::from_catalog("public", "index", "cake_idx".to_owned(), None),
+            None
+        );
+    }
+}
@@ -0,0 +1,571 @@
+use crate::{CallbackEvent, ChecksumAlgorithm, DiscoveredMigration, SchemalaneError, SqlCallback};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+/// Produces the set of local migrations the migrator compares against the
+/// history table.
+///
+/// Sources only report what they find; ordering, duplicate detection and Rust
+/// executor checks are done by `SchemalaneMigrator`. Checksums must be
+/// computed with the algorithm the migrator passes in.
+///
+/// A file that is not a valid migration, e.g. because of its name, ends up in
+/// `Discovery::errors` and does not stop the others from being read; an
+/// `Err` is for failures of the source itself.
+pub trait MigrationSource: Send + Sync {
+    fn discover(&self, checksum_algorithm: ChecksumAlgorithm)
+    -> Result<Discovery, SchemalaneError>;
+
+    /// SQL callback scripts (`beforeMigrate.sql`, ...) kept alongside the
+    /// migrations. Sources without callbacks can rely on the default.
+    fn discover_callbacks(&self) -> Result<Vec<SqlCallback>, SchemalaneError> {
+        Ok(Vec::new())
+    }
+}
+
+/// What a `MigrationSource` found: every migration it could read, and one
+/// error for each file it could not.
+#[derive(Debug, Clone, Default)]
+pub struct Discovery {
+    pub migrations: Vec<DiscoveredMigration>,
+    pub errors: Vec<DiscoveryError>,
+}
+
+/// A file a `MigrationSource` could not read as a migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveryError {
+    pub script: String,
+    pub message: String,
+}
+
+impl Discovery {
+    /// Record the outcome of reading `script`. Validation errors are kept
+    /// in `errors`; any other error is returned.
+    pub fn push(
+        &mut self,
+        script: &str,
+        result: Result<DiscoveredMigration, SchemalaneError>,
+    ) -> Result<(), SchemalaneError> {
+        match result {
+            Ok(migration) => self.migrations.push(migration),
+            Err(SchemalaneError::Validation(message)) => self.errors.push(DiscoveryError {
+                script: script.to_owned(),
+                message,
+            }),
+            Err(err) => return Err(err),
+        }
+        Ok(())
+    }
+
+    pub fn extend(&mut self, other: Self) {
+        self.migrations.extend(other.migrations);
+        self.errors.extend(other.errors);
+    }
+
+    /// The migrations, or the first error if any file was invalid.
+    pub fn into_migrations(self) -> Result<Vec<DiscoveredMigration>, SchemalaneError> {
+        match self.errors.into_iter().next() {
+            Some(error) => Err(SchemalaneError::Validation(error.message)),
+            None => Ok(self.migrations),
+        }
+    }
+}
+
+/// Reads `V<version>__<description>.{sql,rs}` and `R__<description>.{sql,rs}`
+/// files from a single directory, applying any `<script>.conf` sidecar next to
+/// a SQL file.
+///
+/// SQL files named after a `CallbackEvent` are callbacks, not migrations.
+#[derive(Debug, Clone)]
+pub struct FilesystemMigrationSource {
+    dir: PathBuf,
+}
+
+impl FilesystemMigrationSource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn ensure_exists(&self) -> Result<(), SchemalaneError> {
+        if self.dir.exists() {
+            return Ok(());
+        }
+        Err(SchemalaneError::Validation(format!(
+            "migrations directory not found: {}",
+            self.dir.display()
+        )))
+    }
+}
+
+impl MigrationSource for FilesystemMigrationSource {
+    fn discover(
+        &self,
+        checksum_algorithm: ChecksumAlgorithm,
+    ) -> Result<Discovery, SchemalaneError> {
+        self.ensure_exists()?;
+
+        let mut discovery = Discovery::default();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+
+            let extension = path.extension().and_then(|ext| ext.to_str());
+            if !matches!(extension, Some("sql" | "rs")) {
+                continue;
+            }
+
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                discovery.push(
+                    &path.to_string_lossy(),
+                    Err(SchemalaneError::Validation(
+                        "non-utf8 migration filename".to_owned(),
+                    )),
+                )?;
+                continue;
+            };
+            if CallbackEvent::from_script(file_name).is_some() {
+                continue;
+            }
+
+            let migration = if extension == Some("sql") {
+                DiscoveredMigration::sql(
+                    file_name,
+                    std::fs::read_to_string(&path)?,
+                    checksum_algorithm,
+                )
+                .and_then(|migration| {
+                    let conf_path = self.dir.join(format!("{file_name}.conf"));
+                    if conf_path.is_file() {
+                        migration.with_script_config(&std::fs::read_to_string(conf_path)?)
+                    } else {
+                        Ok(migration)
+                    }
+                })
+            } else {
+                let checksum = checksum_algorithm.checksum(&std::fs::read(&path)?);
+                DiscoveredMigration::rust(file_name, checksum)
+            };
+            discovery.push(file_name, migration)?;
+        }
+
+        discovery.errors.sort_by(|a, b| a.script.cmp(&b.script));
+        Ok(discovery)
+    }
+
+    fn discover_callbacks(&self) -> Result<Vec<SqlCallback>, SchemalaneError> {
+        self.ensure_exists()?;
+
+        let mut callbacks = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !path.is_file() {
+                continue;
+            }
+            if let Some(callback) = SqlCallback::new(file_name, std::fs::read_to_string(&path)?) {
+                callbacks.push(callback);
+            }
+        }
+
+        Ok(callbacks)
+    }
+}
+
+/// A migration compiled into the binary by `embed_migrations!`.
+///
+/// SQL entries carry the full script text; Rust entries only carry their
+/// checksums since the code itself is registered as a `RustMigrationExecutor`.
+/// Checksums are precomputed for every `ChecksumAlgorithm`. SQL callback
+/// scripts are embedded the same way, without checksums.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddedMigration {
+    script: &'static str,
+    sql: Option<&'static str>,
+    config: Option<&'static str>,
+    callback: bool,
+    crc32_checksum: i32,
+    flyway_checksum: i32,
+}
+
+impl EmbeddedMigration {
+    pub const fn sql(
+        script: &'static str,
+        sql: &'static str,
+        crc32_checksum: i32,
+        flyway_checksum: i32,
+    ) -> Self {
+        Self {
+            script,
+            sql: Some(sql),
+            config: None,
+            callback: false,
+            crc32_checksum,
+            flyway_checksum,
+        }
+    }
+
+    pub const fn rust(script: &'static str, crc32_checksum: i32, flyway_checksum: i32) -> Self {
+        Self {
+            script,
+            sql: None,
+            config: None,
+            callback: false,
+            crc32_checksum,
+            flyway_checksum,
+        }
+    }
+
+    /// A SQL callback script such as `afterMigrate.sql`.
+    pub const fn callback(script: &'static str, sql: &'static str) -> Self {
+        Self {
+            script,
+            sql: Some(sql),
+            config: None,
+            callback: true,
+            crc32_checksum: 0,
+            flyway_checksum: 0,
+        }
+    }
+
+    /// Attach the text of the script's `<script>.conf` sidecar.
+    #[must_use]
+    pub const fn with_config(mut self, config: &'static str) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    pub const fn script(&self) -> &'static str {
+        self.script
+    }
+
+    pub const fn checksum(&self, checksum_algorithm: ChecksumAlgorithm) -> i32 {
+        match checksum_algorithm {
+            ChecksumAlgorithm::Crc32 => self.crc32_checksum,
+            ChecksumAlgorithm::Flyway => self.flyway_checksum,
+        }
+    }
+}
+
+/// Serves migrations held in memory, either compiled in via
+/// `embed_migrations!` or added by hand (handy for tests).
+///
+/// Never touches the filesystem.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryMigrationSource {
+    entries: Vec<InMemoryEntry>,
+}
+
+#[derive(Debug, Clone)]
+enum InMemoryEntry {
+    Embedded(EmbeddedMigration),
+    Sql {
+        script: Cow<'static, str>,
+        sql: Cow<'static, str>,
+    },
+    Rust {
+        script: Cow<'static, str>,
+        checksum: i32,
+    },
+    Callback(SqlCallback),
+}
+
+impl InMemoryMigrationSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_embedded(migrations: &'static [EmbeddedMigration]) -> Self {
+        Self {
+            entries: migrations
+                .iter()
+                .copied()
+                .map(InMemoryEntry::Embedded)
+                .collect(),
+        }
+    }
+
+    /// Add a SQL migration; its checksum is computed from `sql`.
+    #[must_use]
+    pub fn with_sql<S, T>(mut self, script: S, sql: T) -> Self
+    where
+        S: Into<Cow<'static, str>>,
+        T: Into<Cow<'static, str>>,
+    {
+        self.entries.push(InMemoryEntry::Sql {
+            script: script.into(),
+            sql: sql.into(),
+        });
+        self
+    }
+
+    /// Add a Rust migration; register its executor on the migrator by `script`.
+    #[must_use]
+    pub fn with_rust<S>(mut self, script: S, checksum: i32) -> Self
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        self.entries.push(InMemoryEntry::Rust {
+            script: script.into(),
+            checksum,
+        });
+        self
+    }
+
+    /// Add a SQL callback script named after its `CallbackEvent`, e.g.
+    /// `afterMigrate__refresh_views.sql`.
+    pub fn with_callback_sql<S, T>(mut self, script: S, sql: T) -> Result<Self, SchemalaneError>
+    where
+        S: Into<String>,
+        T: Into<Cow<'static, str>>,
+    {
+        let script = script.into();
+        let callback = SqlCallback::new(script.as_str(), sql).ok_or_else(|| {
+            SchemalaneError::Validation(format!(
+                "invalid callback script name '{script}': expected <event>.sql or <event>__<description>.sql"
+            ))
+        })?;
+        self.entries.push(InMemoryEntry::Callback(callback));
+        Ok(self)
+    }
+}
+
+impl MigrationSource for InMemoryMigrationSource {
+    fn discover(
+        &self,
+        checksum_algorithm: ChecksumAlgorithm,
+    ) -> Result<Discovery, SchemalaneError> {
+        let mut discovery = Discovery::default();
+        for entry in &self.entries {
+            match entry {
+                InMemoryEntry::Embedded(embedded) if !embedded.callback => discovery.push(
+                    embedded.script,
+                    discover_embedded(embedded, checksum_algorithm),
+                )?,
+                InMemoryEntry::Sql { script, sql } => discovery.push(
+                    script,
+                    DiscoveredMigration::sql(script, sql.clone(), checksum_algorithm),
+                )?,
+                InMemoryEntry::Rust { script, checksum } => {
+                    discovery.push(script, DiscoveredMigration::rust(script, *checksum))?;
+                }
+                InMemoryEntry::Embedded(_) | InMemoryEntry::Callback(_) => {}
+            }
+        }
+        Ok(discovery)
+    }
+
+    fn discover_callbacks(&self) -> Result<Vec<SqlCallback>, SchemalaneError> {
+        Ok(self
+            .entries
+            .iter()
+            .filter_map(|entry| match entry {
+                InMemoryEntry::Embedded(EmbeddedMigration {
+                    script,
+                    sql: Some(sql),
+                    callback: true,
+                    ..
+                }) => SqlCallback::new(*script, *sql),
+                InMemoryEntry::Callback(callback) => Some(callback.clone()),
+                _ => None,
+            })
+            .collect())
+    }
+}
+
+fn discover_embedded(
+    embedded: &EmbeddedMigration,
+    checksum_algorithm: ChecksumAlgorithm,
+) -> Result<DiscoveredMigration, SchemalaneError> {
+    let checksum = embedded.checksum(checksum_algorithm);
+    let migration = embedded.sql.map_or_else(
+        || DiscoveredMigration::rust(embedded.script, checksum),
+        |sql| DiscoveredMigration::sql_with_checksum(embedded.script, sql, checksum),
+    )?;
+    match embedded.config {
+        Some(config) => migration.with_script_config(config),
+        None => Ok(migration),
+    }
+}
+
+/// Merges several sources into one migration stream, e.g. a shared
+/// directory plus a service-specific one.
+#[derive(Default)]
+pub struct CompositeMigrationSource {
+    sources: Vec<Box<dyn MigrationSource>>,
+}
+
+impl CompositeMigrationSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_dirs<I, P>(dirs: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        dirs.into_iter().fold(Self::new(), |composite, dir| {
+            composite.with_source(FilesystemMigrationSource::new(dir))
+        })
+    }
+
+    #[must_use]
+    pub fn with_source<M>(mut self, source: M) -> Self
+    where
+        M: MigrationSource + 'static,
+    {
+        self.sources.push(Box::new(source));
+        self
+    }
+}
+
+impl MigrationSource for CompositeMigrationSource {
+    fn discover(
+        &self,
+        checksum_algorithm: ChecksumAlgorithm,
+    ) -> Result<Discovery, SchemalaneError> {
+        let mut discovery = Discovery::default();
+        for source in &self.sources {
+            discovery.extend(source.discover(checksum_algorithm)?);
+        }
+        Ok(discovery)
+    }
+
+    fn discover_callbacks(&self) -> Result<Vec<SqlCallback>, SchemalaneError> {
+        let mut callbacks = Vec::new();
+        for source in &self.sources {
+            callbacks.extend(source.discover_callbacks()?);
+        }
+        Ok(callbacks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CompositeMigrationSource, FilesystemMigrationSource, InMemoryMigrationSource,
+        MigrationSource,
+    };
+    use crate::{
+        CallbackEvent, ChecksumAlgorithm, MigrationBody, SchemalaneConfig, SchemalaneMigrator,
+    };
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn in_memory_source_computes_sql_checksums() {
+        let source = InMemoryMigrationSource::new()
+            .with_sql("V1__create_cake.sql", "CREATE TABLE cake ();")
+            .with_rust("V2__seed_cake.rs", 99);
+
+        let migrations = source
+            .discover(ChecksumAlgorithm::Crc32)
+            .expect("in-memory discovery")
+            .migrations;
+        assert_eq!(migrations.len(), 2);
+        assert_eq!(
+            migrations[0].checksum,
+            Some(ChecksumAlgorithm::Crc32.checksum(b"CREATE TABLE cake ();"))
+        );
+        assert!(
+            matches!(migrations[0].body, MigrationBody::Sql(ref sql) if sql == "CREATE TABLE cake ();")
+        );
+        assert_eq!(migrations[1].checksum, Some(99));
+        assert!(matches!(migrations[1].body, MigrationBody::Rust));
+    }
+
+    #[test]
+    fn composite_source_merges_directories() {
+        let temp = TempDir::new().expect("temp dir");
+        let shared = temp.path().join("shared");
+        let service = temp.path().join("service");
+        fs::create_dir_all(&shared).expect("create shared dir");
+        fs::create_dir_all(&service).expect("create service dir");
+        fs::write(shared.join("V1__shared_tables.sql"), "SELECT 1;").expect("write V1");
+        fs::write(service.join("V2__service_tables.sql"), "SELECT 2;").expect("write V2");
+
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig::default())
+            .with_source(CompositeMigrationSource::from_dirs([&shared, &service]));
+        let migrations = migrator.discover_migrations().expect("composite discovery");
+        let scripts: Vec<_> = migrations.iter().map(|m| m.script.as_str()).collect();
+        assert_eq!(scripts, ["V1__shared_tables.sql", "V2__service_tables.sql"]);
+
+        fs::write(service.join("V1__service_override.sql"), "SELECT 3;").expect("write dup");
+        let err = migrator
+            .discover_migrations()
+            .expect_err("duplicate versions across directories should fail");
+        assert!(
+            err.to_string().contains("duplicate migration version '1'"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn callback_scripts_are_kept_apart_from_migrations() {
+        let temp = TempDir::new().expect("temp dir");
+        fs::write(temp.path().join("V1__create_cake.sql"), "SELECT 1;").expect("write V1");
+        fs::write(
+            temp.path().join("beforeMigrate.sql"),
+            "SET lock_timeout = '5s';",
+        )
+        .expect("write beforeMigrate");
+        fs::write(
+            temp.path().join("afterMigrate__refresh_views.sql"),
+            "REFRESH MATERIALIZED VIEW cake_stats;",
+        )
+        .expect("write afterMigrate");
+
+        let source = FilesystemMigrationSource::new(temp.path());
+        let migrations = source
+            .discover(ChecksumAlgorithm::Crc32)
+            .expect("callbacks are not migrations")
+            .migrations;
+        assert_eq!(migrations.len(), 1);
+        let mut callbacks: Vec<_> = source
+            .discover_callbacks()
+            .expect("callback discovery")
+            .into_iter()
+            .map(|callback| (callback.event, callback.script))
+            .collect();
+        callbacks.sort_unstable_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(
+            callbacks,
+            [
+                (
+                    CallbackEvent::AfterMigrate,
+                    "afterMigrate__refresh_views.sql".to_owned()
+                ),
+                (CallbackEvent::BeforeMigrate, "beforeMigrate.sql".to_owned()),
+            ]
+        );
+
+        let in_memory = InMemoryMigrationSource::new()
+            .with_sql("V1__create_cake.sql", "SELECT 1;")
+            .with_callback_sql("afterEachMigrate.sql", "SELECT 2;")
+            .expect("valid callback name");
+        assert_eq!(
+            in_memory
+                .discover(ChecksumAlgorithm::Crc32)
+                .expect("in-memory discovery")
+                .migrations
+                .len(),
+            1
+        );
+        assert_eq!(
+            in_memory.discover_callbacks().expect("callbacks")[0].event,
+            CallbackEvent::AfterEachMigrate
+        );
+        InMemoryMigrationSource::new()
+            .with_callback_sql("afterEverything.sql", "SELECT 3;")
+            .expect_err("not a callback event");
+    }
+}
@@ -0,0 +1,547 @@
+use regex::Regex;
+use serde::Serialize;
+use std::sync::OnceLock;
+
+/// Where a statement sits in its script; lines and columns are 1-based and
+/// counted in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct StatementSpan {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl std::fmt::Display for StatementSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.start_line == self.end_line {
+            write!(
+                f,
+                "line {}, columns {}-{}",
+                self.start_line, self.start_column, self.end_column
+            )
+        } else {
+            write!(
+                f,
+                "line {}, column {} to line {}, column {}",
+                self.start_line, self.start_column, self.end_line, self.end_column
+            )
+        }
+    }
+}
+
+/// One statement of a SQL script, without its terminating `;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqlStatement {
+    pub sql: String,
+    pub span: StatementSpan,
+    /// Rows following a `COPY ... FROM stdin` statement, up to (not
+    /// including) the `\.` terminator line.
+    pub copy_data: Option<String>,
+}
+
+/// Splits a Postgres script on top-level `;`.
+///
+/// Semicolons inside `--` and nested `/* */` comments, quoted identifiers,
+/// standard and `E''` strings and dollar-quoted bodies do not split. The data
+/// block after `COPY ... FROM stdin;` is attached to that statement, and the
+/// `BEGIN ATOMIC ... END` body of a SQL-standard function stays in its
+/// `CREATE` statement. Comment-only
+/// segments are dropped. Unterminated quotes or comments run to the end of the
+/// script, leaving Postgres to report the syntax error.
+pub fn split_statements(script: &str) -> Vec<SqlStatement> {
+    Splitter::new(script).split()
+}
+
+struct Splitter {
+    chars: Vec<char>,
+    line_starts: Vec<usize>,
+}
+
+impl Splitter {
+    fn new(script: &str) -> Self {
+        let chars: Vec<char> = script.chars().collect();
+        let line_starts = std::iter::once(0)
+            .chain(
+                chars
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, ch)| **ch == '\n')
+                    .map(|(idx, _)| idx + 1),
+            )
+            .collect();
+        Self { chars, line_starts }
+    }
+
+    fn split(&self) -> Vec<SqlStatement> {
+        let mut statements = Vec::new();
+        let mut start = None;
+        let mut last = 0;
+        let mut idx = 0;
+        let mut atomic = AtomicBody::default();
+
+        while idx < self.chars.len() {
+            let ch = self.chars[idx];
+            match ch {
+                '-' if self.peek(idx + 1) == Some('-') => {
+                    idx = self.line_end(idx);
+                    continue;
+                }
+                '/' if self.peek(idx + 1) == Some('*') => {
+                    idx = self.block_comment_end(idx);
+                    continue;
+                }
+                ';' if !atomic.is_open() => {
+                    if let Some(first) = start.take() {
+                        let mut statement = self.statement(first, last);
+                        if is_copy_from_stdin(&statement.sql) {
+                            idx = self.attach_copy_data(&mut statement, idx);
+                        } else {
+                            idx += 1;
+                        }
+                        statements.push(statement);
+                    } else {
+                        idx += 1;
+                    }
+                    continue;
+                }
+                _ if ch.is_whitespace() => {
+                    idx += 1;
+                    continue;
+                }
+                _ => {}
+            }
+
+            start.get_or_insert(idx);
+            let end = match ch {
+                '\'' => self.quoted_end(idx, '\'', self.is_escape_string(idx)),
+                '"' => self.quoted_end(idx, '"', false),
+                '$' => self.dollar_quoted_end(idx).unwrap_or(idx + 1),
+                _ if is_word_start(ch) => self.word_end(idx),
+                _ => idx + 1,
+            };
+            if is_word_start(ch) {
+                atomic.word(&self.chars[idx..end]);
+            } else {
+                atomic.other();
+            }
+            last = end - 1;
+            idx = end;
+        }
+
+        if let Some(first) = start {
+            statements.push(self.statement(first, last));
+        }
+        statements
+    }
+
+    fn statement(&self, first: usize, last: usize) -> SqlStatement {
+        let (start_line, start_column) = self.position(first);
+        let (end_line, end_column) = self.position(last);
+        SqlStatement {
+            sql: self.chars[first..=last].iter().collect(),
+            span: StatementSpan {
+                start_line,
+                start_column,
+                end_line,
+                end_column,
+            },
+            copy_data: None,
+        }
+    }
+
+    /// Takes the lines after the `;` at `semicolon` up to a `\.` line as the
+    /// statement's COPY data; returns where scanning resumes.
+    fn attach_copy_data(&self, statement: &mut SqlStatement, semicolon: usize) -> usize {
+        let data_start = (self.line_end(semicolon) + 1).min(self.chars.len());
+        let mut line_start = data_start;
+
+        while line_start < self.chars.len() {
+            let line_end = self.line_end(line_start);
+            let line: String = self.chars[line_start..line_end].iter().collect();
+            if line.trim_end_matches('\r') == "\\." {
+                statement.copy_data = Some(self.chars[data_start..line_start].iter().collect());
+                (statement.span.end_line, statement.span.end_column) =
+                    self.position(line_start + 1);
+                return line_end;
+            }
+            line_start = line_end + 1;
+        }
+
+        statement.copy_data = Some(self.chars[data_start..].iter().collect());
+        if let Some(last) = self.chars.len().checked_sub(1)
+            && data_start < self.chars.len()
+        {
+            (statement.span.end_line, statement.span.end_column) = self.position(last);
+        }
+        self.chars.len()
+    }
+
+    fn peek(&self, idx: usize) -> Option<char> {
+        self.chars.get(idx).copied()
+    }
+
+    fn position(&self, idx: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= idx);
+        (line, idx - self.line_starts[line - 1] + 1)
+    }
+
+    /// Index of the `\n` ending the line that contains `idx`, or the script length.
+    fn line_end(&self, idx: usize) -> usize {
+        self.chars[idx..]
+            .iter()
+            .position(|&ch| ch == '\n')
+            .map_or(self.chars.len(), |offset| idx + offset)
+    }
+
+    fn word_end(&self, idx: usize) -> usize {
+        self.chars[idx..]
+            .iter()
+            .position(|&ch| !is_identifier_char(ch))
+            .map_or(self.chars.len(), |offset| idx + offset)
+    }
+
+    /// `/* */` comments nest in Postgres.
+    fn block_comment_end(&self, idx: usize) -> usize {
+        let mut depth = 0usize;
+        let mut idx = idx;
+        while idx < self.chars.len() {
+            match (self.chars[idx], self.peek(idx + 1)) {
+                ('/', Some('*')) => {
+                    depth += 1;
+                    idx += 2;
+                }
+                ('*', Some('/')) => {
+                    depth -= 1;
+                    idx += 2;
+                    if depth == 0 {
+                        return idx;
+                    }
+                }
+                _ => idx += 1,
+            }
+        }
+        self.chars.len()
+    }
+
+    /// A `'` preceded by a standalone `E` starts a string with backslash escapes.
+    fn is_escape_string(&self, quote: usize) -> bool {
+        quote
+            .checked_sub(1)
+            .is_some_and(|prefix| matches!(self.chars[prefix], 'e' | 'E'))
+            && quote
+                .checked_sub(2)
+                .is_none_or(|before| !is_identifier_char(self.chars[before]))
+    }
+
+    fn quoted_end(&self, open: usize, quote: char, backslash_escapes: bool) -> usize {
+        let mut idx = open + 1;
+        while idx < self.chars.len() {
+            let ch = self.chars[idx];
+            if (backslash_escapes && ch == '\\')
+                || (ch == quote && self.peek(idx + 1) == Some(quote))
+            {
+                idx += 2;
+            } else if ch == quote {
+                return idx + 1;
+            } else {
+                idx += 1;
+            }
+        }
+        self.chars.len()
+    }
+
+    /// End of a `$tag$ ... $tag$` body opened at `open`, or `None` if the `$`
+    /// does not open one (e.g. a `$1` parameter or part of an identifier).
+    fn dollar_quoted_end(&self, open: usize) -> Option<usize> {
+        if open
+            .checked_sub(1)
+            .is_some_and(|before| is_identifier_char(self.chars[before]))
+        {
+            return None;
+        }
+
+        let tag_len = self.chars[open + 1..].iter().position(|&ch| ch == '$')?;
+        let tag = &self.chars[open + 1..open + 1 + tag_len];
+        if tag.first().is_some_and(char::is_ascii_digit)
+            || !tag.iter().all(|&ch| is_identifier_char(ch) && ch != '$')
+        {
+            return None;
+        }
+
+        let delimiter = &self.chars[open..open + tag_len + 2];
+        let body_start = open + delimiter.len();
+        Some(
+            self.chars[body_start..]
+                .windows(delimiter.len())
+                .position(|window| window == delimiter)
+                .map_or(self.chars.len(), |offset| {
+                    body_start + offset + delimiter.len()
+                }),
+        )
+    }
+}
+
+/// Tracks `BEGIN ATOMIC ... END` function bodies, whose `;` do not end the
+/// statement. `CASE ... END` nests inside them.
+#[derive(Default)]
+struct AtomicBody {
+    depth: usize,
+    after_begin: bool,
+}
+
+impl AtomicBody {
+    fn word(&mut self, word: &[char]) {
+        let word = word.iter().collect::<String>().to_ascii_uppercase();
+        match word.as_str() {
+            "ATOMIC" if self.after_begin => self.depth += 1,
+            "CASE" if self.is_open() => self.depth += 1,
+            "END" if self.is_open() => self.depth -= 1,
+            _ => {}
+        }
+        self.after_begin = word == "BEGIN";
+    }
+
+    const fn other(&mut self) {
+        self.after_begin = false;
+    }
+
+    const fn is_open(&self) -> bool {
+        self.depth > 0
+    }
+}
+
+const fn is_word_start(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ch == '_' || !ch.is_ascii()
+}
+
+const fn is_identifier_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_' || ch == '$' || !ch.is_ascii()
+}
+
+fn is_copy_from_stdin(sql: &str) -> bool {
+    copy_from_stdin_regex().is_match(sql)
+}
+
+#[expect(clippy::expect_used, reason = "regex is a compile-time constant")]
+fn copy_from_stdin_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"(?is)^COPY\s+(?P<target>.+?)\s+FROM\s+STDIN\b(?P<options>.*)$")
+            .expect("valid COPY regex")
+    })
+}
+
+/// Rows per `INSERT` when replaying COPY data.
+const COPY_ROWS_PER_INSERT: usize = 500;
+
+/// Rewrites a `COPY <table> [(columns)] FROM stdin` statement and its
+/// text-format rows (as written by `pg_dump`) into `INSERT` statements, so
+/// they can run on an ordinary connection or transaction.
+pub fn copy_as_inserts(statement: &SqlStatement) -> Result<Vec<String>, String> {
+    let captures = copy_from_stdin_regex()
+        .captures(&statement.sql)
+        .ok_or_else(|| "not a COPY ... FROM stdin statement".to_owned())?;
+    if !captures["options"].trim().is_empty() {
+        return Err(format!(
+            "unsupported COPY options '{}': only text-format COPY ... FROM stdin without options is supported",
+            captures["options"].trim()
+        ));
+    }
+
+    let target = captures["target"].trim();
+    let rows = statement
+        .copy_data
+        .as_deref()
+        .unwrap_or_default()
+        .lines()
+        .map(|line| {
+            let fields = line
+                .trim_end_matches('\r')
+                .split('\t')
+                .map(copy_field_literal)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("({})", fields.join(", ")))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(rows
+        .chunks(COPY_ROWS_PER_INSERT)
+        .map(|chunk| {
+            format!(
+                "INSERT INTO {target} OVERRIDING SYSTEM VALUE VALUES {}",
+                chunk.join(", ")
+            )
+        })
+        .collect())
+}
+
+/// Turns one text-format COPY field into a SQL literal: `\N` is NULL, other
+/// backslash escapes are decoded and the result is single-quoted.
+fn copy_field_literal(field: &str) -> Result<String, String> {
+    if field == "\\N" {
+        return Ok("NULL".to_owned());
+    }
+
+    let mut bytes = Vec::with_capacity(field.len());
+    let mut chars = field.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+
+        match chars.next() {
+            Some('b') => bytes.push(0x08),
+            Some('f') => bytes.push(0x0c),
+            Some('n') => bytes.push(b'\n'),
+            Some('r') => bytes.push(b'\r'),
+            Some('t') => bytes.push(b'\t'),
+            Some('v') => bytes.push(0x0b),
+            Some(digit @ '0'..='7') => {
+                let mut value = digit.to_digit(8).unwrap_or_default();
+                for _ in 0..2 {
+                    let Some(next) = chars.peek().and_then(|ch| ch.to_digit(8)) else {
+                        break;
+                    };
+                    value = value * 8 + next;
+                    chars.next();
+                }
+                bytes.push(
+                    u8::try_from(value)
+                        .map_err(|_| format!("invalid octal escape in COPY field '{field}'"))?,
+                );
+            }
+            Some('x') if chars.peek().is_some_and(char::is_ascii_hexdigit) => {
+                let mut value = 0;
+                for _ in 0..2 {
+                    let Some(next) = chars.peek().and_then(|ch| ch.to_digit(16)) else {
+                        break;
+                    };
+                    value = value * 16 + next;
+                    chars.next();
+                }
+                bytes.push(u8::try_from(value).unwrap_or_default());
+            }
+            Some(other) => {
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(other.encode_utf8(&mut buf).as_bytes());
+            }
+            None => bytes.push(b'\\'),
+        }
+    }
+
+    let text = String::from_utf8(bytes)
+        .map_err(|_| format!("COPY field '{field}' does not decode to UTF-8"))?;
+    Ok(format!("'{}'", text.replace('\'', "''")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StatementSpan, copy_as_inserts, split_statements};
+
+    #[test]
+    fn splits_on_top_level_semicolons_only() {
+        let script = "\
+-- leading comment; not a statement
+CREATE TABLE cake (name TEXT DEFAULT 'a;b', \"odd;col\" INT);
+/* block /* nested; */ still comment; */
+INSERT INTO cake VALUES (E'it\\'s;', 1);
+CREATE FUNCTION f() RETURNS TEXT AS $body$
+BEGIN
+    RETURN 'x;y';
+END;
+$body$ LANGUAGE plpgsql;
+SELECT $1::int, a$b FROM t;
+SELECT 'no trailing semicolon'";
+
+        let statements = split_statements(script);
+        let sql: Vec<_> = statements.iter().map(|s| s.sql.as_str()).collect();
+        assert_eq!(
+            sql,
+            [
+                "CREATE TABLE cake (name TEXT DEFAULT 'a;b', \"odd;col\" INT)",
+                "INSERT INTO cake VALUES (E'it\\'s;', 1)",
+                "CREATE FUNCTION f() RETURNS TEXT AS $body$\nBEGIN\n    RETURN 'x;y';\nEND;\n$body$ LANGUAGE plpgsql",
+                "SELECT $1::int, a$b FROM t",
+                "SELECT 'no trailing semicolon'",
+            ]
+        );
+        assert_eq!(
+            statements[2].span,
+            StatementSpan {
+                start_line: 5,
+                start_column: 1,
+                end_line: 9,
+                end_column: 23,
+            }
+        );
+        assert_eq!(statements[1].span.to_string(), "line 4, columns 1-38");
+        assert!(split_statements("-- nothing here\n;;\n/* */").is_empty());
+    }
+
+    #[test]
+    fn keeps_begin_atomic_bodies_in_one_statement() {
+        let script = "\
+CREATE FUNCTION size_of(price NUMERIC) RETURNS TEXT
+LANGUAGE SQL
+begin atomic
+    SELECT CASE WHEN price > 10 THEN 'large' ELSE 'small' END;
+    -- comment; with END
+end;
+BEGIN;
+CREATE PROCEDURE reset_cakes() BEGIN /* gap */ ATOMIC DELETE FROM cake; END;
+COMMIT;";
+
+        let sql: Vec<_> = split_statements(script)
+            .into_iter()
+            .map(|statement| statement.sql)
+            .collect();
+        assert_eq!(
+            sql,
+            [
+                "CREATE FUNCTION size_of(price NUMERIC) RETURNS TEXT\nLANGUAGE SQL\nbegin atomic\n    SELECT CASE WHEN price > 10 THEN 'large' ELSE 'small' END;\n    -- comment; with END\nend",
+                "BEGIN",
+                "CREATE PROCEDURE reset_cakes() BEGIN /* gap */ ATOMIC DELETE FROM cake; END",
+                "COMMIT",
+            ]
+        );
+    }
+
+    #[test]
+    fn attaches_copy_data_and_rewrites_it_as_inserts() {
+        let script = "\
+COPY public.cake (id, name) FROM stdin;
+1\tvanilla
+2\t\\N
+3\tit's\\ta\\\\b\\101
+\\.
+SELECT 1;";
+
+        let statements = split_statements(script);
+        assert_eq!(statements.len(), 2);
+        let copy = &statements[0];
+        assert_eq!(
+            copy.copy_data.as_deref(),
+            Some("1\tvanilla\n2\t\\N\n3\tit's\\ta\\\\b\\101\n")
+        );
+        assert_eq!((copy.span.end_line, copy.span.end_column), (5, 2));
+        assert_eq!(statements[1].sql, "SELECT 1");
+        assert_eq!(statements[1].span.start_line, 6);
+
+        assert_eq!(
+            copy_as_inserts(copy).expect("text COPY is supported"),
+            [
+                "INSERT INTO public.cake (id, name) OVERRIDING SYSTEM VALUE VALUES ('1', 'vanilla'), ('2', NULL), ('3', 'it''s\ta\\bA')"
+            ]
+        );
+
+        let csv = &split_statements("COPY cake FROM stdin WITH (FORMAT csv);\n1,a\n\\.\n")[0];
+        let err = copy_as_inserts(csv).expect_err("csv COPY is rejected");
+        assert!(
+            err.contains("unsupported COPY options"),
+            "unexpected error: {err}"
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{SchemalaneConfig, SchemalaneError};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// What a multi-target run does after a target fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnFailure {
+    /// Skip the remaining targets.
+    #[default]
+    Stop,
+    /// Run the remaining targets anyway.
+    Continue,
+}
+
+impl OnFailure {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Stop => "stop",
+            Self::Continue => "continue",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, SchemalaneError> {
+        match value {
+            "stop" => Ok(Self::Stop),
+            "continue" => Ok(Self::Continue),
+            _ => Err(SchemalaneError::Validation(format!(
+                "invalid on-failure policy '{value}': expected 'stop' or 'continue'"
+            ))),
+        }
+    }
+}
+
+impl std::str::FromStr for OnFailure {
+    type Err = SchemalaneError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse(value)
+    }
+}
+
+impl std::fmt::Display for OnFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Several databases migrated in one run, read from a TOML file:
+///
+/// ```toml
+/// on_failure = "continue"
+///
+/// [[target]]
+/// name = "orders"
+/// url_env = "ORDERS_DATABASE_URL"
+/// migrations_dir = "db/orders"
+/// schema = "orders"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetsConfig {
+    #[serde(default)]
+    pub on_failure: OnFailure,
+    /// Run in file order.
+    #[serde(rename = "target")]
+    pub targets: Vec<TargetConfig>,
+}
+
+/// One database of a `TargetsConfig`. Unset fields keep the value of the
+/// base configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetConfig {
+    pub name: String,
+    /// Environment variable holding the database URL, so the file can be
+    /// committed without credentials.
+    pub url_env: String,
+    pub migrations_dir: PathBuf,
+    pub schema: Option<String>,
+    pub history_table: Option<String>,
+}
+
+impl TargetsConfig {
+    pub fn load(path: &Path) -> Result<Self, SchemalaneError> {
+        let text = std::fs::read_to_string(path)?;
+        let mut config = Self::from_toml(&text)
+            .map_err(|err| SchemalaneError::Validation(format!("{}: {err}", path.display())))?;
+        // Relative directories are relative to the file, not the caller.
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        for target in &mut config.targets {
+            target.migrations_dir = base.join(&target.migrations_dir);
+        }
+        Ok(config)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SchemalaneError> {
+        Self::from_toml(text)
+            .map_err(|err| SchemalaneError::Validation(format!("targets file: {err}")))
+    }
+
+    fn from_toml(text: &str) -> Result<Self, String> {
+        let config: Self = toml::from_str(text).map_err(|err| err.message().to_owned())?;
+        if config.targets.is_empty() {
+            return Err("no [[target]] defined".to_owned());
+        }
+        let mut names = BTreeSet::new();
+        for target in &config.targets {
+            if !names.insert(target.name.as_str()) {
+                return Err(format!("duplicate target name '{}'", target.name));
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl TargetConfig {
+    /// Reads the URL from `url_env`.
+    pub fn database_url(&self) -> Result<String, SchemalaneError> {
+        std::env::var(&self.url_env).map_err(|_| {
+            SchemalaneError::Validation(format!(
+                "target '{}': environment variable {} is not set",
+                self.name, self.url_env
+            ))
+        })
+    }
+
+    /// `base` with this target's schema, history table and migrations
+    /// directory.
+    pub fn apply(&self, base: &SchemalaneConfig) -> SchemalaneConfig {
+        let mut config = base.clone();
+        config.migrations_dir.clone_from(&self.migrations_dir);
+        config.migrations_dirs.clear();
+        if let Some(schema) = &self.schema {
+            config.schema.clone_from(schema);
+            config.schemas.clear();
+        }
+        if let Some(history_table) = &self.history_table {
+            config.history_table.clone_from(history_table);
+        }
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OnFailure, TargetsConfig};
+    use crate::SchemalaneConfig;
+    use std::path::PathBuf;
+
+    #[test]
+    fn targets_file_is_parsed_and_applied_over_the_base_config() {
+        let config = TargetsConfig::parse(
+            r#"
+on_failure = "continue"
+
+[[target]]
+name = "orders"
+url_env = "ORDERS_DATABASE_URL"
+migrations_dir = "db/orders"
+schema = "orders"
+
+[[target]]
+name = "billing"
+url_env = "BILLING_DATABASE_URL"
+migrations_dir = "db/billing"
+history_table = "billing_history"
+"#,
+        )
+        .expect("valid targets file");
+        assert_eq!(config.on_failure, OnFailure::Continue);
+        assert_eq!(config.targets.len(), 2);
+
+        let base = SchemalaneConfig {
+            schemas: vec!["app".to_owned(), "audit".to_owned()],
+            ..SchemalaneConfig::default()
+        };
+        let orders = config.targets[0].apply(&base);
+        assert_eq!(orders.migrations_dir, PathBuf::from("db/orders"));
+        assert_eq!(orders.managed_schemas(), ["orders"]);
+        assert_eq!(orders.history_table, "flyway_schema_history");
+        let billing = config.targets[1].apply(&base);
+        assert_eq!(billing.managed_schemas(), ["app", "audit"]);
+        assert_eq!(billing.history_table, "billing_history");
+    }
+
+    #[test]
+    fn targets_file_errors_are_validation_errors() {
+        let target = "[[target]]\nname = \"a\"\nurl_env = \"A_URL\"\nmigrations_dir = \"a\"\n";
+        assert_eq!(
+            TargetsConfig::parse("on_failure = \"stop\"\n")
+                .map(|_| ())
+                .map_err(|err| err.exit_code()),
+            Err(2)
+        );
+        let duplicate = format!("{target}{target}");
+        assert!(
+            TargetsConfig::parse(&duplicate)
+                .is_err_and(|err| err.to_string().contains("duplicate target name 'a'"))
+        );
+        assert!(TargetsConfig::parse(&format!("{target}schemas = [\"x\"]\n")).is_err());
+        assert!(TargetsConfig::parse("on_failure = \"retry\"\n").is_err());
+        assert_eq!(
+            TargetsConfig::parse(target).map(|c| c.on_failure).ok(),
+            Some(OnFailure::Stop)
+        );
+    }
+}
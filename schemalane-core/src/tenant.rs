@@ -0,0 +1,358 @@
+use crate::{
+    MigrationState, MigrationTarget, ParsedVersion, RunReport, SchemalaneError, SchemalaneMigrator,
+    StatusReport,
+};
+use futures_util::stream::{self, StreamExt};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+
+const DEFAULT_TENANT_CONCURRENCY: usize = 4;
+
+/// Which schemas a `TenantRunner` migrates, one tenant per schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TenantSelector {
+    /// Schemas whose name matches a SQL `LIKE` pattern, e.g. `tenant_%`.
+    /// System schemas never match.
+    SchemaPattern(String),
+    /// A query returning the schema names in its first column.
+    Query(String),
+    /// An explicit list of schemas.
+    Schemas(Vec<String>),
+}
+
+/// Runs a migrator against many tenant schemas, a bounded number at a time.
+///
+/// Each tenant gets its own copy of the migrator from
+/// `SchemalaneMigrator::for_tenant`: its history table lives in the tenant
+/// schema, unqualified names in its migrations resolve there,
+/// `${schemalane:defaultSchema}` expands to it, and its advisory lock is
+/// derived from it, so two runners only wait on each other for the same
+/// tenant.
+pub struct TenantRunner<'m> {
+    migrator: &'m SchemalaneMigrator,
+    concurrency: usize,
+}
+
+impl<'m> TenantRunner<'m> {
+    pub const fn new(migrator: &'m SchemalaneMigrator) -> Self {
+        Self {
+            migrator,
+            concurrency: DEFAULT_TENANT_CONCURRENCY,
+        }
+    }
+
+    /// How many tenants run at once; at least 1. Each one holds up to two
+    /// pooled connections, one of them for its advisory lock, and opens one
+    /// more of its own to run migrations on.
+    #[must_use]
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// The schemas `selector` picks, sorted and without duplicates.
+    pub async fn tenants(
+        &self,
+        db: &DatabaseConnection,
+        selector: &TenantSelector,
+    ) -> Result<Vec<String>, SchemalaneError> {
+        let stmt = match selector {
+            TenantSelector::Schemas(schemas) => {
+                let mut schemas = schemas.clone();
+                schemas.sort();
+                schemas.dedup();
+                return Ok(schemas);
+            }
+            TenantSelector::SchemaPattern(pattern) => Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "SELECT nspname::text FROM pg_catalog.pg_namespace \
+                 WHERE nspname LIKE $1 \
+                   AND nspname NOT LIKE 'pg\\_%' AND nspname <> 'information_schema'",
+                [pattern.as_str().into()],
+            ),
+            TenantSelector::Query(query) => {
+                Statement::from_string(DbBackend::Postgres, query.clone())
+            }
+        };
+
+        let mut schemas = Vec::new();
+        for row in db.query_all_raw(stmt).await? {
+            schemas.push(row.try_get_by_index::<String>(0)?);
+        }
+        schemas.sort();
+        schemas.dedup();
+        Ok(schemas)
+    }
+
+    /// `up` for every tenant. A failing tenant does not stop the others.
+    pub async fn up(
+        &self,
+        db: &DatabaseConnection,
+        tenants: &[String],
+    ) -> BTreeMap<String, Result<RunReport, SchemalaneError>> {
+        self.up_to(db, tenants, &MigrationTarget::Latest).await
+    }
+
+    /// `up_to` for every tenant, each stopping at `target`.
+    pub async fn up_to(
+        &self,
+        db: &DatabaseConnection,
+        tenants: &[String],
+        target: &MigrationTarget,
+    ) -> BTreeMap<String, Result<RunReport, SchemalaneError>> {
+        self.for_each(tenants, |migrator| async move {
+            migrator.up_to(db, target).await
+        })
+        .await
+    }
+
+    /// `status` for every tenant; see `TenantStatusRollup` for a summary.
+    pub async fn status(
+        &self,
+        db: &DatabaseConnection,
+        tenants: &[String],
+    ) -> BTreeMap<String, Result<StatusReport, SchemalaneError>> {
+        self.for_each(tenants, |migrator| async move { migrator.status(db).await })
+            .await
+    }
+
+    async fn for_each<T, F, Fut>(
+        &self,
+        tenants: &[String],
+        run: F,
+    ) -> BTreeMap<String, Result<T, SchemalaneError>>
+    where
+        T: Send,
+        F: Fn(SchemalaneMigrator) -> Fut + Sync,
+        Fut: Future<Output = Result<T, SchemalaneError>> + Send,
+    {
+        stream::iter(tenants)
+            .map(|schema| {
+                let migrator = self.migrator.for_tenant(schema);
+                let run = &run;
+                async move { (schema.clone(), Box::pin(run(migrator)).await) }
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await
+    }
+}
+
+/// Where one tenant stands, as shown by `TenantStatusRollup`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TenantStatus {
+    pub schema: String,
+    /// Highest version applied successfully, if any.
+    pub current_version: Option<String>,
+    /// Migrations `up` would apply: pending ones and outdated repeatables.
+    pub pending: usize,
+    pub failed: usize,
+    /// Why the status of this tenant could not be read.
+    pub error: Option<String>,
+}
+
+impl TenantStatus {
+    fn from_report(schema: &str, report: &StatusReport) -> Self {
+        let current_version = report
+            .migrations
+            .iter()
+            .filter(|entry| entry.state == MigrationState::Success)
+            .filter_map(|entry| entry.version.as_deref())
+            .filter_map(|version| Some((ParsedVersion::parse(version).ok()?, version)))
+            .max()
+            .map(|(_, version)| version.to_owned());
+        Self {
+            schema: schema.to_owned(),
+            current_version,
+            pending: report.summary.pending + report.summary.outdated,
+            failed: report.summary.failed,
+            error: None,
+        }
+    }
+
+    /// Whether `up` has anything to do for this tenant.
+    pub const fn is_behind(&self) -> bool {
+        self.pending > 0
+    }
+}
+
+/// One line per tenant of a multi-tenant `status`, and how many of them are
+/// behind.
+#[derive(Debug, Clone, Serialize)]
+pub struct TenantStatusRollup {
+    pub tenants: Vec<TenantStatus>,
+    pub up_to_date: usize,
+    pub behind: usize,
+    /// Tenants with a failed migration in their history.
+    pub failed: usize,
+    /// Tenants whose status could not be read.
+    pub errors: usize,
+}
+
+impl TenantStatusRollup {
+    pub fn from_reports(reports: &BTreeMap<String, Result<StatusReport, SchemalaneError>>) -> Self {
+        let tenants: Vec<_> = reports
+            .iter()
+            .map(|(schema, report)| match report {
+                Ok(report) => TenantStatus::from_report(schema, report),
+                Err(err) => TenantStatus {
+                    schema: schema.clone(),
+                    current_version: None,
+                    pending: 0,
+                    failed: 0,
+                    error: Some(err.to_string()),
+                },
+            })
+            .collect();
+        let count =
+            |matches: fn(&TenantStatus) -> bool| tenants.iter().filter(|t| matches(t)).count();
+        Self {
+            up_to_date: count(|t| t.error.is_none() && !t.is_behind() && t.failed == 0),
+            behind: count(TenantStatus::is_behind),
+            failed: count(|t| t.failed > 0),
+            errors: count(|t| t.error.is_some()),
+            tenants,
+        }
+    }
+}
+
+pub fn format_tenant_status_table(rollup: &TenantStatusRollup) -> String {
+    let mut lines = vec![
+        "schema | current_version | pending | failed | state".to_owned(),
+        "-------|-----------------|---------|--------|------".to_owned(),
+    ];
+    for tenant in &rollup.tenants {
+        let state = tenant.error.as_ref().map_or_else(
+            || {
+                if tenant.failed > 0 {
+                    "failed".to_owned()
+                } else if tenant.is_behind() {
+                    "behind".to_owned()
+                } else {
+                    "up to date".to_owned()
+                }
+            },
+            |error| format!("error: {error}"),
+        );
+        lines.push(format!(
+            "{} | {} | {} | {} | {state}",
+            tenant.schema,
+            tenant.current_version.as_deref().unwrap_or("-"),
+            tenant.pending,
+            tenant.failed,
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push(format!(
+        "summary: tenants={}, up_to_date={}, behind={}, failed={}, errors={}",
+        rollup.tenants.len(),
+        rollup.up_to_date,
+        rollup.behind,
+        rollup.failed,
+        rollup.errors
+    ));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TenantStatusRollup, format_tenant_status_table};
+    use crate::{
+        MigrationState, SchemalaneConfig, SchemalaneError, SchemalaneMigrator, StatusEntry,
+        StatusReport, StatusSummary,
+    };
+    use std::collections::BTreeMap;
+
+    fn report(schema: &str, versions: &[(&str, MigrationState)]) -> StatusReport {
+        let mut summary = StatusSummary::default();
+        let migrations = versions
+            .iter()
+            .map(|&(version, state)| {
+                match state {
+                    MigrationState::Success => summary.success += 1,
+                    MigrationState::Pending => summary.pending += 1,
+                    MigrationState::Failed => summary.failed += 1,
+                    _ => {}
+                }
+                StatusEntry {
+                    version: Some(version.to_owned()),
+                    description: "step".to_owned(),
+                    migration_type: "SQL".to_owned(),
+                    script: format!("V{version}__step.sql"),
+                    checksum: None,
+                    installed_rank: None,
+                    installed_on: None,
+                    execution_time_ms: None,
+                    transaction_mode: None,
+                    state,
+                }
+            })
+            .collect();
+        StatusReport {
+            schema: schema.to_owned(),
+            history_table: "flyway_schema_history".to_owned(),
+            advisory_lock_id: None,
+            migrations,
+            summary,
+        }
+    }
+
+    #[test]
+    fn rollup_shows_which_tenants_are_behind() {
+        let mut reports = BTreeMap::new();
+        reports.insert(
+            "tenant_a".to_owned(),
+            Ok(report(
+                "tenant_a",
+                &[
+                    ("2", MigrationState::Success),
+                    ("10", MigrationState::Success),
+                ],
+            )),
+        );
+        reports.insert(
+            "tenant_b".to_owned(),
+            Ok(report(
+                "tenant_b",
+                &[
+                    ("2", MigrationState::Success),
+                    ("10", MigrationState::Pending),
+                ],
+            )),
+        );
+        reports.insert(
+            "tenant_c".to_owned(),
+            Err(SchemalaneError::Validation("boom".to_owned())),
+        );
+
+        let rollup = TenantStatusRollup::from_reports(&reports);
+        assert_eq!(rollup.up_to_date, 1);
+        assert_eq!(rollup.behind, 1);
+        assert_eq!(rollup.errors, 1);
+        assert_eq!(rollup.tenants[0].current_version.as_deref(), Some("10"));
+        assert_eq!(rollup.tenants[1].current_version.as_deref(), Some("2"));
+        assert!(rollup.tenants[1].is_behind());
+
+        let table = format_tenant_status_table(&rollup);
+        assert!(table.contains("tenant_a | 10 | 0 | 0 | up to date"));
+        assert!(table.contains("tenant_b | 2 | 1 | 0 | behind"));
+        assert!(table.contains("tenant_c | - | 0 | 0 | error: Validation error: boom"));
+        assert!(table.ends_with("summary: tenants=3, up_to_date=1, behind=1, failed=0, errors=1"));
+    }
+
+    #[test]
+    fn tenant_migrators_use_their_own_schema_and_lock() {
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            schemas: vec!["app".to_owned(), "audit".to_owned()],
+            ..SchemalaneConfig::default()
+        });
+        let tenant = migrator.for_tenant("tenant_a");
+        assert_eq!(tenant.config().schema, "tenant_a");
+        assert_eq!(tenant.config().managed_schemas(), vec!["tenant_a"]);
+        assert_eq!(tenant.config().advisory_lock, crate::AdvisoryLock::Derived);
+        assert_eq!(migrator.config().schema, "app");
+    }
+}
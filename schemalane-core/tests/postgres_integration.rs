@@ -1,12 +1,21 @@
+#![allow(
+    clippy::needless_raw_string_hashes,
+    clippy::uninlined_format_args,
+    reason = "fixtures predate these lints"
+)]
+
 use schemalane_core::{
-    MigrationState, RustMigrationExecutor, RustTransactionMode, SchemalaneConfig, SchemalaneError,
-    SchemalaneMigrator,
+    AdvisoryLock, CallbackEvent, DEFAULT_BASELINE_DESCRIPTION, MigrationState, MigrationTarget,
+    MigrationTimeoutKind, MigrationTimeouts, RetryPolicy, RustCallback, RustMigrationExecutor,
+    RustTransactionMode, SchemalaneConfig, SchemalaneError, SchemalaneMigrator, TenantRunner,
+    TenantSelector, TenantStatusRollup, TransactionMode, ValidationIssueKind, ensure_valid,
 };
-use sea_orm::{ConnectionTrait, Database, DbBackend, DbErr, Statement};
+use sea_orm::{ConnectionTrait, Database, DbBackend, DbErr, Statement, TransactionTrait};
 use sea_orm_migration::SchemaManager;
 use std::error::Error;
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
 use tempfile::TempDir;
 use testcontainers_modules::{postgres::Postgres, testcontainers::runners::SyncRunner};
 
@@ -140,6 +149,275 @@ CREATE TABLE cake (
     Ok(())
 }
 
+#[test]
+#[ignore = "requires Docker daemon"]
+fn fresh_drops_every_kind_of_schema_object() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_bakery.sql",
+        r"
+CREATE EXTENSION citext;
+CREATE TYPE flavour AS ENUM ('chocolate', 'vanilla');
+CREATE TYPE dimensions AS (width INT, height INT);
+CREATE DOMAIN positive_price AS NUMERIC CHECK (VALUE > 0);
+CREATE SEQUENCE order_numbers;
+CREATE TABLE cake (
+    id SERIAL PRIMARY KEY,
+    name CITEXT NOT NULL,
+    flavour flavour NOT NULL,
+    price positive_price
+);
+CREATE VIEW chocolate_cake AS SELECT * FROM cake WHERE flavour = 'chocolate';
+CREATE MATERIALIZED VIEW cake_count AS SELECT COUNT(*) FROM cake;
+CREATE FUNCTION add_tax(price NUMERIC, rate NUMERIC) RETURNS NUMERIC
+    LANGUAGE sql AS 'SELECT price * (1 + rate)';
+CREATE PROCEDURE reset_cakes() LANGUAGE sql AS 'DELETE FROM cake';
+",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            migrations_dir,
+            ..Default::default()
+        });
+        migrator.up(&db).await?;
+
+        let plan = migrator.dry_run_fresh(&db).await?;
+        let planned: Vec<_> = plan
+            .dropped_objects
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            planned,
+            [
+                "extension citext",
+                "materialized view public.cake_count",
+                "view public.chocolate_cake",
+                "table public.cake",
+                "table public.flyway_schema_history",
+                "table public.flyway_schema_history_schemalane",
+                "sequence public.order_numbers",
+                "function public.add_tax(price numeric, rate numeric)",
+                "procedure public.reset_cakes()",
+                "domain public.positive_price",
+                "type public.dimensions",
+                "type public.flavour",
+            ]
+        );
+
+        // V1 recreates every type, so it fails if any of them survived.
+        let report = migrator.fresh(&db, true).await?;
+        assert_eq!(report.dropped_objects, plan.dropped_objects);
+        assert_eq!(report.applied.len(), 1);
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires Docker daemon"]
+fn managed_schemas_are_created_recorded_and_cleaned() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_tables.sql",
+        r"
+CREATE TABLE public.cake (id SERIAL PRIMARY KEY);
+CREATE TABLE audit.events (id SERIAL PRIMARY KEY);
+CREATE TABLE billing.invoices (id SERIAL PRIMARY KEY);
+",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        // `billing` exists beforehand, so it is emptied but never dropped.
+        db.execute_unprepared("CREATE SCHEMA billing").await?;
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            migrations_dir: migrations_dir.clone(),
+            schemas: vec!["public".to_owned(), "audit".to_owned(), "billing".to_owned()],
+            ..Default::default()
+        });
+
+        let report = migrator.up(&db).await?;
+        assert_eq!(report.created_schemas, ["audit"]);
+        let marker = scalar_i64(
+            &db,
+            "SELECT COUNT(*) AS count FROM public.flyway_schema_history WHERE type = 'SCHEMA' AND script = '\"audit\"'",
+        )
+        .await?;
+        assert_eq!(marker, 1);
+        let status = migrator.status(&db).await?;
+        assert!(
+            status
+                .migrations
+                .iter()
+                .all(|entry| entry.state == MigrationState::Success)
+        );
+
+        let plan = migrator.dry_run_fresh(&db).await?;
+        let dropped: Vec<_> = plan.dropped_objects.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            dropped,
+            [
+                "table public.cake",
+                "table public.flyway_schema_history",
+                "table public.flyway_schema_history_schemalane",
+                "schema audit",
+                "table billing.invoices",
+            ]
+        );
+
+        let report = migrator.fresh(&db, true).await?;
+        assert_eq!(report.created_schemas, ["audit"]);
+        assert_eq!(report.applied.len(), 1);
+        let events = scalar_i64(&db, "SELECT COUNT(*) AS count FROM audit.events").await?;
+        assert_eq!(events, 0);
+
+        // Migrations run with the search_path the pool sets up.
+        write_migration(
+            &migrations_dir,
+            "V2__seed_events.sql",
+            "INSERT INTO events DEFAULT VALUES;",
+        )?;
+        let mut options = sea_orm::ConnectOptions::new(db_url);
+        options.set_schema_search_path("public,audit");
+        migrator.up(&Database::connect(options).await?).await?;
+        let events = scalar_i64(&db, "SELECT COUNT(*) AS count FROM audit.events").await?;
+        assert_eq!(events, 1);
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires Docker daemon"]
+#[expect(
+    clippy::literal_string_with_formatting_args,
+    reason = "the SQL uses placeholders"
+)]
+fn tenant_runner_migrates_each_matching_schema() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_cake.sql",
+        "CREATE TABLE ${schemalane:defaultSchema}.cake (id SERIAL PRIMARY KEY);",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        for schema in ["tenant_a", "tenant_b", "tenant_c", "other"] {
+            db.execute_unprepared(&format!("CREATE SCHEMA {schema}"))
+                .await?;
+        }
+        // `tenant_c` already has a table the migration would create.
+        db.execute_unprepared("CREATE TABLE tenant_c.cake (id INT)")
+            .await?;
+
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            migrations_dir: migrations_dir.clone(),
+            ..Default::default()
+        });
+        let runner = TenantRunner::new(&migrator).with_concurrency(2);
+        let tenants = runner
+            .tenants(&db, &TenantSelector::SchemaPattern("tenant_%".to_owned()))
+            .await?;
+        assert_eq!(tenants, ["tenant_a", "tenant_b", "tenant_c"]);
+
+        let rollup = TenantStatusRollup::from_reports(&runner.status(&db, &tenants).await);
+        assert_eq!(rollup.behind, 3);
+
+        let results = runner.up(&db, &tenants).await;
+        assert_eq!(
+            results["tenant_a"].as_ref().map(|r| r.applied.len()).ok(),
+            Some(1)
+        );
+        assert_eq!(
+            results["tenant_b"].as_ref().map(|r| r.applied.len()).ok(),
+            Some(1)
+        );
+        assert!(matches!(
+            results["tenant_c"],
+            Err(SchemalaneError::MigrationExecution { .. })
+        ));
+        let history = scalar_i64(
+            &db,
+            "SELECT COUNT(*) AS count FROM tenant_b.flyway_schema_history",
+        )
+        .await?;
+        assert_eq!(history, 1);
+        assert!(!table_exists(&db, "flyway_schema_history").await?);
+
+        write_migration(
+            &migrations_dir,
+            "V2__add_name.sql",
+            "ALTER TABLE cake ADD COLUMN name TEXT;",
+        )?;
+        let tenants = runner
+            .tenants(
+                &db,
+                &TenantSelector::Query("SELECT 'tenant_a' UNION SELECT 'tenant_b'".to_owned()),
+            )
+            .await?;
+        let held_back = runner
+            .up_to(&db, &tenants[1..], &MigrationTarget::parse("1")?)
+            .await;
+        let held_back = held_back["tenant_b"]
+            .as_ref()
+            .map_err(ToString::to_string)?;
+        assert!(held_back.applied.is_empty());
+        assert_eq!(held_back.unapplied.len(), 1);
+        runner.up(&db, &tenants[..1]).await["tenant_a"]
+            .as_ref()
+            .map_err(ToString::to_string)?;
+        // The unqualified name resolved to the tenant schema.
+        let named = scalar_i64(
+            &db,
+            "SELECT COUNT(*) AS count FROM information_schema.columns WHERE table_schema = 'tenant_a' AND table_name = 'cake' AND column_name = 'name'",
+        )
+        .await?;
+        assert_eq!(named, 1);
+
+        let rollup = TenantStatusRollup::from_reports(&runner.status(&db, &tenants).await);
+        assert_eq!(rollup.up_to_date, 1);
+        assert_eq!(rollup.behind, 1);
+        assert_eq!(rollup.tenants[0].current_version.as_deref(), Some("2"));
+        assert_eq!(rollup.tenants[1].current_version.as_deref(), Some("1"));
+        assert!(rollup.tenants[1].is_behind());
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
 #[test]
 #[ignore = "requires Docker daemon"]
 fn status_detects_checksum_mismatch() -> Result<(), Box<dyn Error + 'static>> {
@@ -410,39 +688,1179 @@ fn rust_migration_requires_registered_executor() -> Result<(), Box<dyn Error + '
     Ok(())
 }
 
-fn connection_string(
-    node: &testcontainers_modules::testcontainers::core::Container<Postgres>,
-) -> Result<String, Box<dyn Error + 'static>> {
-    let host = node.get_host()?;
-    let port = node.get_host_port_ipv4(5432)?;
-    Ok(format!(
-        "postgres://postgres:postgres@{host}:{port}/postgres"
-    ))
-}
+#[test]
+#[ignore = "requires Docker daemon"]
+fn repair_clears_failed_rows_and_realigns_checksums() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
 
-fn write_migration(
-    migrations_dir: &Path,
-    file_name: &str,
-    sql: &str,
-) -> Result<(), std::io::Error> {
-    let path = migrations_dir.join(file_name);
-    fs::write(path, sql)
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_cake.sql",
+        "CREATE TABLE cake (id SERIAL PRIMARY KEY);",
+    )?;
+    write_migration(
+        &migrations_dir,
+        "V2__broken.sql",
+        "CREATE TABLE broken (id SERIAL PRIMARY KEY, oops);",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            migrations_dir: migrations_dir.clone(),
+            ..Default::default()
+        });
+
+        migrator.up(&db).await.expect_err("V2 should fail");
+        let err = migrator
+            .up(&db)
+            .await
+            .expect_err("failed row should block up");
+        assert!(
+            matches!(err, SchemalaneError::FailedHistory(_)),
+            "expected FailedHistory, got: {err}"
+        );
+
+        write_migration(
+            &migrations_dir,
+            "V1__create_cake.sql",
+            "-- reformatted\nCREATE TABLE cake (id SERIAL PRIMARY KEY);",
+        )?;
+        write_migration(
+            &migrations_dir,
+            "V2__broken.sql",
+            "CREATE TABLE broken (id SERIAL PRIMARY KEY);",
+        )?;
+
+        let report = migrator.repair(&db, false).await?;
+        assert_eq!(report.removed_failed.len(), 1);
+        assert_eq!(report.removed_failed[0].script, "V2__broken.sql");
+        assert_eq!(report.realigned.len(), 1);
+        assert_eq!(report.realigned[0].script, "V1__create_cake.sql");
+
+        let up_report = migrator.up(&db).await?;
+        assert_eq!(up_report.applied.len(), 1);
+        assert_eq!(up_report.skipped, 1);
+
+        fs::remove_file(migrations_dir.join("V2__broken.sql"))?;
+        let report = migrator.repair(&db, true).await?;
+        assert_eq!(report.marked_deleted.len(), 1);
+
+        let status = migrator.status(&db).await?;
+        assert_eq!(status.summary.success, 1);
+        assert_eq!(status.summary.missing, 0);
+        assert_eq!(status.summary.failed, 0);
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
 }
 
-fn write_rust_migration(migrations_dir: &Path, file_name: &str) -> Result<(), std::io::Error> {
-    let path = migrations_dir.join(file_name);
-    fs::write(
-        path,
-        r#"
-use sea_orm::DbErr;
-use sea_orm_migration::SchemaManager;
+#[test]
+#[ignore = "requires Docker daemon"]
+fn baseline_skips_migrations_already_in_schema() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_cake.sql",
+        "CREATE TABLE cake (id SERIAL PRIMARY KEY);",
+    )?;
+    write_migration(
+        &migrations_dir,
+        "V2__add_cake_name.sql",
+        "ALTER TABLE cake ADD COLUMN name TEXT;",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        db.execute_unprepared("CREATE TABLE cake (id SERIAL PRIMARY KEY);")
+            .await?;
+
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            migrations_dir,
+            ..Default::default()
+        });
+
+        let baseline = migrator
+            .baseline(&db, "1", DEFAULT_BASELINE_DESCRIPTION)
+            .await?;
+        assert_eq!(baseline.installed_rank, 1);
+
+        let status = migrator.status(&db).await?;
+        assert_eq!(status.summary.below_baseline, 1);
+        assert_eq!(status.summary.pending, 1);
+
+        let report = migrator.up(&db).await?;
+        assert_eq!(report.applied.len(), 1);
+        assert_eq!(report.applied[0].script, "V2__add_cake_name.sql");
+        assert_eq!(report.skipped, 1);
+
+        let err = migrator
+            .baseline(&db, "1", DEFAULT_BASELINE_DESCRIPTION)
+            .await
+            .expect_err("baseline on a non-empty history should fail");
+        assert!(
+            matches!(err, SchemalaneError::Validation(_)),
+            "expected Validation, got: {err}"
+        );
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
 
-pub async fn migration(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
-    let _ = manager;
     Ok(())
 }
-"#,
-    )
+
+#[test]
+#[ignore = "requires Docker daemon"]
+fn no_transaction_sql_runs_concurrent_index_and_records_mode()
+-> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_cake.sql",
+        "CREATE TABLE cake (id SERIAL PRIMARY KEY, name TEXT NOT NULL);",
+    )?;
+    write_migration(
+        &migrations_dir,
+        "V2__index_cake_name.sql",
+        "-- schemalane:no-transaction\nCREATE INDEX CONCURRENTLY cake_name_idx ON cake (name);\n\
+         CREATE TEMP TABLE seed_cake (name TEXT);\n\
+         INSERT INTO seed_cake VALUES ('lemon');\n\
+         INSERT INTO cake (name) SELECT name FROM seed_cake;",
+    )?;
+    write_migration(&migrations_dir, "V3__vacuum_cake.sql", "VACUUM cake;")?;
+    write_migration(
+        &migrations_dir,
+        "V3__vacuum_cake.sql.conf",
+        "executeInTransaction=false\n",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            migrations_dir,
+            ..Default::default()
+        });
+
+        let report = migrator.up(&db).await?;
+        assert_eq!(report.applied.len(), 3);
+        // The temporary table only exists on the connection that created it.
+        let seeded = scalar_i64(&db, "SELECT COUNT(*) AS count FROM cake").await?;
+        assert_eq!(seeded, 1);
+
+        let status = migrator.status(&db).await?;
+        let modes: Vec<_> = status
+            .migrations
+            .iter()
+            .map(|m| (m.script.as_str(), m.transaction_mode))
+            .collect();
+        assert_eq!(
+            modes,
+            [
+                ("V1__create_cake.sql", Some(TransactionMode::Transaction)),
+                ("V2__index_cake_name.sql", Some(TransactionMode::NoTransaction)),
+                ("V3__vacuum_cake.sql", Some(TransactionMode::NoTransaction)),
+            ]
+        );
+
+        let non_transactional = scalar_i64(
+            &db,
+            "SELECT COUNT(*) AS count FROM public.flyway_schema_history_schemalane WHERE transactional = FALSE",
+        )
+        .await?;
+        assert_eq!(non_transactional, 2);
+
+        let added_columns = scalar_i64(
+            &db,
+            "SELECT COUNT(*) AS count FROM information_schema.columns WHERE table_schema = 'public' AND table_name = 'flyway_schema_history' AND column_name = 'transactional'",
+        )
+        .await?;
+        assert_eq!(added_columns, 0);
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires Docker daemon"]
+#[expect(
+    clippy::literal_string_with_formatting_args,
+    reason = "the SQL uses placeholders"
+)]
+fn placeholders_are_substituted_at_run_time() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_settings.sql",
+        "CREATE TABLE ${schemalane:defaultSchema}.settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);\n\
+         INSERT INTO settings VALUES ('region', '${region}'), ('owner', '${schemalane:user}'), ('stamp', '${schemalane:timestamp}');",
+    )?;
+    write_migration(
+        &migrations_dir,
+        "V2__tenant.sql",
+        "INSERT INTO settings VALUES ('tenant', '${tenant}');",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        let config = SchemalaneConfig {
+            migrations_dir,
+            placeholders: [("region".to_owned(), "eu-west".to_owned())].into(),
+            ..Default::default()
+        };
+        let migrator = SchemalaneMigrator::new(config.clone());
+
+        let report = migrator.validate(&db).await?;
+        let issues: Vec<_> = report
+            .issues
+            .iter()
+            .map(|issue| (issue.kind, issue.script.as_deref()))
+            .collect();
+        assert_eq!(
+            issues,
+            [(
+                ValidationIssueKind::UnresolvedPlaceholder,
+                Some("V2__tenant.sql")
+            )]
+        );
+
+        let err = migrator
+            .up(&db)
+            .await
+            .expect_err("V2 has an unresolved placeholder");
+        assert!(
+            err.to_string()
+                .contains("unresolved placeholders: V2__tenant.sql (${tenant})"),
+            "unexpected error: {err}"
+        );
+        assert!(!table_exists(&db, "settings").await?);
+
+        let mut placeholders = config.placeholders.clone();
+        placeholders.insert("tenant".to_owned(), "acme".to_owned());
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            placeholders,
+            ..config
+        });
+        let before = migrator.status(&db).await?;
+        migrator.up(&db).await?;
+
+        let substituted = scalar_i64(
+            &db,
+            "SELECT COUNT(*) AS count FROM settings WHERE (key, value) IN (('region', 'eu-west'), ('tenant', 'acme'), ('owner', current_user))",
+        )
+        .await?;
+        assert_eq!(substituted, 3);
+        let stamped = scalar_i64(
+            &db,
+            "SELECT COUNT(*) AS count FROM settings WHERE key = 'stamp' AND value ~ '^\\d{4}-\\d{2}-\\d{2} \\d{2}:\\d{2}:\\d{2}$'",
+        )
+        .await?;
+        assert_eq!(stamped, 1);
+
+        // Checksums cover the file as written, so other values do not drift.
+        let after = migrator.status(&db).await?;
+        let checksums = |report: &schemalane_core::StatusReport| -> Vec<_> {
+            report.migrations.iter().map(|m| m.checksum).collect()
+        };
+        assert_eq!(checksums(&before), checksums(&after));
+        assert_eq!(after.summary.checksum_mismatch, 0);
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires Docker daemon"]
+fn callbacks_run_around_migrations_and_report_failures() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_cake.sql",
+        "CREATE TABLE cake (id SERIAL PRIMARY KEY);\nCREATE TABLE seen_settings AS SELECT current_setting('lock_timeout') AS lock_timeout;",
+    )?;
+    write_migration(
+        &migrations_dir,
+        "beforeMigrate.sql",
+        "SET lock_timeout = '1234ms';\nCREATE TABLE IF NOT EXISTS audit (event TEXT NOT NULL, script TEXT);",
+    )?;
+    write_migration(
+        &migrations_dir,
+        "afterEachMigrate.sql",
+        "INSERT INTO audit (event) VALUES ('afterEachMigrate');",
+    )?;
+    write_migration(
+        &migrations_dir,
+        "afterMigrate__done.sql",
+        "INSERT INTO audit (event) VALUES ('afterMigrate');",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        let config = SchemalaneConfig {
+            migrations_dir: migrations_dir.clone(),
+            ..Default::default()
+        };
+        let migrator = SchemalaneMigrator::new(config.clone())
+            .with_callback(
+                CallbackEvent::BeforeEachMigrate,
+                "record_before_each",
+                audit_callback(CallbackEvent::BeforeEachMigrate),
+            )
+            .with_callback(
+                CallbackEvent::AfterMigrateError,
+                "record_error",
+                audit_callback(CallbackEvent::AfterMigrateError),
+            );
+
+        migrator.up(&db).await?;
+        let audited = scalar_i64(
+            &db,
+            "SELECT COUNT(*) AS count FROM audit WHERE (event, COALESCE(script, '-')) IN (('beforeEachMigrate', 'V1__create_cake.sql'), ('afterEachMigrate', '-'), ('afterMigrate', '-'))",
+        )
+        .await?;
+        assert_eq!(audited, 3);
+        // beforeMigrate shares its session with the migrations.
+        let seen = "SELECT COUNT(*) AS count FROM seen_settings WHERE lock_timeout = '1234ms'";
+        assert_eq!(scalar_i64(&db, seen).await?, 1);
+
+        write_migration(
+            &migrations_dir,
+            "V2__broken.sql",
+            "INSERT INTO cake (missing) VALUES (1);",
+        )?;
+        migrator.up(&db).await.expect_err("V2 fails");
+        let errors = scalar_i64(
+            &db,
+            "SELECT COUNT(*) AS count FROM audit WHERE event = 'afterMigrateError' AND script = 'V2__broken.sql'",
+        )
+        .await?;
+        assert_eq!(errors, 1);
+        // The afterEachMigrate callback rolled back with the failed migration.
+        let after_each = scalar_i64(
+            &db,
+            "SELECT COUNT(*) AS count FROM audit WHERE event = 'afterEachMigrate'",
+        )
+        .await?;
+        assert_eq!(after_each, 1);
+
+        migrator.repair(&db, false).await?;
+        let failing = SchemalaneMigrator::new(config).with_callback(
+            CallbackEvent::AfterMigrateError,
+            "page_on_call",
+            RustCallback::new(|_, _| {
+                Box::pin(async { Err(DbErr::Custom("pager unreachable".to_owned())) })
+            }),
+        );
+        let err = failing.up(&db).await.expect_err("V2 still fails");
+        assert!(
+            matches!(
+                err,
+                SchemalaneError::Callback {
+                    event: CallbackEvent::AfterMigrateError,
+                    ref callback,
+                    handling: Some(_),
+                    ..
+                } if callback == "page_on_call"
+            ),
+            "unexpected error: {err}"
+        );
+        assert!(
+            err.to_string()
+                .contains("afterMigrateError callback page_on_call failed: Custom Error: pager unreachable (while handling: Migration execution failed for V2__broken.sql"),
+            "unexpected error: {err}"
+        );
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires Docker daemon"]
+fn migration_timeouts_cancel_blocked_and_slow_migrations() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_cake.sql",
+        "CREATE TABLE cake (id SERIAL PRIMARY KEY);",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        let config = SchemalaneConfig {
+            migrations_dir: migrations_dir.clone(),
+            migration_timeouts: MigrationTimeouts {
+                lock_timeout: Some(Duration::from_secs(30)),
+                statement_timeout: Some(Duration::from_millis(200)),
+            },
+            ..Default::default()
+        };
+        let migrator = SchemalaneMigrator::new(config.clone());
+        migrator.up(&db).await?;
+
+        // The header's lock_timeout wins over the 30s default.
+        write_migration(
+            &migrations_dir,
+            "V2__add_price.sql",
+            "-- schemalane:lock-timeout=100ms\nALTER TABLE cake ADD COLUMN price INT;",
+        )?;
+        let blocker = db.begin().await?;
+        blocker
+            .execute_unprepared("LOCK TABLE cake IN ACCESS EXCLUSIVE MODE")
+            .await?;
+        let err = migrator.up(&db).await.expect_err("V2 cannot get its lock");
+        blocker.rollback().await?;
+        assert!(
+            matches!(
+                err,
+                SchemalaneError::MigrationTimeout {
+                    ref script,
+                    timeout: MigrationTimeoutKind::LockTimeout,
+                    ..
+                } if script == "V2__add_price.sql"
+            ),
+            "unexpected error: {err}"
+        );
+        assert_eq!(err.exit_code(), 8);
+        migrator.repair(&db, false).await?;
+        migrator.up(&db).await?;
+
+        // The default statement_timeout applies to Rust migrations too.
+        write_rust_migration(&migrations_dir, "V3__slow_backfill.rs")?;
+        let err = SchemalaneMigrator::new(config)
+            .with_rust_migration(
+                "V3__slow_backfill.rs",
+                RustMigrationExecutor::transactional(|manager| {
+                    Box::pin(async move {
+                        manager
+                            .get_connection()
+                            .execute_unprepared("SELECT pg_sleep(2)")
+                            .await?;
+                        Ok(())
+                    })
+                }),
+            )
+            .up(&db)
+            .await
+            .expect_err("V3 outlives its statement_timeout");
+        assert!(
+            matches!(
+                err,
+                SchemalaneError::MigrationTimeout {
+                    timeout: MigrationTimeoutKind::StatementTimeout,
+                    ..
+                }
+            ),
+            "unexpected error: {err}"
+        );
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires Docker daemon"]
+fn lock_failures_are_retried_before_being_recorded() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_cake.sql",
+        "CREATE TABLE cake (id SERIAL PRIMARY KEY);",
+    )?;
+
+    let failed_rows =
+        "SELECT COUNT(*) AS count FROM public.flyway_schema_history WHERE NOT success";
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        let config = SchemalaneConfig {
+            migrations_dir: migrations_dir.clone(),
+            migration_timeouts: MigrationTimeouts {
+                lock_timeout: Some(Duration::from_millis(100)),
+                statement_timeout: None,
+            },
+            retry: RetryPolicy {
+                max_retries: 10,
+                initial_backoff: Duration::from_millis(100),
+                max_backoff: Duration::from_millis(200),
+            },
+            ..Default::default()
+        };
+        SchemalaneMigrator::new(config.clone()).up(&db).await?;
+
+        // The lock is released while V2 is backing off, so a retry succeeds.
+        write_migration(
+            &migrations_dir,
+            "V2__add_price.sql",
+            "ALTER TABLE cake ADD COLUMN price INT;",
+        )?;
+        let blocker = db.begin().await?;
+        blocker
+            .execute_unprepared("LOCK TABLE cake IN ACCESS EXCLUSIVE MODE")
+            .await?;
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            blocker.rollback().await
+        });
+        let report = SchemalaneMigrator::new(config.clone()).up(&db).await?;
+        release.await??;
+        assert_eq!(report.applied.len(), 1);
+        assert!(!report.retries.is_empty());
+        assert!(
+            report
+                .retries
+                .iter()
+                .all(|retry| retry.script == "V2__add_price.sql" && retry.sqlstate == "55P03")
+        );
+        assert_eq!(scalar_i64(&db, failed_rows).await?, 0);
+
+        // Once the retries run out only the last failure is recorded.
+        write_migration(
+            &migrations_dir,
+            "V3__add_size.sql",
+            "ALTER TABLE cake ADD COLUMN size INT;",
+        )?;
+        let blocker = db.begin().await?;
+        blocker
+            .execute_unprepared("LOCK TABLE cake IN ACCESS EXCLUSIVE MODE")
+            .await?;
+        let err = SchemalaneMigrator::new(SchemalaneConfig {
+            retry: RetryPolicy {
+                max_retries: 2,
+                ..config.retry
+            },
+            ..config
+        })
+        .up(&db)
+        .await
+        .expect_err("V3 never gets its lock");
+        blocker.rollback().await?;
+        assert_eq!(err.retryable_sqlstate(), Some("55P03"));
+        assert!(
+            err.to_string().ends_with("(after 3 attempts)"),
+            "unexpected error: {err}"
+        );
+        assert_eq!(scalar_i64(&db, failed_rows).await?, 1);
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires Docker daemon"]
+fn failed_history_insert_rolls_back_transactional_migration() -> Result<(), Box<dyn Error + 'static>>
+{
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_cake.sql",
+        "CREATE TABLE cake (id SERIAL PRIMARY KEY, name TEXT NOT NULL);",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            migrations_dir: migrations_dir.clone(),
+            ..Default::default()
+        })
+        .with_rust_migration(
+            "V3__seed_cake.rs",
+            RustMigrationExecutor::transactional(|manager| {
+                Box::pin(async move {
+                    manager
+                        .get_connection()
+                        .execute_unprepared("INSERT INTO cake(name) VALUES ('vanilla')")
+                        .await?;
+                    Ok(())
+                })
+            }),
+        );
+        migrator.up(&db).await?;
+
+        // Stand-in for a crash after the migration ran but before its success
+        // row was written.
+        db.execute_unprepared(
+            r"
+CREATE FUNCTION reject_success_rows() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'history write interrupted';
+END;
+$$ LANGUAGE plpgsql;
+CREATE TRIGGER reject_success_rows BEFORE INSERT ON public.flyway_schema_history
+    FOR EACH ROW WHEN (NEW.success) EXECUTE FUNCTION reject_success_rows();
+",
+        )
+        .await?;
+
+        write_migration(
+            &migrations_dir,
+            "V2__create_price.sql",
+            "CREATE TABLE price (id SERIAL PRIMARY KEY);",
+        )?;
+        fs::write(
+            migrations_dir.join("V3__seed_cake.rs"),
+            "// registered above",
+        )?;
+        migrator
+            .up(&db)
+            .await
+            .expect_err("history insert should fail");
+
+        let price_table = scalar_i64(
+            &db,
+            "SELECT COUNT(*) AS count FROM pg_catalog.pg_tables WHERE tablename = 'price'",
+        )
+        .await?;
+        assert_eq!(price_table, 0);
+        let status = migrator.status(&db).await?;
+        assert_eq!(status.summary.success, 1);
+        assert_eq!(status.summary.pending, 2);
+        assert_eq!(status.summary.failed, 0);
+
+        db.execute_unprepared("DROP TRIGGER reject_success_rows ON public.flyway_schema_history")
+            .await?;
+        let report = migrator.up(&db).await?;
+        assert_eq!(report.applied.len(), 2);
+        let cake_count = scalar_i64(&db, "SELECT COUNT(*) AS count FROM public.cake").await?;
+        assert_eq!(cake_count, 1);
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires Docker daemon"]
+fn grouped_up_rolls_back_every_migration_on_failure() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_cake.sql",
+        "CREATE TABLE cake (id SERIAL PRIMARY KEY, name TEXT NOT NULL);",
+    )?;
+    write_migration(
+        &migrations_dir,
+        "V2__seed_cake.sql",
+        "INSERT INTO cake(name) VALUES ('vanilla');",
+    )?;
+    write_migration(
+        &migrations_dir,
+        "V3__broken.sql",
+        "INSERT INTO missing_table VALUES (1);",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            migrations_dir: migrations_dir.clone(),
+            group_transactions: true,
+            ..Default::default()
+        });
+
+        let err = migrator
+            .up(&db)
+            .await
+            .expect_err("V3 should fail the whole group");
+        assert!(matches!(
+            err,
+            SchemalaneError::MigrationExecution { ref script, .. } if script == "V3__broken.sql"
+        ));
+
+        let cake_table = scalar_i64(
+            &db,
+            "SELECT COUNT(*) AS count FROM pg_catalog.pg_tables WHERE tablename = 'cake'",
+        )
+        .await?;
+        assert_eq!(cake_table, 0);
+
+        let status = migrator.status(&db).await?;
+        assert_eq!(status.summary.success, 0);
+        assert_eq!(status.summary.failed, 1);
+        assert_eq!(status.summary.pending, 2);
+
+        write_migration(
+            &migrations_dir,
+            "V3__broken.sql",
+            "-- schemalane:no-transaction\nSELECT 1;",
+        )?;
+        migrator.repair(&db, false).await?;
+        let err = migrator
+            .up(&db)
+            .await
+            .expect_err("no-transaction migrations cannot be grouped");
+        assert_eq!(err.exit_code(), 2);
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires Docker daemon"]
+fn sql_failures_report_the_failing_statement() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_cake.sql",
+        "\
+CREATE TABLE cake (id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY, name TEXT);
+COPY cake (id, name) FROM stdin;
+1\tvanilla;chocolate
+2\t\\N
+\\.
+CREATE FUNCTION cake_count() RETURNS BIGINT AS $$
+    SELECT count(*) FROM cake;
+$$ LANGUAGE sql;
+",
+    )?;
+    write_migration(
+        &migrations_dir,
+        "V2__add_price.sql",
+        "\
+ALTER TABLE cake ADD COLUMN price NUMERIC;
+-- the next statement fails
+INSERT INTO price (cake_id)
+    VALUES (1);
+",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            migrations_dir,
+            ..Default::default()
+        });
+
+        let err = migrator.up(&db).await.expect_err("V2 should fail");
+        let SchemalaneError::MigrationExecution {
+            script,
+            statement,
+            database_error,
+            ..
+        } = &err
+        else {
+            unreachable!("expected MigrationExecution, got: {err}");
+        };
+        assert_eq!(script, "V2__add_price.sql");
+        assert_eq!(
+            statement.as_deref().map(ToString::to_string).as_deref(),
+            Some("line 3, column 1 to line 4, column 14")
+        );
+        assert_eq!(
+            database_error.as_ref().map(|details| details.code.as_str()),
+            Some("42P01")
+        );
+
+        let cakes = scalar_i64(&db, "SELECT cake_count() AS count").await?;
+        assert_eq!(cakes, 2);
+        let price_column = scalar_i64(
+            &db,
+            "SELECT COUNT(*) AS count FROM information_schema.columns WHERE table_name = 'cake' AND column_name = 'price'",
+        )
+        .await?;
+        assert_eq!(price_column, 0);
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires Docker daemon"]
+fn repeatable_migrations_rerun_when_changed() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "R__cake_names.sql",
+        "CREATE OR REPLACE VIEW cake_names AS SELECT name FROM cake;",
+    )?;
+    write_migration(
+        &migrations_dir,
+        "V1__create_cake.sql",
+        "CREATE TABLE cake (id SERIAL PRIMARY KEY, name TEXT NOT NULL);",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            migrations_dir: migrations_dir.clone(),
+            ..Default::default()
+        });
+
+        let report = migrator.up(&db).await?;
+        let scripts: Vec<_> = report.applied.iter().map(|m| m.script.as_str()).collect();
+        assert_eq!(scripts, ["V1__create_cake.sql", "R__cake_names.sql"]);
+        assert_eq!(report.applied[1].version, None);
+
+        let report = migrator.up(&db).await?;
+        assert!(report.applied.is_empty());
+
+        write_migration(
+            &migrations_dir,
+            "R__cake_names.sql",
+            "CREATE OR REPLACE VIEW cake_names AS SELECT name, id FROM cake;",
+        )?;
+        let status = migrator.status(&db).await?;
+        assert_eq!(status.summary.outdated, 1);
+        assert_eq!(status.summary.checksum_mismatch, 0);
+
+        let report = migrator.up(&db).await?;
+        assert_eq!(report.applied.len(), 1);
+        assert_eq!(report.applied[0].script, "R__cake_names.sql");
+
+        let status = migrator.status(&db).await?;
+        assert_eq!(status.summary.success, 2);
+        assert_eq!(status.summary.superseded, 1);
+
+        let null_versions = scalar_i64(
+            &db,
+            "SELECT COUNT(*) AS count FROM public.flyway_schema_history WHERE version IS NULL",
+        )
+        .await?;
+        assert_eq!(null_versions, 2);
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires Docker daemon"]
+fn dry_run_plans_without_executing() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_cake.sql",
+        "CREATE TABLE cake (id SERIAL PRIMARY KEY);",
+    )?;
+    write_migration(
+        &migrations_dir,
+        "V2__create_price_histories.sql",
+        "CREATE TABLE price_histories (id SERIAL PRIMARY KEY);",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            migrations_dir,
+            ..Default::default()
+        });
+
+        let plan = migrator.dry_run_up(&db, &MigrationTarget::Latest).await?;
+        let steps: Vec<_> = plan
+            .steps
+            .iter()
+            .map(|step| (step.installed_rank, step.script.as_str()))
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                (1, "V1__create_cake.sql"),
+                (2, "V2__create_price_histories.sql")
+            ]
+        );
+        assert_eq!(
+            plan.steps[0].sql.as_deref(),
+            Some("CREATE TABLE cake (id SERIAL PRIMARY KEY);")
+        );
+        assert!(!table_exists(&db, "flyway_schema_history").await?);
+        assert!(!table_exists(&db, "cake").await?);
+
+        migrator.up_to(&db, &MigrationTarget::Steps(1)).await?;
+        let plan = migrator.dry_run_up(&db, &MigrationTarget::Latest).await?;
+        assert_eq!(plan.skipped, 1);
+        assert_eq!(plan.steps.len(), 1);
+        assert_eq!(plan.steps[0].installed_rank, 2);
+
+        let plan = migrator.dry_run_fresh(&db).await?;
+        let dropped: Vec<_> = plan
+            .dropped_objects
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            dropped,
+            [
+                "table public.cake",
+                "table public.flyway_schema_history",
+                "table public.flyway_schema_history_schemalane",
+            ]
+        );
+        assert_eq!(plan.steps.len(), 2);
+        assert!(table_exists(&db, "cake").await?);
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires Docker daemon"]
+fn validate_reports_every_problem_without_writing() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    write_migration(
+        &migrations_dir,
+        "V1__create_cake.sql",
+        "CREATE TABLE cake ();",
+    )?;
+    write_migration(
+        &migrations_dir,
+        "V2__create_price.sql",
+        "CREATE TABLE price ();",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let db = Database::connect(&db_url).await?;
+        let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+            migrations_dir: migrations_dir.clone(),
+            ..Default::default()
+        });
+
+        let report = migrator.validate(&db).await?;
+        assert!(report.is_valid());
+        assert!(!table_exists(&db, "flyway_schema_history").await?);
+
+        migrator.up(&db).await?;
+        write_migration(
+            &migrations_dir,
+            "V1__create_cake.sql",
+            "CREATE TABLE cakes ();",
+        )?;
+        fs::remove_file(migrations_dir.join("V2__create_price.sql"))?;
+        write_rust_migration(&migrations_dir, "V3__seed_cake.rs")?;
+
+        let report = migrator.validate(&db).await?;
+        let kinds: Vec<_> = report.issues.iter().map(|issue| issue.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ValidationIssueKind::MissingRustExecutor,
+                ValidationIssueKind::ChecksumMismatch,
+                ValidationIssueKind::Missing,
+            ]
+        );
+        let err = ensure_valid(&report).expect_err("invalid migrations should fail");
+        assert_eq!(err.exit_code(), 2);
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires Docker daemon"]
+fn lock_timeout_reports_the_holder() -> Result<(), Box<dyn Error + 'static>> {
+    let node = Postgres::default().start()?;
+    let db_url = connection_string(&node)?;
+
+    let temp = TempDir::new()?;
+    let migrations_dir = temp.path().join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+    write_migration(
+        &migrations_dir,
+        "V1__create_cake.sql",
+        "CREATE TABLE cake ();",
+    )?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async move {
+        let holder = Database::connect(&db_url).await?;
+        let config = SchemalaneConfig {
+            migrations_dir,
+            advisory_lock: AdvisoryLock::Fixed(4242),
+            ..Default::default()
+        };
+        // One statement, so the lock and the pid come from the same session.
+        let holder_pid = scalar_i64(
+            &holder,
+            "SELECT pg_backend_pid()::bigint AS count FROM (SELECT pg_advisory_lock(4242)) AS locked",
+        )
+        .await?;
+
+        let db = Database::connect(&db_url).await?;
+        for lock_timeout in [Duration::ZERO, Duration::from_millis(200)] {
+            let migrator = SchemalaneMigrator::new(SchemalaneConfig {
+                lock_timeout: Some(lock_timeout),
+                ..config.clone()
+            });
+            let err = migrator
+                .up(&db)
+                .await
+                .expect_err("lock held by another session");
+            let SchemalaneError::LockTimeout { holders, .. } = &err else {
+                return Err(format!("expected LockTimeout, got: {err}").into());
+            };
+            assert_eq!(err.exit_code(), 7);
+            assert_eq!(
+                holders.iter().map(|h| i64::from(h.pid)).collect::<Vec<_>>(),
+                [holder_pid]
+            );
+        }
+
+        // Closing the holder's sessions releases the lock.
+        holder.close().await?;
+        let report = SchemalaneMigrator::new(config).up(&db).await?;
+        assert_eq!(report.applied.len(), 1);
+
+        Ok::<(), Box<dyn Error + 'static>>(())
+    })?;
+
+    Ok(())
+}
+
+fn connection_string(
+    node: &testcontainers_modules::testcontainers::core::Container<Postgres>,
+) -> Result<String, Box<dyn Error + 'static>> {
+    let host = node.get_host()?;
+    let port = node.get_host_port_ipv4(5432)?;
+    Ok(format!(
+        "postgres://postgres:postgres@{host}:{port}/postgres"
+    ))
+}
+
+fn write_migration(
+    migrations_dir: &Path,
+    file_name: &str,
+    sql: &str,
+) -> Result<(), std::io::Error> {
+    let path = migrations_dir.join(file_name);
+    fs::write(path, sql)
+}
+
+fn write_rust_migration(migrations_dir: &Path, file_name: &str) -> Result<(), std::io::Error> {
+    let path = migrations_dir.join(file_name);
+    fs::write(
+        path,
+        r#"
+use sea_orm::DbErr;
+use sea_orm_migration::SchemaManager;
+
+pub async fn migration(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    let _ = manager;
+    Ok(())
+}
+"#,
+    )
+}
+
+/// Rust callback recording its event and migration in the `audit` table.
+fn audit_callback(event: CallbackEvent) -> RustCallback {
+    RustCallback::new(move |manager, context| {
+        let sql = format!(
+            "INSERT INTO audit VALUES ('{event}', '{}')",
+            context.script.as_deref().unwrap_or("-")
+        );
+        Box::pin(async move {
+            manager.get_connection().execute_unprepared(&sql).await?;
+            Ok(())
+        })
+    })
 }
 
 async fn scalar_i64(db: &sea_orm::DatabaseConnection, sql: &str) -> Result<i64, sea_orm::DbErr> {
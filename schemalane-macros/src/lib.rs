@@ -1,3 +1,4 @@
+use crc32fast::Hasher;
 use proc_macro::TokenStream;
 use proc_macro2::Span;
 use quote::{format_ident, quote};
@@ -10,67 +11,77 @@ pub fn embed_migrations(input: TokenStream) -> TokenStream {
     let relative_path = parse_macro_input!(input as LitStr);
     let relative_value = relative_path.value();
 
-    let manifest_dir = match std::env::var("CARGO_MANIFEST_DIR") {
-        Ok(value) => value,
-        Err(err) => {
-            return compile_error(format!(
-                "embed_migrations! could not resolve CARGO_MANIFEST_DIR: {err}"
-            ));
-        }
-    };
-
-    let manifest_dir = PathBuf::from(manifest_dir);
-    let full_path = manifest_dir.join(&relative_value);
-    if !full_path.exists() {
-        return compile_error(format!(
-            "embed_migrations! path does not exist: {}",
-            full_path.display()
-        ));
-    }
-    if !full_path.is_dir() {
-        return compile_error(format!(
-            "embed_migrations! path is not a directory: {}",
-            full_path.display()
-        ));
-    }
-
-    let canonical_path = match full_path.canonicalize() {
+    let canonical_path = match resolve_migrations_dir(&relative_value) {
         Ok(path) => path,
-        Err(err) => {
-            return compile_error(format!(
-                "embed_migrations! failed to canonicalize {}: {err}",
-                full_path.display()
-            ));
-        }
+        Err(err) => return compile_error(err),
     };
 
-    let migrations = match discover_rust_migrations(&canonical_path) {
+    let migrations = match discover_migrations(&canonical_path) {
         Ok(migrations) => migrations,
         Err(err) => return compile_error(err),
     };
 
     let mut module_tokens = Vec::new();
     let mut registration_tokens = Vec::new();
+    let mut embedded_tokens = Vec::new();
     let mut used_idents = HashSet::new();
 
     for migration in migrations {
-        let module_ident = unique_module_ident(&migration.script, &mut used_idents);
         let path_lit = lit_str_from_path(&migration.path);
         let script_lit = LitStr::new(&migration.script, Span::call_site());
-
-        module_tokens.push(quote! {
-            #[path = #path_lit]
-            mod #module_ident;
-        });
-
-        registration_tokens.push(quote! {
-            migrator.register_rust_migration(
-                #script_lit,
-                ::schemalane_core::RustMigrationExecutor::new(|manager| {
-                    Box::pin(#module_ident::migration(manager))
-                }),
-            );
-        });
+        let crc32_checksum = migration.crc32_checksum;
+        let flyway_checksum = migration.flyway_checksum;
+
+        match migration.kind {
+            MigrationFileKind::Sql => {
+                let with_config = migration.config_path.as_deref().map(|config_path| {
+                    let config_lit = lit_str_from_path(config_path);
+                    quote! { .with_config(include_str!(#config_lit)) }
+                });
+                embedded_tokens.push(quote! {
+                    ::schemalane_core::EmbeddedMigration::sql(
+                        #script_lit,
+                        include_str!(#path_lit),
+                        #crc32_checksum,
+                        #flyway_checksum,
+                    )
+                    #with_config
+                });
+            }
+            MigrationFileKind::Callback => {
+                embedded_tokens.push(quote! {
+                    ::schemalane_core::EmbeddedMigration::callback(
+                        #script_lit,
+                        include_str!(#path_lit),
+                    )
+                });
+            }
+            MigrationFileKind::Rust => {
+                let module_ident = unique_module_ident(&migration.script, &mut used_idents);
+
+                module_tokens.push(quote! {
+                    #[path = #path_lit]
+                    mod #module_ident;
+                });
+
+                registration_tokens.push(quote! {
+                    migrator.register_rust_migration(
+                        #script_lit,
+                        ::schemalane_core::RustMigrationExecutor::new(|manager| {
+                            Box::pin(#module_ident::migration(manager))
+                        }),
+                    );
+                });
+
+                embedded_tokens.push(quote! {
+                    ::schemalane_core::EmbeddedMigration::rust(
+                        #script_lit,
+                        #crc32_checksum,
+                        #flyway_checksum,
+                    )
+                });
+            }
+        }
     }
 
     let migrations_dir_lit = lit_str_from_path(&canonical_path);
@@ -81,10 +92,15 @@ pub fn embed_migrations(input: TokenStream) -> TokenStream {
 
             pub const MIGRATIONS_DIR: &str = #migrations_dir_lit;
 
+            pub const EMBEDDED_MIGRATIONS: &[::schemalane_core::EmbeddedMigration] = &[
+                #(#embedded_tokens),*
+            ];
+
             pub fn build_migrator(
                 config: ::schemalane_core::SchemalaneConfig,
             ) -> ::schemalane_core::SchemalaneMigrator {
-                let mut migrator = ::schemalane_core::SchemalaneMigrator::new(config);
+                let mut migrator = ::schemalane_core::SchemalaneMigrator::new(config)
+                    .with_embedded_migrations(EMBEDDED_MIGRATIONS);
                 #(#registration_tokens)*
                 migrator
             }
@@ -97,13 +113,86 @@ pub fn embed_migrations(input: TokenStream) -> TokenStream {
     .into()
 }
 
-struct RustMigrationFile {
+fn resolve_migrations_dir(relative_value: &str) -> Result<PathBuf, String> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|err| format!("embed_migrations! could not resolve CARGO_MANIFEST_DIR: {err}"))?;
+
+    let full_path = PathBuf::from(manifest_dir).join(relative_value);
+    if !full_path.exists() {
+        return Err(format!(
+            "embed_migrations! path does not exist: {}",
+            full_path.display()
+        ));
+    }
+    if !full_path.is_dir() {
+        return Err(format!(
+            "embed_migrations! path is not a directory: {}",
+            full_path.display()
+        ));
+    }
+
+    full_path.canonicalize().map_err(|err| {
+        format!(
+            "embed_migrations! failed to canonicalize {}: {err}",
+            full_path.display()
+        )
+    })
+}
+
+/// Mirrors `CallbackEvent` in `schemalane-core`.
+const CALLBACK_EVENTS: [&str; 5] = [
+    "beforeMigrate",
+    "beforeEachMigrate",
+    "afterEachMigrate",
+    "afterMigrate",
+    "afterMigrateError",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MigrationFileKind {
+    Sql,
+    Rust,
+    /// A SQL callback script such as `afterMigrate.sql`.
+    Callback,
+}
+
+impl MigrationFileKind {
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Sql => "SQL",
+            Self::Rust => "Rust",
+            Self::Callback => "SQL callback",
+        }
+    }
+
+    const fn extension(self) -> &'static str {
+        match self {
+            Self::Sql | Self::Callback => "sql",
+            Self::Rust => "rs",
+        }
+    }
+}
+
+fn is_callback_script(file_name: &str) -> bool {
+    file_name.strip_suffix(".sql").is_some_and(|stem| {
+        let event = stem.split_once("__").map_or(stem, |(event, _)| event);
+        CALLBACK_EVENTS.contains(&event)
+    })
+}
+
+struct MigrationFile {
     path: PathBuf,
     script: String,
-    version: Vec<u64>,
+    /// `None` for repeatable (`R__`) migrations.
+    version: Option<Vec<u64>>,
+    kind: MigrationFileKind,
+    /// `<script>.conf` sidecar of a SQL migration, if present.
+    config_path: Option<PathBuf>,
+    crc32_checksum: i32,
+    flyway_checksum: i32,
 }
 
-fn discover_rust_migrations(dir: &Path) -> Result<Vec<RustMigrationFile>, String> {
+fn discover_migrations(dir: &Path) -> Result<Vec<MigrationFile>, String> {
     let mut migrations = Vec::new();
 
     let read_dir = std::fs::read_dir(dir).map_err(|err| {
@@ -120,9 +209,11 @@ fn discover_rust_migrations(dir: &Path) -> Result<Vec<RustMigrationFile>, String
         if !path.is_file() {
             continue;
         }
-        if path.extension().and_then(|ext| ext.to_str()) != Some("rs") {
-            continue;
-        }
+        let kind = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("sql") => MigrationFileKind::Sql,
+            Some("rs") => MigrationFileKind::Rust,
+            _ => continue,
+        };
 
         let script = path
             .file_name()
@@ -130,79 +221,120 @@ fn discover_rust_migrations(dir: &Path) -> Result<Vec<RustMigrationFile>, String
             .ok_or_else(|| format!("non-utf8 migration filename: {}", path.display()))?
             .to_owned();
 
-        let version = parse_rust_migration_filename(&script)?;
-        migrations.push(RustMigrationFile {
+        let kind = if kind == MigrationFileKind::Sql && is_callback_script(&script) {
+            MigrationFileKind::Callback
+        } else {
+            kind
+        };
+        let version = if kind == MigrationFileKind::Callback {
+            None
+        } else {
+            parse_migration_filename(&script, kind)?
+        };
+        let content = std::fs::read(&path)
+            .map_err(|err| format!("failed to read migration {}: {err}", path.display()))?;
+        if kind != MigrationFileKind::Rust && std::str::from_utf8(&content).is_err() {
+            let what = if kind == MigrationFileKind::Callback {
+                "SQL callback"
+            } else {
+                "SQL migration"
+            };
+            return Err(format!("{what} {} is not valid UTF-8", path.display()));
+        }
+
+        let config_path = path.with_file_name(format!("{script}.conf"));
+        let config_path =
+            (kind == MigrationFileKind::Sql && config_path.is_file()).then_some(config_path);
+
+        migrations.push(MigrationFile {
             path,
             script,
             version,
+            kind,
+            config_path,
+            crc32_checksum: calculate_checksum(&content),
+            flyway_checksum: calculate_flyway_checksum(&content),
         });
     }
 
     migrations.sort_by(|a, b| {
         a.version
-            .cmp(&b.version)
+            .is_none()
+            .cmp(&b.version.is_none())
+            .then_with(|| a.version.cmp(&b.version))
             .then_with(|| a.script.cmp(&b.script))
     });
     Ok(migrations)
 }
 
-fn parse_rust_migration_filename(file_name: &str) -> Result<Vec<u64>, String> {
-    if !std::path::Path::new(file_name)
-        .extension()
-        .is_some_and(|ext| ext.eq_ignore_ascii_case("rs"))
-    {
+fn parse_migration_filename(
+    file_name: &str,
+    kind: MigrationFileKind,
+) -> Result<Option<Vec<u64>>, String> {
+    let label = kind.label();
+    let extension = kind.extension();
+    let Some(stem) = file_name
+        .strip_suffix(extension)
+        .and_then(|stem| stem.strip_suffix('.'))
+    else {
         return Err(format!(
-            "invalid Rust migration filename '{file_name}': expected .rs extension"
+            "invalid {label} migration filename '{file_name}': expected .{extension} extension"
         ));
-    }
+    };
 
-    let stem = &file_name[..file_name.len() - 3];
-    let Some(rest) = stem.strip_prefix('V') else {
-        return Err(format!(
-            "invalid Rust migration filename '{file_name}': expected V<version>__<description>.rs"
-        ));
+    let expected = || {
+        format!(
+            "invalid {label} migration filename '{file_name}': expected V<version>__<description>.{extension} or R__<description>.{extension}"
+        )
     };
-    let Some((version_text, description)) = rest.split_once("__") else {
-        return Err(format!(
-            "invalid Rust migration filename '{file_name}': expected V<version>__<description>.rs"
-        ));
+    let (version_text, description) = if let Some(description) = stem.strip_prefix("R__") {
+        (None, description)
+    } else {
+        let rest = stem.strip_prefix('V').ok_or_else(expected)?;
+        let (version_text, description) = rest.split_once("__").ok_or_else(expected)?;
+        (Some(version_text), description)
     };
 
-    if version_text.is_empty() {
+    if version_text.is_some_and(str::is_empty) {
         return Err(format!(
-            "invalid Rust migration filename '{file_name}': missing version"
+            "invalid {label} migration filename '{file_name}': missing version"
         ));
     }
     if description.is_empty() {
         return Err(format!(
-            "invalid Rust migration filename '{file_name}': missing description"
+            "invalid {label} migration filename '{file_name}': missing description"
         ));
     }
 
+    let version = version_text
+        .map(|version_text| parse_version(file_name, label, version_text))
+        .transpose()?;
+
+    if !description
+        .chars()
+        .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_')
+    {
+        return Err(format!(
+            "invalid {label} migration filename '{file_name}': invalid description '{description}'"
+        ));
+    }
+
+    Ok(version)
+}
+
+fn parse_version(file_name: &str, label: &str, version_text: &str) -> Result<Vec<u64>, String> {
     let mut version = Vec::new();
     for part in version_text.split(['.', '_']) {
         if part.is_empty() || !part.chars().all(|ch| ch.is_ascii_digit()) {
             return Err(format!(
-                "invalid Rust migration filename '{file_name}': invalid version '{version_text}'"
+                "invalid {label} migration filename '{file_name}': invalid version '{version_text}'"
             ));
         }
         let number = part.parse::<u64>().map_err(|err| {
-            format!(
-                "invalid Rust migration filename '{file_name}': version parse error: {err}"
-            )
+            format!("invalid {label} migration filename '{file_name}': version parse error: {err}")
         })?;
         version.push(number);
     }
-
-    if !description
-        .chars()
-        .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_')
-    {
-        return Err(format!(
-            "invalid Rust migration filename '{file_name}': invalid description '{description}'"
-        ));
-    }
-
     Ok(version)
 }
 
@@ -245,6 +377,24 @@ fn sanitize_ident(value: &str) -> String {
     result
 }
 
+/// Mirrors `calculate_checksum` in `schemalane-core` so embedded checksums
+/// match the ones computed from files at runtime.
+fn calculate_checksum(bytes: &[u8]) -> i32 {
+    let mut hasher = Hasher::new();
+    hasher.update(bytes);
+    i32::from_be_bytes(hasher.finalize().to_be_bytes())
+}
+
+/// Mirrors `calculate_flyway_checksum` in `schemalane-core`.
+fn calculate_flyway_checksum(bytes: &[u8]) -> i32 {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let mut hasher = Hasher::new();
+    for line in bytes.split(|byte| matches!(byte, b'\n' | b'\r')) {
+        hasher.update(line);
+    }
+    i32::from_be_bytes(hasher.finalize().to_be_bytes())
+}
+
 fn lit_str_from_path(path: &Path) -> LitStr {
     let as_string = path.to_string_lossy().into_owned();
     LitStr::new(&as_string, Span::call_site())